@@ -0,0 +1,174 @@
+//! Benchmarks of the physics kernels : narrow phase, broad phase, full steps and spawning.
+//!
+//! ```sh
+//! cargo bench --bench physics
+//! cargo bench --bench physics -- broad_phase
+//! ```
+//!
+//! Everything runs on the CPU, no window nor GPU is needed.
+
+use std::{cell::Cell, hint::black_box};
+
+use boucing_ball::{
+    balls::{Balls, one_ball::Ball},
+    bench::{BENCH_SEED, BENCH_WORLD_SIZE},
+    physics::{clock::STEP_DT, geometry::BodyShape},
+    scene::Scene,
+};
+use criterion::{BatchSize, BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+
+///Ball counts of the benchmarks. The world keeps the same size, so the more balls, the denser the
+///world.
+const COUNTS: [usize; 4] = [100, 1_000, 5_000, 20_000];
+
+fn world(count: usize) -> Balls {
+    let (width, height) = BENCH_WORLD_SIZE;
+    Scene::random(width, height, count, BENCH_SEED).build()
+}
+
+///A world after a second of simulation, when the balls started piling up.
+fn settled_world(count: usize) -> Balls {
+    let mut balls = world(count);
+    let events = balls.events();
+    for _ in 0..60 {
+        balls.simulate(STEP_DT, BENCH_WORLD_SIZE);
+    }
+    events.drain();
+
+    balls
+}
+
+fn narrow_phase(c: &mut Criterion) {
+    let mut group = c.benchmark_group("narrow_phase");
+
+    let ball = |pos: [f32; 2], speed: [f32; 2], id| {
+        let mut ball = Ball::new(10., pos, id);
+        ball.speed = speed.into();
+        ball
+    };
+    let crate_at = |pos: [f32; 2], id| {
+        ball(pos, [0., 0.], id).with_shape(BodyShape::Box {
+            half_extents: [10., 10.],
+        })
+    };
+    let pairs = [
+        (
+            "head_on",
+            ball([100., 100.], [50., 0.], 0),
+            ball([115., 100.], [-50., 0.], 1),
+        ),
+        (
+            "resting",
+            ball([100., 100.], [0., 0.], 0),
+            ball([100., 119.], [0., 0.], 1),
+        ),
+        (
+            "apart",
+            ball([100., 100.], [50., 0.], 0),
+            ball([200., 100.], [-50., 0.], 1),
+        ),
+        (
+            "boxes_resting",
+            crate_at([100., 100.], 0),
+            crate_at([103., 119.], 1),
+        ),
+    ];
+    for (name, a, b) in pairs {
+        group.bench_function(name, |bencher| {
+            bencher.iter_batched(
+                || (a, b),
+                |(mut a, mut b)| {
+                    black_box(a.handle_collision_balls(&mut b, STEP_DT));
+                    (a, b)
+                },
+                BatchSize::SmallInput,
+            )
+        });
+    }
+
+    group.finish();
+}
+
+fn broad_phase(c: &mut Criterion) {
+    let mut group = c.benchmark_group("broad_phase");
+
+    for count in COUNTS {
+        let mut balls = settled_world(count);
+        group.throughput(Throughput::Elements(count as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(count), &count, |bencher, _| {
+            bencher.iter(|| {
+                let pairs = Cell::new(0_usize);
+                balls.balls.map_then_map_with_elem_in_range_then_map(
+                    |_: &mut Ball| (),
+                    Balls::broad_phase_range,
+                    |_: &mut Ball, _: &mut Ball| pairs.set(pairs.get() + 1),
+                    |_: &mut Ball| (),
+                );
+                black_box(pairs.get())
+            })
+        });
+    }
+
+    group.finish();
+}
+
+fn full_step(c: &mut Criterion) {
+    let mut group = c.benchmark_group("full_step");
+    group.sample_size(20);
+
+    for count in COUNTS {
+        let balls = settled_world(count);
+        group.throughput(Throughput::Elements(count as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(count), &count, |bencher, _| {
+            bencher.iter_batched_ref(
+                || world_copy(&balls),
+                |balls| balls.simulate(STEP_DT, BENCH_WORLD_SIZE),
+                BatchSize::LargeInput,
+            )
+        });
+    }
+
+    group.finish();
+}
+
+fn spawning(c: &mut Criterion) {
+    let mut group = c.benchmark_group("spawning");
+
+    for count in COUNTS {
+        let (width, height) = BENCH_WORLD_SIZE;
+        let scene = Scene::random(width, height, count, BENCH_SEED);
+        group.throughput(Throughput::Elements(count as u64));
+        group.bench_with_input(
+            BenchmarkId::new("build", count),
+            &scene,
+            |bencher, scene| bencher.iter(|| black_box(scene.build())),
+        );
+        group.bench_with_input(
+            BenchmarkId::new("push_into_full_world", count),
+            &count,
+            |bencher, _| {
+                bencher.iter_batched_ref(
+                    || world(count),
+                    |balls| {
+                        let id = balls.next_id();
+                        balls.push_ball(Ball::new(5., [width / 2., height / 2.], id));
+                    },
+                    BatchSize::LargeInput,
+                )
+            },
+        );
+    }
+
+    group.finish();
+}
+
+///Copy of the balls of `balls`, with the same ids.
+fn world_copy(balls: &Balls) -> Balls {
+    let mut copy = Balls::new(balls.boundary, balls.iter().copied().collect())
+        .expect("the balls come from a world");
+    copy.auto_spawn = false;
+    copy
+}
+
+criterion_group!(benches, narrow_phase, broad_phase, full_step, spawning);
+criterion_main!(benches);
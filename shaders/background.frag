@@ -0,0 +1,9 @@
+#version 150
+
+uniform vec2 resolution;
+
+out vec4 fragColor;
+
+void main() {
+  fragColor = vec4(gl_FragCoord.x / resolution.x, 1.0 - gl_FragCoord.y / resolution.y, 0.5, 1.0);
+}
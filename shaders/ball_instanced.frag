@@ -0,0 +1,38 @@
+#version 150
+
+in vec2 world_pos;
+flat in vec2 v_position;
+flat in float v_radius;
+flat in vec3 v_color;
+flat in vec2 v_last_pos;
+flat in vec2 v_collision_pos;
+
+uniform float camera_zoom;
+
+out vec4 fragColor;
+
+float segment_distance(vec2 p, vec2 a, vec2 b) {
+  vec2 pa = p - a;
+  vec2 ba = b - a;
+  float h = clamp(dot(pa, ba) / max(dot(ba, ba), 1e-6), 0.0, 1.0);
+
+  return length(pa - ba * h);
+}
+
+void main() {
+  float dist = length(world_pos - v_position);
+  // lines are a pixel wide whatever the zoom
+  float pixel = 1. / camera_zoom;
+
+  if (segment_distance(world_pos, v_position, v_collision_pos) < pixel) {
+    fragColor = vec4(1., 1., 1., 1.);
+  } else if (dist <= v_radius * 0.95) {
+    fragColor = vec4(v_color, 1.);
+  } else if (dist <= v_radius) {
+    fragColor = vec4(0., 0., 0., 1.);
+  } else if (segment_distance(world_pos, v_position, v_last_pos) < pixel) {
+    fragColor = vec4(1., 0.2, 0.2, 1.);
+  } else {
+    discard;
+  }
+}
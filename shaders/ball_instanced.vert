@@ -0,0 +1,44 @@
+#version 150
+
+// corner of the quad, in [0, 1]
+in vec2 corner;
+
+// per ball attributes
+in vec2 ball_position;
+in float radius;
+in vec3 color;
+in vec2 velocity;
+in vec2 collision_pos;
+
+uniform vec2 resolution;
+uniform vec2 camera_center;
+uniform float camera_zoom;
+uniform float trail_time;
+
+out vec2 world_pos;
+flat out vec2 v_position;
+flat out float v_radius;
+flat out vec3 v_color;
+flat out vec2 v_last_pos;
+flat out vec2 v_collision_pos;
+
+void main() {
+  vec2 last_pos = ball_position - velocity * trail_time;
+
+  // smallest box holding the ball, its velocity trail and its collision line, plus a pixel
+  float pixel = 1. / camera_zoom;
+  vec2 box_min = min(min(ball_position - radius, collision_pos), last_pos) - pixel;
+  vec2 box_max = max(max(ball_position + radius, collision_pos), last_pos) + pixel;
+  world_pos = mix(box_min, box_max, corner);
+
+  v_position = ball_position;
+  v_radius = radius;
+  v_color = color;
+  v_last_pos = last_pos;
+  v_collision_pos = collision_pos;
+
+  // the physics has y going down
+  vec2 screen = (world_pos - camera_center) * camera_zoom + resolution / 2.;
+  vec2 ndc = screen / resolution * 2. - 1.;
+  gl_Position = vec4(ndc.x, -ndc.y, 0., 1.);
+}
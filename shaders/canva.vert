@@ -1,9 +0,0 @@
-#version 150
-
-uniform float z;
-
-in vec4 position;
-
-void main() {
-    gl_Position = vec4(position.x,position.y,z,1.);
-}
\ No newline at end of file
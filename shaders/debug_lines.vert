@@ -0,0 +1,19 @@
+#version 150
+
+in vec2 position;
+in vec3 color;
+
+uniform vec2 resolution;
+uniform vec2 camera_center;
+uniform float camera_zoom;
+
+out vec3 v_color;
+
+void main() {
+  v_color = color;
+
+  // world to screen, the physics has y going down
+  vec2 screen = (position - camera_center) * camera_zoom + resolution / 2.;
+  vec2 ndc = screen / resolution * 2. - 1.;
+  gl_Position = vec4(ndc.x, -ndc.y, 0., 1.);
+}
@@ -0,0 +1,23 @@
+#version 150
+
+uniform vec2 resolution;
+// sum of the splats : colors weighted by the field in rgb, the field in alpha
+uniform sampler2D field;
+uniform float threshold;
+
+out vec4 fragColor;
+
+const vec3 WATER = vec3(0.1, 0.35, 0.8);
+const vec3 FOAM = vec3(0.85, 0.92, 1.);
+
+void main() {
+  vec4 value = texture(field, gl_FragCoord.xy / resolution);
+  if (value.a < threshold) {
+    discard;
+  }
+
+  // average color of the particles around, tinted like water, with a light border at the surface
+  vec3 color = mix(value.rgb / value.a, WATER, 0.6);
+  float depth = smoothstep(threshold, threshold * 1.5, value.a);
+  fragColor = vec4(mix(FOAM, color, depth), 1.);
+}
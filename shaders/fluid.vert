@@ -0,0 +1,7 @@
+#version 150
+
+in vec2 corner;
+
+void main() {
+  gl_Position = vec4(corner * 2. - 1., 0., 1.);
+}
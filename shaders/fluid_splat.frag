@@ -0,0 +1,17 @@
+#version 150
+
+in vec2 offset;
+flat in vec3 v_color;
+
+out vec4 fragColor;
+
+void main() {
+  float dist_sq = dot(offset, offset);
+  if (dist_sq >= 1.) {
+    discard;
+  }
+
+  // 1 at the center, falling smoothly to 0 at the edge; the splats are added together
+  float weight = (1. - dist_sq) * (1. - dist_sq);
+  fragColor = vec4(v_color * weight, weight);
+}
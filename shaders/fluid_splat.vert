@@ -0,0 +1,29 @@
+#version 150
+
+// corner of the quad, in [0, 1]
+in vec2 corner;
+
+// per ball attributes
+in vec2 ball_position;
+in float radius;
+in vec3 color;
+
+uniform vec2 resolution;
+uniform vec2 camera_center;
+uniform float camera_zoom;
+uniform float splat_scale;
+
+// from the center of the splat, in splat radii
+out vec2 offset;
+flat out vec3 v_color;
+
+void main() {
+  offset = corner * 2. - 1.;
+  v_color = color;
+  vec2 world_pos = ball_position + offset * radius * splat_scale;
+
+  // the physics has y going down
+  vec2 screen = (world_pos - camera_center) * camera_zoom + resolution / 2.;
+  vec2 ndc = screen / resolution * 2. - 1.;
+  gl_Position = vec4(ndc.x, -ndc.y, 0., 1.);
+}
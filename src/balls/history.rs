@@ -0,0 +1,273 @@
+//! Rewind history : ring buffer of the last seconds of a world, one frame per physics step.
+//!
+//! A full snapshot (keyframe) is kept regularly, the frames in between only store the position,
+//! speed and rotation of the balls relative to it, quantized on 16 bits, and the balls spawned
+//! since. A frame whose balls cannot be stored that way (a ball removed or edited, a delta too big)
+//! starts a new keyframe.
+
+use std::collections::{HashMap, VecDeque};
+
+use my_glium_util::math::Vec2;
+
+use super::{Balls, one_ball::Ball, snapshot::WorldSnapshot};
+
+///Duration kept by default, in simulation seconds.
+pub const HISTORY_SECONDS: f32 = 10.;
+///Frames per keyframe at most.
+const KEYFRAME_INTERVAL: usize = 60;
+///Resolution of the quantized positions, in world units.
+const POSITION_STEP: f32 = 1. / 64.;
+///Resolution of the quantized speeds, in world units per second.
+const SPEED_STEP: f32 = 1. / 16.;
+///Resolution of the quantized angles, in radians.
+const ANGLE_STEP: f32 = 1. / 1024.;
+///Resolution of the quantized angular speeds, in radians per second.
+const ANGULAR_SPEED_STEP: f32 = 1. / 256.;
+
+///Position, speed and rotation of a ball relative to the keyframe, in quantization steps.
+#[derive(Clone, Copy)]
+struct BallDelta {
+    position: [i16; 2],
+    speed: [i16; 2],
+    angle: i16,
+    angular_speed: i16,
+    sleeping: bool,
+}
+
+struct DeltaFrame {
+    time: f32,
+    step: u64,
+    last_ball_spawn_time: f32,
+    next_id: usize,
+    ///In the order of the keyframe balls.
+    balls: Vec<BallDelta>,
+    ///Balls spawned since the keyframe, sorted by id.
+    added: Vec<Ball>,
+}
+
+struct Segment {
+    key: WorldSnapshot,
+    ///Index of every ball of the keyframe, by id.
+    index: HashMap<usize, usize>,
+    deltas: Vec<DeltaFrame>,
+}
+
+pub struct History {
+    ///Simulation seconds kept, a bit more is kept to always start on a keyframe.
+    duration: f32,
+    segments: VecDeque<Segment>,
+}
+
+impl Default for History {
+    fn default() -> Self {
+        Self::new(HISTORY_SECONDS)
+    }
+}
+
+impl History {
+    pub fn new(duration: f32) -> Self {
+        Self {
+            duration,
+            segments: VecDeque::new(),
+        }
+    }
+
+    ///Number of frames kept.
+    pub fn len(&self) -> usize {
+        self.segments.iter().map(|s| 1 + s.deltas.len()).sum()
+    }
+
+    ///Number of frames kept as a full snapshot.
+    pub fn keyframes(&self) -> usize {
+        self.segments.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.segments.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.segments.clear();
+    }
+
+    ///Add the current state of `balls` as the newest frame, dropping the frames that are too old.
+    pub fn record(&mut self, balls: &Balls) {
+        let time = balls.time;
+
+        let delta = self
+            .segments
+            .back()
+            .filter(|segment| segment.deltas.len() + 1 < KEYFRAME_INTERVAL)
+            .and_then(|segment| DeltaFrame::new(segment, balls));
+        match (delta, self.segments.back_mut()) {
+            (Some(delta), Some(segment)) => segment.deltas.push(delta),
+            _ => self.segments.push_back(Segment::new(balls.snapshot())),
+        }
+
+        while self
+            .segments
+            .get(1)
+            .is_some_and(|next| time - next.key.time >= self.duration)
+        {
+            self.segments.pop_front();
+        }
+    }
+
+    ///Frame `index`, the oldest being 0.
+    pub fn get(&self, index: usize) -> Option<WorldSnapshot> {
+        let (segment, index) = self.locate(index)?;
+        match index.checked_sub(1) {
+            None => Some(segment.key.clone()),
+            Some(delta) => Some(segment.deltas[delta].apply(&segment.key)),
+        }
+    }
+
+    ///Simulation time of frame `index`.
+    pub fn time(&self, index: usize) -> Option<f32> {
+        let (segment, index) = self.locate(index)?;
+        match index.checked_sub(1) {
+            None => Some(segment.key.time),
+            Some(delta) => Some(segment.deltas[delta].time),
+        }
+    }
+
+    ///Keep only the `len` oldest frames, to resume the simulation from the last one.
+    pub fn truncate(&mut self, len: usize) {
+        let mut kept = 0;
+        self.segments.retain_mut(|segment| {
+            let frames = (1 + segment.deltas.len()).min(len.saturating_sub(kept));
+            kept += frames;
+            segment.deltas.truncate(frames.saturating_sub(1));
+            frames > 0
+        });
+    }
+
+    ///Segment holding frame `index`, and the index of the frame in it (0 being the keyframe).
+    fn locate(&self, mut index: usize) -> Option<(&Segment, usize)> {
+        for segment in &self.segments {
+            let frames = 1 + segment.deltas.len();
+            if index < frames {
+                return Some((segment, index));
+            }
+            index -= frames;
+        }
+
+        None
+    }
+}
+
+impl Segment {
+    fn new(key: WorldSnapshot) -> Self {
+        let index = key
+            .balls
+            .iter()
+            .enumerate()
+            .map(|(i, ball)| (ball.id, i))
+            .collect();
+
+        Self {
+            key,
+            index,
+            deltas: vec![],
+        }
+    }
+}
+
+impl DeltaFrame {
+    ///`None` when the state of `balls` cannot be stored relative to the keyframe of `segment`.
+    fn new(segment: &Segment, balls: &Balls) -> Option<Self> {
+        let key = &segment.key;
+        if key.border != balls.border
+            || key.soft_bodies != balls.soft_bodies
+            || key.fluid != balls.fluid
+        {
+            return None;
+        }
+
+        let mut deltas: Vec<Option<BallDelta>> = vec![None; key.balls.len()];
+        let mut added = vec![];
+        for ball in balls.iter() {
+            match segment.index.get(&ball.id) {
+                Some(&i) => deltas[i] = Some(BallDelta::new(&key.balls[i], ball)?),
+                //a ball removed then added again with its id is not a spawn
+                None if ball.id >= key.next_id => added.push(*ball),
+                None => return None,
+            }
+        }
+        //`None` left for a removed ball
+        let deltas = deltas.into_iter().collect::<Option<Vec<_>>>()?;
+        added.sort_unstable_by_key(|ball| ball.id);
+
+        Some(Self {
+            time: balls.time,
+            step: balls.step,
+            last_ball_spawn_time: balls.last_ball_spawn_time,
+            next_id: balls.next_id,
+            balls: deltas,
+            added,
+        })
+    }
+
+    fn apply(&self, key: &WorldSnapshot) -> WorldSnapshot {
+        let mut snapshot = key.clone();
+        snapshot.time = self.time;
+        snapshot.step = self.step;
+        snapshot.last_ball_spawn_time = self.last_ball_spawn_time;
+        snapshot.next_id = self.next_id;
+        let dequantize =
+            |q: [i16; 2], step: f32| Vec2::from([f32::from(q[0]) * step, f32::from(q[1]) * step]);
+        for (ball, delta) in snapshot.balls.iter_mut().zip(&self.balls) {
+            ball.position += dequantize(delta.position, POSITION_STEP);
+            ball.speed += dequantize(delta.speed, SPEED_STEP);
+            ball.angle += f32::from(delta.angle) * ANGLE_STEP;
+            ball.angular_speed += f32::from(delta.angular_speed) * ANGULAR_SPEED_STEP;
+            ball.coliding_pos = ball.position;
+            ball.sleeping = delta.sleeping;
+        }
+        snapshot.balls.extend_from_slice(&self.added);
+
+        snapshot
+    }
+}
+
+impl BallDelta {
+    ///`None` when `ball` is not `key` moved, or moved too far to be quantized.
+    fn new(key: &Ball, ball: &Ball) -> Option<Self> {
+        if !same_body(key, ball) {
+            return None;
+        }
+        let delta = |value: f32, base: f32, step: f32| quantize(value - base, step);
+
+        Some(Self {
+            position: [
+                delta(ball.position[0], key.position[0], POSITION_STEP)?,
+                delta(ball.position[1], key.position[1], POSITION_STEP)?,
+            ],
+            speed: [
+                delta(ball.speed[0], key.speed[0], SPEED_STEP)?,
+                delta(ball.speed[1], key.speed[1], SPEED_STEP)?,
+            ],
+            angle: delta(ball.angle, key.angle, ANGLE_STEP)?,
+            angular_speed: delta(ball.angular_speed, key.angular_speed, ANGULAR_SPEED_STEP)?,
+            sleeping: ball.sleeping,
+        })
+    }
+}
+
+///Whether only the movement of the ball changed.
+fn same_body(a: &Ball, b: &Ball) -> bool {
+    a.id == b.id
+        && a.size == b.size
+        && a.shape == b.shape
+        && a.mass == b.mass
+        && a.bounce == b.bounce
+        && a.do_physics == b.do_physics
+        && a.fixed_color == b.fixed_color
+        && a.color == b.color
+}
+
+fn quantize(value: f32, step: f32) -> Option<i16> {
+    let steps = (value / step).round();
+    //also false for NaN
+    (steps.abs() <= f32::from(i16::MAX)).then_some(steps as i16)
+}
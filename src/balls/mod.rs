@@ -1,25 +1,107 @@
 #![allow(dead_code)]
 
 use core::f32;
+use std::{
+    cell::{Cell, RefCell},
+    collections::{HashMap, HashSet},
+    fmt, io,
+    path::Path,
+};
 
+pub mod history;
 pub mod one_ball;
+pub mod sensor;
+pub mod snapshot;
+pub mod soft;
+
+#[cfg(test)]
+mod test;
 
-use glium::uniforms::DynamicUniforms;
 use my_glium_util::{
-    canvas::{CanvasData, traits::CanvasDrawable},
     datastruct::{aabb::Aabb, quadtree::Quadtree},
-    math::Vec2,
+    math::{EuclidianSpace, Vec2},
 };
 use one_ball::Ball;
+use sensor::{Sensor, SensorShape};
+use soft::{SoftBody, SoftKind, SoftLayout};
+
+use crate::{
+    physics::{
+        constants::PHYSIC_SUB_STEP,
+        events::{EventQueue, PhysicsEvent},
+        fluid::{FluidParams, FluidParticle},
+        traits::Body,
+    },
+    stats::{FrameStats, StatsLogger, StepStats},
+};
+
+///Number of balls a quadtree node holds before being split.
+pub const QUADTREE_CAPACITY: usize = 10;
+///Gap under which a sleeping ball still rests on what is below it, in world units.
+const SLEEP_CONTACT_SLOP: f32 = 0.5;
+
+///Spring pulling a ball toward a target (the cursor), as an acceleration so every ball feels the
+///same.
+#[derive(Clone, Copy, Debug)]
+pub struct MouseSpring {
+    pub id: usize,
+    pub target: [f32; 2],
+    ///Acceleration per unit of distance to the target, in 1/s².
+    pub stiffness: f32,
+    ///Acceleration per unit of speed, in 1/s.
+    pub damping: f32,
+}
+
+///A ball that could not be put in the quadtree, its position not being a number.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct InsertError {
+    pub id: usize,
+    pub position: [f32; 2],
+}
+
+impl fmt::Display for InsertError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "could not put ball {} at {:?} in the world",
+            self.id, self.position
+        )
+    }
+}
+
+impl std::error::Error for InsertError {}
 
 pub struct Balls {
     pub boundary: Aabb<f32>,
-    pub balls: Quadtree<f32, Ball, 10>,
+    pub balls: Quadtree<f32, Ball, QUADTREE_CAPACITY>,
 
     time: f32,
+    step: u64,
+    ///Border used by the last physics step.
+    border: (f32, f32),
     last_ball_spawn_time: f32,
-
-    pub z: f32,
+    next_id: usize,
+    ///Spawn new balls regularly, like a fountain.
+    pub auto_spawn: bool,
+    ///The spawner stops once there are this many balls.
+    pub max_balls: Option<usize>,
+    ///Physics sub steps per step.
+    substeps: u16,
+
+    last_step_stats: StepStats,
+    stats_logger: Option<StatsLogger>,
+
+    events: EventQueue,
+    ///Pairs of ball ids touching during the last frame, with the impulse exchanged.
+    contacts: HashMap<(usize, usize), f32>,
+
+    sensors: Vec<Sensor>,
+    ///Biggest ball radius, used to widen the sensors broad phase.
+    max_ball_size: f32,
+    mouse_spring: Option<MouseSpring>,
+    soft_bodies: Vec<SoftBody>,
+    ///The circles are particles of this fluid.
+    fluid: Option<FluidParams>,
 }
 
 impl Balls {
@@ -29,85 +111,419 @@ impl Balls {
             balls: Quadtree::empty(boundary),
 
             time: 0.,
+            step: 0,
+            border: (0., 0.),
             last_ball_spawn_time: 0.,
-
-            z: 0.,
+            next_id: 0,
+            auto_spawn: true,
+            max_balls: None,
+            substeps: PHYSIC_SUB_STEP,
+
+            last_step_stats: StepStats::default(),
+            stats_logger: None,
+
+            events: EventQueue::new(),
+            contacts: HashMap::new(),
+
+            sensors: vec![],
+            max_ball_size: 0.,
+            mouse_spring: None,
+            soft_bodies: vec![],
+            fluid: None,
         }
     }
 
-    pub fn new(boundary: Aabb<f32>, balls: Vec<Ball>) -> Self {
+    ///World holding `balls`, the ones outside of `boundary` being clamped back inside. No ball is
+    ///dropped : a ball that still cannot be inserted is an error.
+    pub fn new(boundary: Aabb<f32>, balls: Vec<Ball>) -> Result<Self, InsertError> {
         let mut qtree = Quadtree::empty(boundary);
+        let mut next_id = 0;
+        let mut max_ball_size: f32 = 0.;
+        let min = [
+            boundary.center.x - boundary.half_dim,
+            boundary.center.y - boundary.half_dim,
+        ];
+        let max = [
+            boundary.center.x + boundary.half_dim,
+            boundary.center.y + boundary.half_dim,
+        ];
         for mut b in balls {
-            if qtree.insert(b).is_err() {
-                b.handle_border_colision_ball((
-                    boundary.center.x + boundary.half_dim,
-                    boundary.center.y + boundary.half_dim,
-                ));
-                println!("replacing ball into :{:?}", b.position);
-                let _ = qtree
-                    .insert(b)
-                    // .expect("could not replace the ball inside qtree::new");
-                    .map_err(|e| println!("error : {e:?}, giving up the ball !"));
+            next_id = next_id.max(b.id + 1);
+            max_ball_size = max_ball_size.max(b.size);
+            let error = InsertError {
+                id: b.id,
+                position: *b.position.as_array(),
+            };
+            if !(b.position[0].is_finite() && b.position[1].is_finite()) {
+                return Err(error);
+            }
+            //a ball bigger than the boundary only has its center kept inside
+            let margin = b.size.min(boundary.half_dim);
+            let position = [
+                b.position[0].clamp(min[0] + margin, max[0] - margin),
+                b.position[1].clamp(min[1] + margin, max[1] - margin),
+            ];
+            if position != *b.position.as_array() {
+                b.position = position.into();
+                b.coliding_pos = b.position;
             }
+            qtree.insert(b).map_err(|_| error)?;
         }
 
-        Self {
+        Ok(Self {
             boundary,
             balls: qtree,
 
             time: 0.,
+            step: 0,
+            border: (0., 0.),
             last_ball_spawn_time: 0.,
+            next_id,
+            auto_spawn: true,
+            max_balls: None,
+            substeps: PHYSIC_SUB_STEP,
+
+            last_step_stats: StepStats::default(),
+            stats_logger: None,
+
+            events: EventQueue::new(),
+            contacts: HashMap::new(),
+
+            sensors: vec![],
+            max_ball_size,
+            mouse_spring: None,
+            soft_bodies: vec![],
+            fluid: None,
+        })
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Ball> {
+        self.balls.query_range(self.boundary).into_iter()
+    }
 
-            z: 0.,
+    pub fn substeps(&self) -> u16 {
+        self.substeps
+    }
+
+    ///Whether the spawner reached `max_balls`.
+    fn is_full(&self) -> bool {
+        self.max_balls.is_some_and(|max| self.balls.len() >= max)
+    }
+
+    ///Set the physics sub steps per step, at least 1.
+    pub fn set_substeps(&mut self, substeps: u16) {
+        self.substeps = substeps.max(1);
+    }
+
+    ///Simulation time, in seconds.
+    pub fn time(&self) -> f32 {
+        self.time
+    }
+
+    ///Number of physics steps done since creation.
+    pub fn step(&self) -> u64 {
+        self.step
+    }
+
+    pub fn border(&self) -> (f32, f32) {
+        self.border
+    }
+
+    ///Border of the world for a visible area of `size`, never bigger than the boundary.
+    pub fn border_for(&self, size: (f32, f32)) -> (f32, f32) {
+        (
+            (self.boundary.center.x + self.boundary.half_dim).min(size.0),
+            (self.boundary.center.y + self.boundary.half_dim).min(size.1),
+        )
+    }
+
+    pub fn last_step_stats(&self) -> StepStats {
+        self.last_step_stats
+    }
+
+    pub fn stats(&self) -> FrameStats {
+        FrameStats::measure(self)
+    }
+
+    ///Log the stats of every physics step into a CSV file.
+    pub fn log_stats_to<P: AsRef<Path>>(&mut self, path: P) -> io::Result<()> {
+        self.stats_logger = Some(StatsLogger::create(path)?);
+        Ok(())
+    }
+
+    ///Stop logging the stats, writing what is left of the file.
+    pub fn finish_stats_log(&mut self) -> io::Result<()> {
+        match self.stats_logger.take() {
+            Some(mut logger) => logger.flush(),
+            None => Ok(()),
         }
     }
 
+    ///Pairs of ball ids touching during the last frame, with the impulse exchanged.
+    pub fn contacts(&self) -> impl Iterator<Item = ((usize, usize), f32)> + '_ {
+        self.contacts
+            .iter()
+            .map(|(&pair, &impulse)| (pair, impulse))
+    }
+
+    ///Area in which a ball looks for the balls it may collide with.
+    pub fn broad_phase_range(ball: &Ball) -> Aabb<f32> {
+        ball.bounding_box()
+    }
+
+    ///Handle on the events emitted by the physics step.
+    pub fn events(&self) -> EventQueue {
+        self.events.clone()
+    }
+
+    ///Id to give to the next spawned ball.
+    pub fn next_id(&mut self) -> usize {
+        let id = self.next_id;
+        self.next_id += 1;
+        id
+    }
+
     pub fn push_ball(&mut self, ball: Ball) {
+        self.next_id = self.next_id.max(ball.id + 1);
+        self.max_ball_size = self.max_ball_size.max(ball.size);
+        self.events.push(PhysicsEvent::Spawned { id: ball.id });
         self.balls.insert_fit(ball);
     }
-}
 
-impl CanvasDrawable for Balls {
-    fn set_z(&mut self, z: f32) {
-        self.z = z;
-        self.balls.map_query_range(self.boundary, |b| {
-            b.set_z(z);
-        });
+    ///Remove the ball with the given id, waking every other ball since it may have been holding them.
+    pub fn remove_ball(&mut self, id: usize) -> Option<Ball> {
+        self.remove_balls(&[id]).pop()
     }
 
-    fn canvas_uniforms(&self) -> Vec<DynamicUniforms> {
-        let mut result = Vec::with_capacity(self.balls.len());
-        for ball in self.balls.query_range(self.boundary) {
-            let mut uni = ball.canvas_uniforms();
-            result.append(&mut uni);
+    ///Remove the balls with the given ids with a single rebuild of the quadtree,
+    ///waking every other ball since they may have been holding them.
+    pub fn remove_balls(&mut self, ids: &[usize]) -> Vec<Ball> {
+        let ids: HashSet<usize> = ids.iter().copied().collect();
+        let mut removed = Vec::new();
+        let mut kept = Vec::with_capacity(self.balls.len());
+        for ball in self.balls.iter_mut() {
+            if ids.contains(&ball.id) {
+                removed.push(*ball);
+            } else {
+                kept.push(*ball);
+            }
+        }
+
+        if removed.is_empty() {
+            return removed;
+        }
+        removed.sort_unstable_by_key(|ball| ball.id);
+
+        self.balls = Quadtree::empty(self.boundary);
+        for mut ball in kept {
+            if ball.sleeping {
+                ball.wake_up();
+                self.events.push(PhysicsEvent::Wake { id: ball.id });
+            }
+            self.balls.insert_fit(ball);
+        }
+        for ball in &removed {
+            let id = ball.id;
+            self.contacts.retain(|&(a, b), _| a != id && b != id);
+            self.soft_bodies.retain_mut(|body| body.remove_particle(id));
+            if self.mouse_spring.is_some_and(|spring| spring.id == id) {
+                self.mouse_spring = None;
+            }
+            self.events.push(PhysicsEvent::Removed { id });
         }
 
-        result
+        removed
     }
 
-    fn get_z(&self) -> f32 {
-        self.z
+    pub fn ball(&self, id: usize) -> Option<&Ball> {
+        self.iter().find(|ball| ball.id == id)
     }
 
-    fn is_absolute_coord_in(&self, _coord: (f32, f32)) -> bool {
-        true
+    pub fn ball_mut(&mut self, id: usize) -> Option<&mut Ball> {
+        self.balls.iter_mut().find(|ball| ball.id == id)
+    }
+
+    ///Balls whose id is in `ids` sorted by id, found in a single pass over the world.
+    pub fn balls_with_ids(&self, ids: &HashSet<usize>) -> Vec<&Ball> {
+        let mut found: Vec<&Ball> = self.iter().filter(|ball| ids.contains(&ball.id)).collect();
+        found.sort_unstable_by_key(|ball| ball.id);
+        found
+    }
+
+    ///Change the balls whose id is in `ids` in a single pass, waking them up like `edit_ball`.
+    pub fn edit_balls(&mut self, ids: &HashSet<usize>, mut edit: impl FnMut(&mut Ball)) {
+        let mut max_size = self.max_ball_size;
+        for ball in self.balls.iter_mut().filter(|ball| ids.contains(&ball.id)) {
+            edit(ball);
+            ball.wake_up();
+            max_size = max_size.max(ball.size);
+        }
+        self.max_ball_size = max_size;
     }
 
-    fn is_relative_coord_in(&self, _coord: (f32, f32)) -> bool {
+    ///Change a ball and wake it up so the change is simulated, `false` if there is no such ball.
+    pub fn edit_ball(&mut self, id: usize, edit: impl FnOnce(&mut Ball)) -> bool {
+        let Some(ball) = self.ball_mut(id) else {
+            return false;
+        };
+        edit(ball);
+        ball.wake_up();
+        let size = ball.size;
+        self.max_ball_size = self.max_ball_size.max(size);
+
         true
     }
 
-    fn update(&mut self, canva_info: &CanvasData, dt: f32) {
-        self.time += dt;
+    ///Ids of every ball under `pos`.
+    pub fn balls_at(&self, pos: [f32; 2]) -> Vec<usize> {
+        let range = Aabb::new((pos[0], pos[1]), self.max_ball_size);
+
+        self.balls
+            .query_range(range)
+            .into_iter()
+            .filter(|ball| ball.contains(pos))
+            .map(|ball| ball.id)
+            .collect()
+    }
+
+    ///Id of the ball under `pos`, the closest one if they overlap.
+    pub fn ball_at(&self, pos: [f32; 2]) -> Option<usize> {
+        let range = Aabb::new((pos[0], pos[1]), self.max_ball_size);
+        let dist_sq =
+            |ball: &Ball| (ball.position[0] - pos[0]).powi(2) + (ball.position[1] - pos[1]).powi(2);
+
+        self.balls
+            .query_range(range)
+            .into_iter()
+            .filter(|ball| ball.contains(pos))
+            .min_by(|a, b| dist_sq(a).total_cmp(&dist_sq(b)))
+            .map(|ball| ball.id)
+    }
 
-        let border: (f32, f32) = (
-            (self.boundary.center.x + self.boundary.half_dim)
-                .min(canva_info.size.0 * canva_info.window_resolution.0 as f32),
-            (self.boundary.center.y + self.boundary.half_dim)
-                .min(canva_info.size.1 * canva_info.window_resolution.1 as f32),
+    ///Ids of the balls whose center is in the rectangle between `a` and `b`.
+    pub fn balls_in(&self, a: [f32; 2], b: [f32; 2]) -> Vec<usize> {
+        let (min, max) = (
+            [a[0].min(b[0]), a[1].min(b[1])],
+            [a[0].max(b[0]), a[1].max(b[1])],
+        );
+        let range = Aabb::new(
+            ((min[0] + max[0]) / 2., (min[1] + max[1]) / 2.),
+            (max[0] - min[0]).max(max[1] - min[1]) / 2.,
         );
 
-        if self.time - self.last_ball_spawn_time > 0.05 {
+        self.balls
+            .query_range(range)
+            .into_iter()
+            .filter(|ball| {
+                (min[0]..=max[0]).contains(&ball.position[0])
+                    && (min[1]..=max[1]).contains(&ball.position[1])
+            })
+            .map(|ball| ball.id)
+            .collect()
+    }
+
+    pub fn mouse_spring(&self) -> Option<MouseSpring> {
+        self.mouse_spring
+    }
+
+    ///Pull a ball with a spring during the next steps, `None` to let it go.
+    pub fn set_mouse_spring(&mut self, spring: Option<MouseSpring>) {
+        self.mouse_spring = spring;
+    }
+
+    pub fn fluid(&self) -> Option<FluidParams> {
+        self.fluid
+    }
+
+    ///Simulate the circles as particles of a fluid, `None` to go back to rigid balls.
+    pub fn set_fluid(&mut self, fluid: Option<FluidParams>) {
+        self.fluid = fluid;
+    }
+
+    pub fn soft_bodies(&self) -> &[SoftBody] {
+        &self.soft_bodies
+    }
+
+    ///Add a soft body of `kind` filling the rectangle between `a` and `b`, made of balls of
+    ///`radius`. Returns the ids of its balls.
+    pub fn add_soft_body(
+        &mut self,
+        kind: SoftKind,
+        a: [f32; 2],
+        b: [f32; 2],
+        radius: f32,
+    ) -> Vec<usize> {
+        let layout = SoftLayout::new(kind, a, b, radius);
+        let ids: Vec<usize> = layout.positions.iter().map(|_| self.next_id()).collect();
+        for (i, (&position, &id)) in layout.positions.iter().zip(&ids).enumerate() {
+            let mut ball = Ball::new(radius, position, id);
+            ball.do_physics = !layout.pinned.contains(&i);
+            self.push_ball(ball);
+        }
+        self.soft_bodies.push(SoftBody::new(kind, &layout, &ids));
+
+        ids
+    }
+
+    ///Boundary of the quadtree for a world from `(0, 0)` to `world_size`, with a margin of half the
+    ///world around it so balls briefly crossing the border are not lost.
+    pub fn boundary_for(world_size: (f32, f32)) -> Aabb<f32> {
+        let (width, height) = world_size;
+        Aabb::new((width / 2., height / 2.), width.max(height))
+    }
+
+    ///Resize the world from `old_size` to `new_size`. With `scale` the balls move with it, else they
+    ///stay in place. Balls outside of the new world are put back inside, none is lost. On an axis
+    ///too short for a ball, it keeps its place relative to the old world so the balls stay spread.
+    pub fn resize_world(&mut self, old_size: (f32, f32), new_size: (f32, f32), scale: bool) {
+        let (scale_x, scale_y) = if scale {
+            (new_size.0 / old_size.0, new_size.1 / old_size.1)
+        } else {
+            (1., 1.)
+        };
+        let inside = |v: f32, old: f32, old_max: f32, radius: f32, max: f32| {
+            if !v.is_finite() {
+                max / 2.
+            } else if max <= 2. * radius {
+                let relative = if old_max > 0. { old / old_max } else { 0.5 };
+                relative.clamp(0., 1.) * max
+            } else {
+                v.clamp(radius, max - radius)
+            }
+        };
+
+        let mut all: Vec<Ball> = self.iter().copied().collect();
+        for ball in &mut all {
+            let (x, y) = (ball.position[0], ball.position[1]);
+            let position = [
+                inside(x * scale_x, x, old_size.0, ball.size, new_size.0),
+                inside(y * scale_y, y, old_size.1, ball.size, new_size.1),
+            ];
+            if position != *ball.position.as_array() {
+                ball.position = position.into();
+                ball.coliding_pos = ball.position;
+                if ball.sleeping {
+                    ball.wake_up();
+                    self.events.push(PhysicsEvent::Wake { id: ball.id });
+                }
+            }
+        }
+
+        self.boundary = Self::boundary_for(new_size);
+        self.balls = Quadtree::empty(self.boundary);
+        for ball in all {
+            self.balls.insert_fit(ball);
+        }
+        self.border = new_size;
+    }
+
+    ///Run one physics step of `dt` seconds, with balls kept inside `(0, 0)..border`.
+    pub fn simulate(&mut self, dt: f32, border: (f32, f32)) {
+        self.time += dt;
+        self.step += 1;
+        self.border = border;
+
+        if self.auto_spawn && self.time - self.last_ball_spawn_time > 0.05 && !self.is_full() {
             self.last_ball_spawn_time = self.time;
 
             //adding balls :
@@ -118,57 +534,125 @@ impl CanvasDrawable for Balls {
                     border.0 / 10. + i_f.cos().abs() * 20.,
                     border.1 / 10. + i_f.sin().abs() * 20.,
                 ],
-                self.balls.len(),
+                self.next_id(),
             );
             new_ball.speed = Vec2::from([i_f.cos().abs(), i_f.sin().abs()]) * 100.;
 
             self.push_ball(new_ball);
 
-            let i_f = self.balls.len() as f32 / 20.;
-            let mut new_ball = Ball::new(
-                3., //((i_f/dt).sin().abs()+1.) * 5.,
-                [
-                    border.0 - border.0 / 10. + i_f.cos().abs() * 20.,
-                    border.1 / 10. + i_f.sin().abs() * 20.,
-                ],
-                self.balls.len(),
-            );
-            new_ball.speed = Vec2::from([i_f.cos().abs(), i_f.sin().abs()]) * 100.;
-
-            self.push_ball(new_ball);
+            if !self.is_full() {
+                let i_f = self.balls.len() as f32 / 20.;
+                let mut new_ball = Ball::new(
+                    3., //((i_f/dt).sin().abs()+1.) * 5.,
+                    [
+                        border.0 - border.0 / 10. + i_f.cos().abs() * 20.,
+                        border.1 / 10. + i_f.sin().abs() * 20.,
+                    ],
+                    self.next_id(),
+                );
+                new_ball.speed = Vec2::from([i_f.cos().abs(), i_f.sin().abs()]) * 100.;
+
+                self.push_ball(new_ball);
+            }
         }
 
-        const PHYSIC_SUB_STEP: u16 = 10;
-        let sub_dt = dt / f32::from(PHYSIC_SUB_STEP);
+        let substeps = self.substeps;
+        let sub_dt = dt / f32::from(substeps);
+        let mouse_spring = self.mouse_spring;
+        let soft_bodies = &self.soft_bodies;
+        let fluid = self.fluid;
+        let boundary = self.boundary;
+        let max_ball_size = self.max_ball_size;
         let balls = &mut self.balls;
 
-        let range_mapping =
-            |ball: &Ball| Aabb::new((*ball.position.as_array()).into(), ball.size );
+        //the balls of the soft bodies do not sleep, their springs keep pulling on them
+        let soft_particles: HashSet<usize> = soft_bodies
+            .iter()
+            .flat_map(|body| body.particles.iter().copied())
+            .collect();
+        //springs, pressure and fluid forces of the sub step
+        let extra_forces: RefCell<HashMap<usize, Vec2>> = RefCell::new(HashMap::new());
+
+        let asleep_before: HashSet<usize> = balls
+            .iter_mut()
+            .filter(|ball| ball.sleeping)
+            .map(|ball| ball.id)
+            .collect();
+        let step_contacts: RefCell<HashMap<(usize, usize), f32>> = RefCell::new(HashMap::new());
+        let step_events: RefCell<Vec<PhysicsEvent>> = RefCell::new(vec![]);
+        let step_stats = Cell::new(StepStats::default());
+
+        let range_mapping = Self::broad_phase_range;
 
         let first_map = |ball: &mut Ball| {
             if !ball.do_physics {
                 return;
             }
-            ball.reset_force();
-            ball.handle_friction();
-            ball.handle_gravity();
-            ball.handle_border_colision_ball(border);
+            ball.reset_forces();
+            if let Some(spring) = mouse_spring.filter(|spring| spring.id == ball.id) {
+                ball.wake_up();
+                let to_target = Vec2::from(spring.target) - ball.position;
+                ball.apply_force(
+                    (to_target * spring.stiffness - ball.speed * spring.damping) * ball.mass,
+                );
+            }
+            if let Some(&force) = extra_forces.borrow().get(&ball.id) {
+                ball.wake_up();
+                ball.apply_force(force);
+            }
+            if ball.sleeping {
+                return;
+            }
+            ball.apply_drag();
+            ball.apply_gravity();
+            for (wall, impulse) in ball
+                .handle_border_colision_ball(border)
+                .into_iter()
+                .flatten()
+            {
+                step_events.borrow_mut().push(PhysicsEvent::BorderHit {
+                    id: ball.id,
+                    wall,
+                    impulse,
+                });
+            }
         };
 
         let map_with_other = |ball: &mut Ball, other_ball: &mut Ball| {
-            ball.handle_collision_balls(other_ball, sub_dt);
+            if let Some(contact) = ball.handle_collision_balls(other_ball, sub_dt) {
+                let mut stats = step_stats.get();
+                stats.collisions += 1;
+                stats.max_penetration = stats.max_penetration.max(contact.depth);
+                step_stats.set(stats);
+
+                let pair = (ball.id.min(other_ball.id), ball.id.max(other_ball.id));
+                *step_contacts.borrow_mut().entry(pair).or_insert(0.) += contact.impulse;
+            }
         };
 
         let last_map = |ball: &mut Ball| {
-            if !ball.do_physics {
+            if !ball.do_physics || ball.sleeping {
                 return;
             }
-            ball.apply_acceleration(sub_dt);
-            ball.apply_acceleration(sub_dt);
-            ball.apply_speed(sub_dt);
+            ball.integrate(sub_dt);
         };
 
-        for _ in 0..PHYSIC_SUB_STEP {
+        for _ in 0..substeps {
+            let mut forces = HashMap::new();
+            if !soft_particles.is_empty() {
+                let particles: HashMap<usize, Ball> = balls
+                    .iter_mut()
+                    .filter(|ball| soft_particles.contains(&ball.id))
+                    .map(|ball| (ball.id, *ball))
+                    .collect();
+                forces = soft::forces(soft_bodies, &particles);
+            }
+            if let Some(params) = &fluid {
+                for (id, force) in fluid_forces(balls, boundary, params) {
+                    *forces.entry(id).or_insert(Vec2::from([0.; 2])) += force;
+                }
+            }
+            *extra_forces.borrow_mut() = forces;
             balls.map_then_map_with_elem_in_range_then_map(
                 first_map,
                 range_mapping,
@@ -177,57 +661,190 @@ impl CanvasDrawable for Balls {
             );
         }
 
-        balls.iter_mut().for_each(|ball| {
+        let mut step_events = step_events.into_inner();
+        let unsupported = unsupported_sleepers(balls, boundary, border, max_ball_size);
+
+        for ball in balls.iter_mut() {
+            if unsupported.contains(&ball.id) {
+                ball.wake_up();
+            }
             if ball.do_physics {
                 ball.handle_color();
+                // a ball held by the mouse does not sleep, even when held still
+                if mouse_spring.is_none_or(|spring| spring.id != ball.id)
+                    && !soft_particles.contains(&ball.id)
+                    && !(fluid.is_some() && ball.is_circle())
+                {
+                    ball.handle_sleep(dt);
+                }
             }
-        });
-    }
 
-    fn on_click(&mut self, coord: (f32, f32)) {
-        let mut clicking_on_ball = false;
-        for elem in &mut self.balls.iter_mut() {
-            if elem.is_absolute_coord_in(coord) {
-                elem.on_click(coord);
-                clicking_on_ball = true;
-                println!("clicking on ball");
+            match (asleep_before.contains(&ball.id), ball.sleeping) {
+                (false, true) => step_events.push(PhysicsEvent::Sleep { id: ball.id }),
+                (true, false) => step_events.push(PhysicsEvent::Wake { id: ball.id }),
+                _ => (),
             }
         }
-        if !clicking_on_ball {
-            println!("adding ball at :{coord:?}");
-            self.push_ball(Ball::new(10., coord.into(), self.balls.len()))
-        }
-    }
 
-    fn on_click_release(&mut self) {
-        for elem in self.balls.iter_mut() {
-            elem.on_click_release();
+        //contacts, sorted by pair so the events do not depend on the order of the maps :
+        let step_contacts = step_contacts.into_inner();
+        let mut touching: Vec<((usize, usize), f32)> = step_contacts
+            .iter()
+            .map(|(&pair, &impulse)| (pair, impulse))
+            .collect();
+        touching.sort_unstable_by_key(|&(pair, _)| pair);
+        for ((a, b), impulse) in touching {
+            step_events.push(if self.contacts.contains_key(&(a, b)) {
+                PhysicsEvent::ContactPersist { a, b, impulse }
+            } else {
+                PhysicsEvent::ContactBegin { a, b, impulse }
+            });
         }
-    }
+        let mut ended: Vec<(usize, usize)> = self
+            .contacts
+            .keys()
+            .copied()
+            .filter(|pair| !step_contacts.contains_key(pair))
+            .collect();
+        ended.sort_unstable();
+        step_events.extend(
+            ended
+                .into_iter()
+                .map(|(a, b)| PhysicsEvent::ContactEnd { a, b }),
+        );
+        self.contacts = step_contacts;
 
-    fn on_drag(&mut self, old_pos: [f32; 2], new_pos: [f32; 2]) {
-        for elem in self.balls.iter_mut() {
-            if elem.is_absolute_coord_in(old_pos.into()) {
-                elem.on_drag(old_pos, new_pos);
+        //sensors :
+        step_events.append(&mut self.update_sensors());
+
+        self.events.extend(step_events);
+
+        //stats :
+        self.last_step_stats = step_stats.get();
+        if self.stats_logger.is_some() {
+            let stats = self.stats();
+            if let Some(Err(e)) = self.stats_logger.as_mut().map(|logger| logger.log(&stats)) {
+                println!("could not log stats : {e}, stopping stats logging");
+                self.stats_logger = None;
             }
         }
     }
 
-    fn on_window_moved(&mut self, new_pos: (f32, f32)) {
-        for elem in self.balls.iter_mut() {
-            elem.on_window_moved(new_pos);
-        }
+    ///Add a trigger region to the world, returns its id.
+    pub fn add_sensor(&mut self, shape: SensorShape) -> usize {
+        self.sensors.push(Sensor::new(shape));
+        self.sensors.len() - 1
     }
 
-    fn on_window_resized(&mut self, new_size: (u32, u32)) {
-        let (b_x, b_y) = (new_size.0 as f32, new_size.1 as f32);
-        let boundary = Aabb::new((b_x / 2., b_y / 2.), b_x.max(b_y));
+    pub fn sensor(&self, id: usize) -> Option<&Sensor> {
+        self.sensors.get(id)
+    }
 
-        let _ = self.balls.change_bounds(boundary);
-        println!("resized !");
+    pub fn sensors(&self) -> &[Sensor] {
+        &self.sensors
+    }
 
-        for elem in self.balls.iter_mut() {
-            elem.on_window_resized(new_size);
+    fn update_sensors(&mut self) -> Vec<PhysicsEvent> {
+        let mut events = vec![];
+        for (sensor_id, sensor) in self.sensors.iter_mut().enumerate() {
+            let range = sensor.broad_phase_range(self.max_ball_size);
+            let (entered, exited) = sensor.update(self.balls.query_range(range));
+
+            events.extend(entered.into_iter().map(|ball| PhysicsEvent::SensorEnter {
+                sensor: sensor_id,
+                ball,
+            }));
+            events.extend(exited.into_iter().map(|ball| PhysicsEvent::SensorExit {
+                sensor: sensor_id,
+                ball,
+            }));
         }
+
+        events
     }
 }
+
+///Ids of the sleeping balls that touch neither a wall nor another ball anymore, their support having
+///moved away. Balls are seen as their bounding circle, and touch within `SLEEP_CONTACT_SLOP`.
+fn unsupported_sleepers(
+    balls: &Quadtree<f32, Ball, QUADTREE_CAPACITY>,
+    boundary: Aabb<f32>,
+    border: (f32, f32),
+    max_ball_size: f32,
+) -> HashSet<usize> {
+    let touches_wall = |ball: &Ball| {
+        let reach = ball.size + SLEEP_CONTACT_SLOP;
+        ball.position[0] < reach
+            || ball.position[1] < reach
+            || ball.position[0] > border.0 - reach
+            || ball.position[1] > border.1 - reach
+    };
+
+    balls
+        .query_range(boundary)
+        .into_iter()
+        .filter(|ball| ball.sleeping && !touches_wall(ball))
+        .filter(|ball| {
+            let range = Aabb::new(
+                (ball.position[0], ball.position[1]),
+                ball.size + max_ball_size + SLEEP_CONTACT_SLOP,
+            );
+            !balls.query_range(range).into_iter().any(|other| {
+                other.id != ball.id
+                    && ball.position.distance(other.position)
+                        < ball.size + other.size + SLEEP_CONTACT_SLOP
+            })
+        })
+        .map(|ball| ball.id)
+        .collect()
+}
+
+///Pressure and viscosity forces of the fluid on every circle, by ball id. The neighbours of a ball
+///are found with a range query of the quadtree.
+fn fluid_forces(
+    balls: &Quadtree<f32, Ball, QUADTREE_CAPACITY>,
+    boundary: Aabb<f32>,
+    params: &FluidParams,
+) -> HashMap<usize, Vec2> {
+    let fluid: Vec<&Ball> = balls
+        .query_range(boundary)
+        .into_iter()
+        .filter(|ball| ball.is_circle())
+        .collect();
+    let index: HashMap<usize, usize> = fluid
+        .iter()
+        .enumerate()
+        .map(|(i, ball)| (ball.id, i))
+        .collect();
+
+    let neighbours: Vec<Vec<usize>> = fluid
+        .iter()
+        .map(|ball| {
+            let range = Aabb::new(
+                (ball.position[0], ball.position[1]),
+                params.smoothing_radius,
+            );
+            balls
+                .query_range(range)
+                .into_iter()
+                .filter_map(|other| index.get(&other.id).copied())
+                .collect()
+        })
+        .collect();
+    let particles: Vec<FluidParticle> = fluid
+        .iter()
+        .map(|ball| FluidParticle {
+            position: ball.position,
+            velocity: ball.speed,
+            mass: ball.mass,
+        })
+        .collect();
+    let densities = params.densities(&particles, &neighbours);
+    let accelerations = params.accelerations(&particles, &neighbours, &densities);
+
+    fluid
+        .iter()
+        .zip(accelerations)
+        .map(|(ball, acceleration)| (ball.id, acceleration * ball.mass))
+        .collect()
+}
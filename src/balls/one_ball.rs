@@ -1,23 +1,29 @@
-use glium::dynamic_uniform;
 use my_glium_util::{
-    canvas::{CanvasData, traits::CanvasDrawable},
     datastruct::points::As2dPoint,
     math::{EuclidianSpace, Vec2, VectorSpace},
 };
 
 use crate::physics::{
-    constants::{FRICTION_COEF, GRAVITY_CONST, LIGHT_SPEED, MOUSE_ACCELERATION_FACTOR},
-    traits::Physics,
+    collision::{self, ContactPoint, Manifold},
+    constants::{FRICTION_COEF, LIGHT_SPEED},
+    contact::{self, Contact, MIN_DISTANCE, StaticBody},
+    events::Wall,
+    geometry::{BodyShape, Shape},
+    traits::Body,
 };
 
 pub type Color = [f32; 3];
 
 #[derive(Clone, Copy)]
 pub struct Ball {
+    ///Radius of a circle, of the smallest circle around the shape for the other shapes.
     pub size: f32,
+    ///Change it with `with_shape`, that updates the size and the mass.
+    pub shape: BodyShape,
     pub color: Color,
+    ///Keep `color` instead of coloring the ball from its id.
+    pub fixed_color: bool,
 
-    pub z: f32,
     pub id: usize,
 
     pub coliding_pos: Vec2,
@@ -26,12 +32,17 @@ pub struct Ball {
     pub position: Vec2,
     pub speed: Vec2,
     pub acc: Vec2,
+    ///In radians, clockwise on screen.
+    pub angle: f32,
+    ///In radians per second.
+    pub angular_speed: f32,
 
     pub do_physics: bool,
     pub mass: f32,
     pub bounce: f32,
 
-    canva_info: Option<CanvasData>,
+    pub sleeping: bool,
+    still_time: f32,
 }
 
 impl Ball {
@@ -40,9 +51,10 @@ impl Ball {
 
         Self {
             size,
+            shape: BodyShape::Circle,
             color: [1.; 3],
+            fixed_color: false,
 
-            z: 1.,
             id,
 
             coliding_pos: pos.into(),
@@ -51,196 +63,244 @@ impl Ball {
             position: pos.into(),
             speed: [0.; 2].into(),
             acc: [0.; 2].into(),
+            angle: 0.,
+            angular_speed: 0.,
 
             do_physics: true,
             mass: size * size,
             bounce: 0.3,
 
-            canva_info: None,
+            sleeping: false,
+            still_time: 0.,
         }
     }
+
+    ///The same ball with another shape, its size and mass follow the shape (the mass being its area
+    ///over pi, like the `size * size` of a circle).
+    pub fn with_shape(mut self, shape: BodyShape) -> Self {
+        self.shape = shape;
+        self.size = shape.bounding_radius(self.size);
+        self.mass = match shape {
+            BodyShape::Circle => self.size * self.size,
+            _ => shape.area(self.size) / std::f32::consts::PI,
+        };
+        self
+    }
+
+    ///Change the size of the ball, scaling its shape with it.
+    pub fn set_size(&mut self, size: f32) {
+        self.shape = self.shape.scaled(size / self.size.max(f32::EPSILON));
+        self.size = size;
+    }
+
+    pub fn is_circle(&self) -> bool {
+        self.shape == BodyShape::Circle
+    }
+
+    ///Whether `point` (in world coordinates) is inside the ball.
+    pub fn contains(&self, point: [f32; 2]) -> bool {
+        if !self.is_circle() {
+            return self.bounding_shape().contains(point.into());
+        }
+        let distance =
+            ((self.position[0] - point[0]).powi(2) + (self.position[1] - point[1]).powi(2)).sqrt();
+
+        distance < self.size
+    }
 }
 
-impl CanvasDrawable for Ball {
-    fn set_z(&mut self, z: f32) {
-        self.z = z;
+//-----------
+//| Physics |
+//-----------
+
+impl Body for Ball {
+    fn position(&self) -> Vec2 {
+        self.position
     }
 
-    fn get_z(&self) -> f32 {
-        self.z
+    fn set_position(&mut self, position: Vec2) {
+        self.position = position;
     }
 
-    fn update(&mut self, canva_info: &CanvasData, dt: f32) {
-        self.canva_info = Some(*canva_info);
-        self.physics_update(dt);
+    fn velocity(&self) -> Vec2 {
+        self.speed
     }
-    fn canvas_uniforms(&self) -> Vec<glium::uniforms::DynamicUniforms> {
-        vec![dynamic_uniform! {
-            position:self.position.as_array(),
-            speed: self.speed.as_array(),
-            radius: &self.size,
-            color:&self.color,
-            collision_pos:self.coliding_pos.as_array(),
 
-            z:&self.z,
-        }]
+    fn set_velocity(&mut self, velocity: Vec2) {
+        self.speed = velocity;
     }
 
-    fn on_click(&mut self, _: (f32, f32)) {
-        self.do_physics = false;
-        self.color = [0.2, 1., 0.2];
+    fn mass(&self) -> f32 {
+        self.mass
+    }
 
-        self.speed = Vec2::v_space_zero();
-        self.acc = Vec2::v_space_zero();
+    fn bounce(&self) -> f32 {
+        self.bounce
+    }
+
+    fn bounding_shape(&self) -> Shape {
+        self.shape.to_world(self.size, self.position, self.angle)
     }
 
-    fn on_click_release(&mut self) {
-        self.do_physics = true;
+    fn is_dynamic(&self) -> bool {
+        self.do_physics
     }
 
-    fn on_drag(&mut self, old_pos: [f32; 2], new_pos: [f32; 2]) {
+    fn reset_forces(&mut self) {
+        self.nb_coll = 0;
         self.coliding_pos = self.position;
-        self.do_physics = false;
-        self.position = new_pos.into();
-        self.speed = [new_pos[0] - old_pos[0], new_pos[1] - old_pos[1]].into();
-        self.speed *= MOUSE_ACCELERATION_FACTOR;
+        self.acc = Vec2::v_space_zero();
     }
 
-    fn is_absolute_coord_in(&self, coord: (f32, f32)) -> bool {
-        let distance =
-            ((self.position[0] - coord.0).powi(2) + (self.position[1] - coord.1).powi(2)).sqrt();
+    fn apply_force(&mut self, force: Vec2) {
+        self.acc += force / self.mass.max(f32::EPSILON);
+    }
 
-        distance < self.size
+    fn integrate(&mut self, dt: f32) {
+        self.apply_acceleration(dt);
+        self.apply_speed(dt);
+        self.angular_speed -= self.angular_speed * FRICTION_COEF * dt;
+        self.angle += self.angular_speed * dt;
     }
-}
 
-//-----------
-//| Physics |
-//-----------
+    fn angular_velocity(&self) -> f32 {
+        self.angular_speed
+    }
 
-impl Physics for Ball {
-    fn physics_update(&mut self, dt: f32) {
-        if !self.do_physics {
-            return;
-        }
+    fn set_angular_velocity(&mut self, angular_velocity: f32) {
+        self.angular_speed = angular_velocity;
+    }
 
-        const PHYSIC_SUB_STEP: u16 = 20;
-        let sub_dt = dt / f32::from(PHYSIC_SUB_STEP);
-
-        let border: (f32, f32) = (
-            self.canva_info.map(|c| c.size.0).unwrap_or(0.)
-                * self
-                    .canva_info
-                    .map(|c| c.window_resolution.0 as f32)
-                    .unwrap_or(0.),
-            self.canva_info.map(|c| c.size.1).unwrap_or(0.)
-                * self
-                    .canva_info
-                    .map(|c| c.window_resolution.1 as f32)
-                    .unwrap_or(0.),
-        );
-
-        for _ in 0..PHYSIC_SUB_STEP {
-            self.reset_force();
-            self.handle_gravity();
-            self.handle_border_colision_ball(border);
-
-            self.apply_acceleration(sub_dt);
-            self.handle_friction();
-            self.apply_speed(sub_dt);
-            self.handle_color();
+    ///Circles do not turn, their collisions go through their centers.
+    fn inverse_inertia(&self) -> f32 {
+        if !self.do_physics || self.is_circle() {
+            return 0.;
         }
+        1. / (self.mass * self.shape.inertia_per_mass(self.size)).max(f32::EPSILON)
     }
 }
 
 impl Ball {
-    const PHYS_MIN_DIST: f32 = 0.001;
     const MAX_VEL: f32 = LIGHT_SPEED / 1_000.;
 
-    pub fn handle_border_colision_ball(&mut self, (b_x, b_y): (f32, f32)) {
+    ///Under this speed, a ball is considered still.
+    const SLEEP_SPEED: f32 = 5.;
+    ///Time a ball has to stay still before falling asleep.
+    const SLEEP_TIME: f32 = 1.;
+    ///Speed change needed from a collision to wake a sleeping ball.
+    const WAKE_SPEED: f32 = 20.;
+    ///Under this speed, hitting a wall is not reported (resting balls touch the floor every step).
+    const BORDER_HIT_MIN_SPEED: f32 = 20.;
+
+    ///Keep the ball inside the border, returns the walls hit on each axis with the impulse of the hit.
+    pub fn handle_border_colision_ball(
+        &mut self,
+        (b_x, b_y): (f32, f32),
+    ) -> [Option<(Wall, f32)>; 2] {
+        if !self.is_circle() {
+            return self.handle_border_colision_shape((b_x, b_y));
+        }
         let [x, y] = &mut self.position.as_mut_array();
         let [s_x, s_y] = &mut self.speed.as_mut_array();
         let size = self.size;
         let bounce = self.bounce;
+        let mass = self.mass;
+
+        let hit = |wall: Wall, speed: f32| {
+            (speed.abs() > Self::BORDER_HIT_MIN_SPEED)
+                .then_some((wall, mass * speed.abs() * (1. + bounce)))
+        };
+        let mut hits = [None; 2];
 
         // Bounding box
         if *x < size {
             *x = size; // prevent sticking
+            hits[0] = hit(Wall::Left, *s_x);
             *s_x *= -bounce;
         } else if *x > b_x - size {
             *x = b_x - size; // prevent sticking
+            hits[0] = hit(Wall::Right, *s_x);
             *s_x *= -bounce;
         }
 
         if *y < size {
             *y = size; // prevent sticking
+            hits[1] = hit(Wall::Top, *s_y);
             *s_y *= -bounce;
         } else if *y > b_y - size {
             *y = b_y - size; // prevent sticking
+            hits[1] = hit(Wall::Bottom, *s_y);
             *s_y *= -bounce;
         }
+
+        hits
+    }
+
+    ///Same as `handle_border_colision_ball`, for the shapes that are not circles : each wall touched
+    ///by the shape pushes it back with a manifold of its deepest points.
+    fn handle_border_colision_shape(&mut self, (b_x, b_y): (f32, f32)) -> [Option<(Wall, f32)>; 2] {
+        let mut hits = [None; 2];
+        //wall, axis, position of the wall on the axis, normal from the ball toward the wall
+        let walls = [
+            (Wall::Left, 0, 0., -1.),
+            (Wall::Right, 0, b_x, 1.),
+            (Wall::Top, 1, 0., -1.),
+            (Wall::Bottom, 1, b_y, 1.),
+        ];
+        for (wall, axis, at, direction) in walls {
+            let shape = self.bounding_shape();
+            let radius = shape.radius();
+            let mut normal = [0.; 2];
+            normal[axis] = direction;
+
+            let mut manifold = Manifold::new(normal.into());
+            for &point in shape.core() {
+                let depth = (point[axis] - at) * direction + radius;
+                if depth > 0. {
+                    let mut position = point;
+                    position[axis] = at;
+                    manifold.push(ContactPoint { position, depth });
+                }
+            }
+            if manifold.points().is_empty() {
+                continue;
+            }
+
+            let mut border = StaticBody {
+                position: self.position,
+                bounce: self.bounce,
+            };
+            let impulse = contact::resolve_manifold(self, &mut border, &manifold);
+            if hits[axis].is_none() && impulse > self.mass * Self::BORDER_HIT_MIN_SPEED {
+                hits[axis] = Some((wall, impulse));
+            }
+        }
+
+        hits
     }
 
     pub fn is_overlapping(&self, other: &Self) -> bool {
+        if !(self.is_circle() && other.is_circle()) {
+            return collision::collide(&self.bounding_shape(), &other.bounding_shape()).is_some();
+        }
         (self.position[0] - other.position[0]) * (self.position[0] - other.position[0])
             + (self.position[1] - other.position[1]) * (self.position[1] - other.position[1])
             < (self.size + other.size) * (self.size + other.size)
     }
 
-    fn handle_static_collision(&mut self, other: &mut Self) {
-        let dist = self
-            .position
-            .distance(other.position)
-            .max(Self::PHYS_MIN_DIST);
-
-        //1. compute distance and overlap factor between balls
-
-        let overlap = 0.5 * ((self.size + other.size) - dist).max(0.0);
-
-        //2. resolve overlap
-        self.position += (self.position - other.position) * overlap / dist;
-        other.position -= (self.position - other.position) * overlap / dist;
-    }
-
-    #[allow(dead_code)]
-    fn handle_dynamic_collision_inelastic(&mut self, other: &mut Self) {
-        const RESTITUTION_THRESHOLD: f32 = 0.5;
-
-        let bounce = (self.bounce + other.bounce) / 2.;
-
-        // 1. inelastic collision :
-
-        let dist = self
-            .position
-            .distance(other.position)
-            .max(Self::PHYS_MIN_DIST);
-
-        let rel_speed = self.speed - other.speed;
-        let norm_p = (self.position - other.position) / dist;
-
-        let rel_vel_along_normal = rel_speed.dot(norm_p);
-
-        if rel_vel_along_normal > RESTITUTION_THRESHOLD {
-            return;
+    ///How deep the two balls are inside each other (0 if they are not touching).
+    pub fn penetration_depth(&self, other: &Self) -> f32 {
+        if !(self.is_circle() && other.is_circle()) {
+            return collision::collide(&self.bounding_shape(), &other.bounding_shape())
+                .map_or(0., |manifold| manifold.depth());
         }
-
-        let norm_p: Vec2 = (self.position - other.position) / dist;
-
-        let norm_impulse = rel_vel_along_normal
-            * ((self.mass * other.mass) / (self.mass + other.mass))
-            * (1.0 + bounce);
-
-        self.speed  -= norm_p * (norm_impulse / self.mass.max(f32::EPSILON));
-        other.speed += norm_p * (norm_impulse / other.mass.max(f32::EPSILON));
+        ((self.size + other.size) - self.position.distance(other.position)).max(0.)
     }
 
     #[allow(dead_code)]
     fn handle_dynamic_collision_elastic(&mut self, other: &mut Self) {
         let norm_p = (self.position - other.position)
-            / self
-                .position
-                .distance(other.position)
-                .max(Self::PHYS_MIN_DIST);
+            / self.position.distance(other.position).max(MIN_DISTANCE);
         let tan_p: Vec2 = [-norm_p[0], norm_p[1]].into();
 
         let dotprod_tan_self = self.speed.dot(tan_p);
@@ -262,38 +322,66 @@ impl Ball {
     ///Handle collision between two balls.
     /// this video has been very usefull to make the physics behind this :  
     ///     -> https://www.youtube.com/watch?v=LPzyNOHY3A4
-    pub fn handle_collision_balls(&mut self, other: &mut Ball, _dt: f32) {
-        if self.is_overlapping(other) && self.nb_coll < 100 {
-            self.coliding_pos = other.position;
-            self.nb_coll += 1;
+    ///
+    ///Returns the contact, with the impulse of the collision and the depth of the overlap it
+    ///solved, or `None` if the balls were not touching.
+    pub fn handle_collision_balls(&mut self, other: &mut Ball, _dt: f32) -> Option<Contact> {
+        if self.nb_coll >= 100 {
+            return None;
+        }
+        let other_position = other.position;
+        let contact = contact::resolve_contact(self, other)?;
+        let impulse = contact.impulse;
+        self.coliding_pos = other_position;
+        self.nb_coll += 1;
+
+        //wake up balls that were hit hard enough
+        if impulse / self.mass.max(f32::EPSILON) > Self::WAKE_SPEED {
+            self.wake_up();
+        }
+        if impulse / other.mass.max(f32::EPSILON) > Self::WAKE_SPEED {
+            other.wake_up();
+        }
+        //a sleeping ball is not integrated, the speed of a soft hit would stay until it wakes up
+        for ball in [&mut *self, &mut *other] {
+            if ball.sleeping {
+                ball.speed = Vec2::v_space_zero();
+                ball.angular_speed = 0.;
+            }
+        }
+
+        Some(contact)
+    }
 
-            //I static collision :
-            self.handle_static_collision(other);
+    ///Put the ball to sleep once it stayed still for long enough, sleeping balls are not integrated.
+    pub fn handle_sleep(&mut self, dt: f32) {
+        if self.sleeping {
+            return;
+        }
 
-            //II dynamic response using impulse (see: https://en.wikipedia.org/wiki/Inelastic_collision)
-            // self.handle_dynamic_collision_elastic(other);
-            self.handle_dynamic_collision_inelastic(other);
+        let spin = self.angular_speed * self.size;
+        if self.speed.dot(self.speed) + spin * spin < Self::SLEEP_SPEED * Self::SLEEP_SPEED {
+            self.still_time += dt;
+        } else {
+            self.still_time = 0.;
+        }
+
+        if self.still_time > Self::SLEEP_TIME {
+            self.sleeping = true;
+            self.speed = Vec2::v_space_zero();
+            self.angular_speed = 0.;
+            self.acc = Vec2::v_space_zero();
         }
     }
 
-    pub fn reset_force(&mut self) {
-        self.nb_coll = 0;
-        self.coliding_pos = self.position;
-        self.acc = Vec2::v_space_zero();
+    pub fn wake_up(&mut self) {
+        self.sleeping = false;
+        self.still_time = 0.;
     }
 
     pub fn apply_acceleration(&mut self, dt: f32) {
         self.speed[0] += self.acc[0] * dt;
         self.speed[1] += self.acc[1] * dt;
-
-    }
-
-    pub fn handle_gravity(&mut self) {
-        self.acc[1] += GRAVITY_CONST * self.mass;
-    }
-
-    pub fn handle_friction(&mut self) {
-        self.acc -= self.speed * FRICTION_COEF;
     }
 
     pub fn apply_speed(&mut self, dt: f32) {
@@ -305,6 +393,9 @@ impl Ball {
     }
 
     pub fn handle_color(&mut self) {
+        if self.fixed_color {
+            return;
+        }
         // let f = f32::sin(self.id as f32 * f32::consts::FRAC_PI_2 ).abs();
         self.color = hue_to_rgb(self.id as f32 * 4. * std::f32::consts::FRAC_PI_2 / 32.);
     }
@@ -0,0 +1,133 @@
+//! Trigger regions, that report the balls entering or leaving them without ever colliding.
+
+use std::collections::HashSet;
+
+use my_glium_util::{
+    datastruct::aabb::Aabb,
+    math::{EuclidianSpace, Vec2},
+};
+
+use super::one_ball::Ball;
+use crate::physics::geometry::segment_distance_sq;
+
+#[derive(Clone)]
+pub enum SensorShape {
+    Aabb {
+        min: Vec2,
+        max: Vec2,
+    },
+    Circle {
+        center: Vec2,
+        radius: f32,
+    },
+    ///Vertices of the polygon, in order (clockwise or not).
+    Polygon(Vec<Vec2>),
+}
+
+impl SensorShape {
+    ///Center and half size of the smallest square containing the shape.
+    fn bounding_square(&self) -> (Vec2, f32) {
+        match self {
+            SensorShape::Aabb { min, max } => {
+                let center = (*min + *max) * 0.5;
+                let half_dim = (max[0] - min[0]).max(max[1] - min[1]) * 0.5;
+                (center, half_dim)
+            }
+            SensorShape::Circle { center, radius } => (*center, *radius),
+            SensorShape::Polygon(vertices) => {
+                let (mut min, mut max) = ([f32::MAX; 2], [f32::MIN; 2]);
+                for v in vertices {
+                    min = [min[0].min(v[0]), min[1].min(v[1])];
+                    max = [max[0].max(v[0]), max[1].max(v[1])];
+                }
+                SensorShape::Aabb {
+                    min: min.into(),
+                    max: max.into(),
+                }
+                .bounding_square()
+            }
+        }
+    }
+
+    ///Is a circle of center `pos` and radius `radius` touching the shape.
+    pub fn is_overlapping(&self, pos: Vec2, radius: f32) -> bool {
+        match self {
+            SensorShape::Aabb { min, max } => {
+                let closest: Vec2 =
+                    [pos[0].clamp(min[0], max[0]), pos[1].clamp(min[1], max[1])].into();
+                closest.distance(pos) < radius
+            }
+            SensorShape::Circle { center, radius: r } => center.distance(pos) < r + radius,
+            SensorShape::Polygon(vertices) => {
+                if vertices.is_empty() {
+                    return false;
+                }
+                let mut edges = vertices.iter().zip(vertices.iter().cycle().skip(1));
+
+                let mut inside = false;
+                for (a, b) in edges.clone() {
+                    //ray casting toward +x
+                    if (a[1] > pos[1]) != (b[1] > pos[1])
+                        && pos[0] < a[0] + (pos[1] - a[1]) * (b[0] - a[0]) / (b[1] - a[1])
+                    {
+                        inside = !inside;
+                    }
+                }
+
+                inside || edges.any(|(a, b)| segment_distance_sq(pos, *a, *b) < radius * radius)
+            }
+        }
+    }
+}
+
+pub struct Sensor {
+    pub shape: SensorShape,
+    inside: HashSet<usize>,
+}
+
+impl Sensor {
+    pub fn new(shape: SensorShape) -> Self {
+        Self {
+            shape,
+            inside: HashSet::new(),
+        }
+    }
+
+    ///Number of balls currently inside the sensor.
+    pub fn count(&self) -> usize {
+        self.inside.len()
+    }
+
+    pub fn contains(&self, ball_id: usize) -> bool {
+        self.inside.contains(&ball_id)
+    }
+
+    ///Range to query in the quadtree, so that every ball of radius up to `max_radius`
+    ///touching the sensor has its center in it.
+    pub fn broad_phase_range(&self, max_radius: f32) -> Aabb<f32> {
+        let (center, half_dim) = self.shape.bounding_square();
+        Aabb::new((center[0], center[1]), half_dim + max_radius)
+    }
+
+    ///Refresh the balls inside the sensor from the candidates found by the broad phase,
+    ///returns the ids of the balls that entered and the ones that exited, sorted so that the events
+    ///do not depend on the order of the quadtree.
+    pub fn update<'a>(
+        &mut self,
+        candidates: impl IntoIterator<Item = &'a Ball>,
+    ) -> (Vec<usize>, Vec<usize>) {
+        let now_inside: HashSet<usize> = candidates
+            .into_iter()
+            .filter(|ball| self.shape.is_overlapping(ball.position, ball.size))
+            .map(|ball| ball.id)
+            .collect();
+
+        let mut entered: Vec<usize> = now_inside.difference(&self.inside).copied().collect();
+        let mut exited: Vec<usize> = self.inside.difference(&now_inside).copied().collect();
+        entered.sort_unstable();
+        exited.sort_unstable();
+        self.inside = now_inside;
+
+        (entered, exited)
+    }
+}
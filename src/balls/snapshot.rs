@@ -0,0 +1,71 @@
+//! Snapshot of the simulated state of a world, to go back to it later.
+
+use my_glium_util::datastruct::{aabb::Aabb, quadtree::Quadtree};
+
+use super::{Balls, one_ball::Ball, soft::SoftBody};
+use crate::physics::fluid::FluidParams;
+
+///Balls, soft bodies, fluid mode and clocks of a world. The sensors, the stats and the pending
+///events are not part of it, the balls inside the sensors are found again on restore.
+#[derive(Clone)]
+pub struct WorldSnapshot {
+    pub time: f32,
+    pub step: u64,
+    pub boundary: Aabb<f32>,
+    pub border: (f32, f32),
+    pub last_ball_spawn_time: f32,
+    pub next_id: usize,
+    ///Sorted by id.
+    pub balls: Vec<Ball>,
+    pub soft_bodies: Vec<SoftBody>,
+    pub fluid: Option<FluidParams>,
+}
+
+impl Balls {
+    pub fn snapshot(&self) -> WorldSnapshot {
+        let mut balls: Vec<Ball> = self.iter().copied().collect();
+        balls.sort_unstable_by_key(|ball| ball.id);
+
+        WorldSnapshot {
+            time: self.time,
+            step: self.step,
+            boundary: self.boundary,
+            border: self.border,
+            last_ball_spawn_time: self.last_ball_spawn_time,
+            next_id: self.next_id,
+            balls,
+            soft_bodies: self.soft_bodies.clone(),
+            fluid: self.fluid,
+        }
+    }
+
+    ///Go back to the state of `snapshot`. The contacts are forgotten, and the mouse spring is let go
+    ///if its ball does not exist anymore.
+    pub fn restore(&mut self, snapshot: &WorldSnapshot) {
+        self.time = snapshot.time;
+        self.step = snapshot.step;
+        self.border = snapshot.border;
+        self.last_ball_spawn_time = snapshot.last_ball_spawn_time;
+        self.next_id = snapshot.next_id;
+        self.contacts.clear();
+        self.soft_bodies = snapshot.soft_bodies.clone();
+        self.fluid = snapshot.fluid;
+
+        self.boundary = snapshot.boundary;
+        self.balls = Quadtree::empty(self.boundary);
+        self.max_ball_size = 0.;
+        for ball in snapshot.balls.iter().copied() {
+            self.max_ball_size = self.max_ball_size.max(ball.size);
+            self.balls.insert_fit(ball);
+        }
+
+        if self
+            .mouse_spring
+            .is_some_and(|spring| snapshot.balls.iter().all(|ball| ball.id != spring.id))
+        {
+            self.mouse_spring = None;
+        }
+        //without events : the balls did not move in or out, the sensors only catch up with them
+        self.update_sensors();
+    }
+}
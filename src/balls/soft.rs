@@ -0,0 +1,325 @@
+//! Soft bodies : balls held together by springs, and for blobs by the pressure of the area they
+//! surround. Their balls are simulated and collide like the others, the springs only add forces.
+
+use std::collections::HashMap;
+
+use my_glium_util::math::{EuclidianSpace, Vec2};
+use serde::{Deserialize, Serialize};
+
+use super::one_ball::Ball;
+use crate::physics::{geometry::cross, traits::Body};
+
+///Distance between neighbour balls, in ball radii, so that they only touch when squeezed.
+pub const PARTICLE_SPACING: f32 = 2.5;
+///Balls on a side of a lattice at most, so that a drag over the whole world stays simulable.
+const MAX_PARTICLES_PER_SIDE: usize = 30;
+///Balls around a blob at least.
+const MIN_BLOB_PARTICLES: usize = 6;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SoftKind {
+    ///Lattice with diagonal springs, keeping its shape.
+    #[default]
+    Jelly,
+    ///Ring of balls inflated by the pressure of its area.
+    Blob,
+    ///Lattice without diagonal springs, hanging from its pinned top row.
+    Cloth,
+}
+
+impl SoftKind {
+    pub const ALL: [SoftKind; 3] = [SoftKind::Jelly, SoftKind::Blob, SoftKind::Cloth];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            SoftKind::Jelly => "jelly",
+            SoftKind::Blob => "blob",
+            SoftKind::Cloth => "cloth",
+        }
+    }
+
+    ///The kind after this one, back to the first after the last.
+    pub fn next(self) -> Self {
+        let index = Self::ALL.iter().position(|&kind| kind == self).unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
+    ///Stiffness and damping of the springs, see `Spring`.
+    fn spring(self) -> (f32, f32) {
+        match self {
+            SoftKind::Jelly => (3000., 20.),
+            SoftKind::Blob => (2000., 20.),
+            SoftKind::Cloth => (6000., 30.),
+        }
+    }
+}
+
+///Spring between two balls, pulling them back to `rest_length`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Spring {
+    ///Ball ids.
+    pub a: usize,
+    pub b: usize,
+    pub rest_length: f32,
+    ///Acceleration of the pair per unit of stretch, in 1/s².
+    pub stiffness: f32,
+    ///Acceleration of the pair per unit of stretching speed, in 1/s.
+    pub damping: f32,
+}
+
+///Pressure of the area surrounded by a ring of balls, pushing the ring out when squeezed and in
+///when stretched.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Pressure {
+    ///Ball ids around the area, in order.
+    pub outline: Vec<usize>,
+    pub rest_area: f32,
+    ///Acceleration of the balls per unit of edge length and of relative area change, in 1/s².
+    pub stiffness: f32,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SoftBody {
+    pub kind: SoftKind,
+    ///Ball ids.
+    pub particles: Vec<usize>,
+    pub springs: Vec<Spring>,
+    pub pressure: Option<Pressure>,
+}
+
+///Balls and springs of a soft body before it is added to a world, by ball index.
+#[derive(Debug)]
+pub struct SoftLayout {
+    pub positions: Vec<[f32; 2]>,
+    ///Balls that are not simulated, holding the body in place.
+    pub pinned: Vec<usize>,
+    pub springs: Vec<(usize, usize)>,
+    ///Ring of balls holding the pressure.
+    pub outline: Option<Vec<usize>>,
+}
+
+impl SoftLayout {
+    ///Soft body of `kind` made of balls of `radius`, filling the rectangle between `a` and `b`. A
+    ///rectangle too small still gives a body of a few balls.
+    pub fn new(kind: SoftKind, a: [f32; 2], b: [f32; 2], radius: f32) -> Self {
+        let min = [a[0].min(b[0]), a[1].min(b[1])];
+        let size = [(a[0] - b[0]).abs(), (a[1] - b[1]).abs()];
+        let spacing = radius * PARTICLE_SPACING;
+
+        match kind {
+            SoftKind::Jelly | SoftKind::Cloth => {
+                let count = |length: f32| {
+                    ((length / spacing).round() as usize + 1).clamp(2, MAX_PARTICLES_PER_SIDE)
+                };
+                let (columns, rows) = (count(size[0]), count(size[1]));
+                let index = |column: usize, row: usize| row * columns + column;
+
+                let mut layout = Self {
+                    positions: vec![],
+                    pinned: vec![],
+                    springs: vec![],
+                    outline: None,
+                };
+                for row in 0..rows {
+                    for column in 0..columns {
+                        layout.positions.push([
+                            min[0] + column as f32 * spacing,
+                            min[1] + row as f32 * spacing,
+                        ]);
+                    }
+                }
+                for row in 0..rows {
+                    for column in 0..columns {
+                        let i = index(column, row);
+                        if column + 1 < columns {
+                            layout.springs.push((i, index(column + 1, row)));
+                        }
+                        if row + 1 < rows {
+                            layout.springs.push((i, index(column, row + 1)));
+                        }
+                        if kind == SoftKind::Jelly && column + 1 < columns && row + 1 < rows {
+                            layout.springs.push((i, index(column + 1, row + 1)));
+                            layout
+                                .springs
+                                .push((index(column + 1, row), index(column, row + 1)));
+                        }
+                        //cloth resists bending through springs skipping a ball
+                        if kind == SoftKind::Cloth && column + 2 < columns {
+                            layout.springs.push((i, index(column + 2, row)));
+                        }
+                        if kind == SoftKind::Cloth && row + 2 < rows {
+                            layout.springs.push((i, index(column, row + 2)));
+                        }
+                    }
+                }
+                if kind == SoftKind::Cloth {
+                    layout.pinned = (0..columns).collect();
+                }
+
+                layout
+            }
+            SoftKind::Blob => {
+                use std::f32::consts::TAU;
+
+                let center = [min[0] + size[0] / 2., min[1] + size[1] / 2.];
+                let mut radii = [size[0].max(spacing) / 2., size[1].max(spacing) / 2.];
+                let count = ((TAU * (radii[0] + radii[1]) / 2. / spacing).round() as usize)
+                    .clamp(MIN_BLOB_PARTICLES, 4 * MAX_PARTICLES_PER_SIDE);
+                //grown until the balls are `spacing` apart
+                let grow = (count as f32 * spacing / (TAU * (radii[0] + radii[1]) / 2.)).max(1.);
+                radii = [radii[0] * grow, radii[1] * grow];
+
+                let positions = (0..count)
+                    .map(|i| {
+                        let angle = i as f32 * TAU / count as f32;
+                        [
+                            center[0] + radii[0] * angle.cos(),
+                            center[1] + radii[1] * angle.sin(),
+                        ]
+                    })
+                    .collect();
+                //springs skipping a ball keep the ring from folding
+                let springs = (0..count)
+                    .flat_map(|i| [(i, (i + 1) % count), (i, (i + 2) % count)])
+                    .collect();
+
+                Self {
+                    positions,
+                    pinned: vec![],
+                    springs,
+                    outline: Some((0..count).collect()),
+                }
+            }
+        }
+    }
+}
+
+impl SoftBody {
+    ///Soft body of the balls of `layout`, whose ids are `ids` (in the order of the layout). The
+    ///springs rest at their length in the layout.
+    pub fn new(kind: SoftKind, layout: &SoftLayout, ids: &[usize]) -> Self {
+        let (stiffness, damping) = kind.spring();
+        let springs = layout
+            .springs
+            .iter()
+            .map(|&(a, b)| Spring {
+                a: ids[a],
+                b: ids[b],
+                rest_length: Vec2::from(layout.positions[a])
+                    .distance(Vec2::from(layout.positions[b])),
+                stiffness,
+                damping,
+            })
+            .collect();
+        let pressure = layout.outline.as_ref().map(|outline| {
+            let points: Vec<Vec2> = outline
+                .iter()
+                .map(|&i| layout.positions[i].into())
+                .collect();
+            Pressure {
+                outline: outline.iter().map(|&i| ids[i]).collect(),
+                rest_area: signed_area(&points),
+                stiffness,
+            }
+        });
+
+        Self {
+            kind,
+            particles: ids.to_vec(),
+            springs,
+            pressure,
+        }
+    }
+
+    ///Forget a removed ball, and the springs and pressure it held. Returns whether the body still
+    ///holds some balls together.
+    pub fn remove_particle(&mut self, id: usize) -> bool {
+        self.particles.retain(|&particle| particle != id);
+        self.springs
+            .retain(|spring| spring.a != id && spring.b != id);
+        if self
+            .pressure
+            .as_ref()
+            .is_some_and(|pressure| pressure.outline.contains(&id))
+        {
+            self.pressure = None;
+        }
+
+        !self.springs.is_empty()
+    }
+
+    ///Add the forces of the springs and of the pressure to `forces`, by ball id. Balls missing from
+    ///`particles` are skipped with their springs.
+    pub fn accumulate_forces(
+        &self,
+        particles: &HashMap<usize, Ball>,
+        forces: &mut HashMap<usize, Vec2>,
+    ) {
+        for spring in &self.springs {
+            let (Some(a), Some(b)) = (particles.get(&spring.a), particles.get(&spring.b)) else {
+                continue;
+            };
+            let delta = b.position - a.position;
+            let length = delta.dot(delta).sqrt();
+            let inverse_masses = a.inverse_mass() + b.inverse_mass();
+            if length <= f32::EPSILON || inverse_masses <= 0. {
+                continue;
+            }
+
+            let normal = delta * (1. / length);
+            let stretching_speed = (b.speed - a.speed).dot(normal);
+            let acceleration = spring.stiffness * (length - spring.rest_length)
+                + spring.damping * stretching_speed;
+            let force = normal * (acceleration / inverse_masses);
+            *forces.entry(spring.a).or_insert([0.; 2].into()) += force;
+            *forces.entry(spring.b).or_insert([0.; 2].into()) += force * -1.;
+        }
+
+        if let Some(pressure) = &self.pressure {
+            let Some(ring) = pressure
+                .outline
+                .iter()
+                .map(|id| particles.get(id))
+                .collect::<Option<Vec<&Ball>>>()
+            else {
+                return;
+            };
+            let points: Vec<Vec2> = ring.iter().map(|ball| ball.position).collect();
+            let area = signed_area(&points);
+            if pressure.rest_area.abs() <= f32::EPSILON {
+                return;
+            }
+
+            //positive when squeezed, the sign of the rest area gives the outside of the edges
+            let squeeze = 1. - area / pressure.rest_area;
+            let outward = pressure.stiffness * squeeze * pressure.rest_area.signum();
+            for i in 0..ring.len() {
+                let (a, b) = (ring[i], ring[(i + 1) % ring.len()]);
+                let edge = b.position - a.position;
+                //as long as the edge, split between its two balls
+                let normal = Vec2::from([edge[1], -edge[0]]) * (outward / 2.);
+                *forces.entry(a.id).or_insert([0.; 2].into()) += normal * a.mass;
+                *forces.entry(b.id).or_insert([0.; 2].into()) += normal * b.mass;
+            }
+        }
+    }
+}
+
+///Forces of every soft body on its balls, by ball id.
+pub fn forces(bodies: &[SoftBody], particles: &HashMap<usize, Ball>) -> HashMap<usize, Vec2> {
+    let mut forces = HashMap::new();
+    for body in bodies {
+        body.accumulate_forces(particles, &mut forces);
+    }
+
+    forces
+}
+
+///Area of the polygon, positive when counter-clockwise with y going up.
+fn signed_area(points: &[Vec2]) -> f32 {
+    (0..points.len())
+        .map(|i| cross(points[i], points[(i + 1) % points.len()]))
+        .sum::<f32>()
+        / 2.
+}
@@ -0,0 +1,667 @@
+#![cfg(test)]
+
+//! World tests: resizing, spawner options, sensors, snapshots and history, soft bodies.
+
+use super::{Balls, one_ball::Ball};
+use crate::test_util::{
+    BORDER, DT, XorShift, assert_close, ball, random_balls, random_pile, run, world,
+};
+
+mod resize {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    fn random_world(rng: &mut XorShift, nb: usize, size: (f32, f32)) -> Balls {
+        let mut world = Balls::empty(Balls::boundary_for(size));
+        world.auto_spawn = false;
+        for ball in random_balls(rng, nb, (2., 4.), [0., 0.], [size.0, size.1], 100.) {
+            world.push_ball(ball);
+        }
+
+        world
+    }
+
+    fn assert_all_inside(world: &Balls, size: (f32, f32)) {
+        for b in world.iter() {
+            assert!(
+                (b.size..=size.0 - b.size).contains(&b.position[0])
+                    && (b.size..=size.1 - b.size).contains(&b.position[1]),
+                "ball {} outside of the world at {:?}",
+                b.id,
+                b.position
+            );
+        }
+    }
+
+    #[test]
+    fn shrinking_keeps_every_ball() {
+        let mut rng = XorShift(0x1234_5678);
+        let (big, small) = ((1600., 1200.), (400., 300.));
+        let mut world = random_world(&mut rng, 3_000, big);
+        let ids: HashSet<usize> = world.iter().map(|b| b.id).collect();
+        assert_eq!(ids.len(), 3_000);
+
+        world.resize_world(big, small, false);
+        assert_eq!(world.iter().map(|b| b.id).collect::<HashSet<_>>(), ids);
+        assert_all_inside(&world, small);
+
+        for _ in 0..10 {
+            world.simulate(DT, small);
+        }
+        assert_eq!(world.iter().count(), 3_000);
+    }
+
+    #[test]
+    fn shrinking_below_the_pile_keeps_every_ball() {
+        let mut rng = XorShift(0x8765_4321);
+        let mut world = random_world(&mut rng, 1_000, BORDER);
+
+        world.resize_world(BORDER, (40., 30.), false);
+        assert_eq!(world.iter().count(), 1_000);
+        assert_all_inside(&world, (40., 30.));
+    }
+
+    #[test]
+    fn new_world_clamps_the_balls_outside_of_it() {
+        let boundary = Balls::boundary_for(BORDER);
+        let world = Balls::new(
+            boundary,
+            vec![
+                ball(5., [400., 300.], [0., 0.], 0),
+                ball(5., [5_000., -5_000.], [0., 0.], 1),
+            ],
+        )
+        .unwrap();
+
+        assert_eq!(world.iter().count(), 2);
+        let b = world.iter().find(|b| b.id == 1).unwrap();
+        assert_eq!(
+            *b.position.as_array(),
+            [
+                boundary.center.x + boundary.half_dim - 5.,
+                boundary.center.y - boundary.half_dim + 5.
+            ]
+        );
+
+        let error = Balls::new(boundary, vec![ball(5., [f32::NAN, 0.], [0., 0.], 3)]);
+        assert_eq!(error.err().map(|e| e.id), Some(3));
+    }
+
+    #[test]
+    fn balls_wider_than_the_world_stay_spread() {
+        let mut world = world(
+            [100., 400., 700.]
+                .iter()
+                .enumerate()
+                .map(|(id, &x)| ball(10., [x, 300.], [0., 0.], id))
+                .collect(),
+        );
+
+        world.resize_world(BORDER, (15., BORDER.1), false);
+        let mut xs: Vec<(usize, f32)> = world.iter().map(|b| (b.id, b.position[0])).collect();
+        xs.sort_unstable_by_key(|&(id, _)| id);
+        let xs: Vec<f32> = xs.into_iter().map(|(_, x)| x).collect();
+        assert!(
+            xs.windows(2).all(|w| w[0] < w[1]),
+            "the balls are not spread : {xs:?}"
+        );
+        assert!(xs.iter().all(|x| (0. ..=15.).contains(x)), "{xs:?}");
+    }
+
+    #[test]
+    fn scaling_moves_the_balls_with_the_world() {
+        let mut world = world(vec![ball(5., [400., 300.], [0., 0.], 0)]);
+
+        world.resize_world(BORDER, (400., 300.), true);
+        assert_eq!(
+            *world.iter().next().unwrap().position.as_array(),
+            [200., 150.]
+        );
+
+        world.resize_world((400., 300.), (1600., 300.), false);
+        assert_eq!(
+            *world.iter().next().unwrap().position.as_array(),
+            [200., 150.]
+        );
+    }
+
+    #[test]
+    fn moved_sleeping_ball_wakes_up() {
+        let mut world = world(vec![ball(5., [700., BORDER.1 - 5.], [0., 0.], 0)]);
+        run(&mut world, 120);
+        assert!(world.iter().next().unwrap().sleeping);
+
+        world.resize_world(BORDER, (400., BORDER.1), false);
+        let b = world.iter().next().unwrap();
+        assert!(!b.sleeping);
+        assert_eq!(b.position[0], 395.);
+    }
+}
+
+mod world_options {
+    use super::*;
+    use crate::scene::Scene;
+
+    #[test]
+    fn spawner_stops_at_max_balls() {
+        let mut world = world(vec![]);
+        world.auto_spawn = true;
+        world.max_balls = Some(5);
+
+        run(&mut world, 120);
+        assert_eq!(world.balls.len(), 5);
+    }
+
+    #[test]
+    fn substeps_are_at_least_one() {
+        let mut world = world(vec![ball(10., [400., 300.], [100., 0.], 0)]);
+        world.set_substeps(0);
+        assert_eq!(world.substeps(), 1);
+
+        run(&mut world, 10);
+        assert!(world.iter().all(|ball| ball.position[0].is_finite()));
+    }
+}
+
+mod sensors {
+    use my_glium_util::math::Vec2;
+
+    use super::*;
+    use crate::{balls::sensor::SensorShape, physics::events::PhysicsEvent};
+
+    fn v(x: f32, y: f32) -> Vec2 {
+        [x, y].into()
+    }
+
+    fn sensor_events(events: &[PhysicsEvent]) -> Vec<PhysicsEvent> {
+        events
+            .iter()
+            .copied()
+            .filter(|event| {
+                matches!(
+                    event,
+                    PhysicsEvent::SensorEnter { .. } | PhysicsEvent::SensorExit { .. }
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn shapes_overlap_touching_circles() {
+        let aabb = SensorShape::Aabb {
+            min: v(0., 0.),
+            max: v(100., 50.),
+        };
+        assert!(aabb.is_overlapping(v(50., 25.), 1.));
+        assert!(aabb.is_overlapping(v(105., 25.), 10.));
+        assert!(!aabb.is_overlapping(v(110., 60.), 10.));
+
+        let circle = SensorShape::Circle {
+            center: v(0., 0.),
+            radius: 10.,
+        };
+        assert!(circle.is_overlapping(v(14., 0.), 5.));
+        assert!(!circle.is_overlapping(v(16., 0.), 5.));
+
+        //an L, whose bounding box holds points outside of it
+        let polygon = SensorShape::Polygon(vec![
+            v(0., 0.),
+            v(100., 0.),
+            v(100., 20.),
+            v(20., 20.),
+            v(20., 100.),
+            v(0., 100.),
+        ]);
+        assert!(polygon.is_overlapping(v(10., 50.), 1.));
+        assert!(polygon.is_overlapping(v(50., 10.), 1.));
+        assert!(!polygon.is_overlapping(v(60., 60.), 1.));
+        //outside, but touching an edge
+        assert!(polygon.is_overlapping(v(60., 25.), 6.));
+        assert!(!SensorShape::Polygon(vec![]).is_overlapping(v(0., 0.), 10.));
+    }
+
+    #[test]
+    fn falling_ball_enters_then_exits() {
+        let mut world = world(vec![ball(5., [400., 100.], [0., 0.], 0)]);
+        let sensor = world.add_sensor(SensorShape::Aabb {
+            min: v(300., 200.),
+            max: v(500., 220.),
+        });
+
+        let mut events = vec![];
+        let mut was_inside = false;
+        for _ in 0..120 {
+            run(&mut world, 1);
+            was_inside |= world.sensor(sensor).unwrap().contains(0);
+            events.extend(sensor_events(&world.events().drain()));
+        }
+
+        assert!(was_inside);
+        assert_eq!(world.sensor(sensor).unwrap().count(), 0);
+        assert_eq!(
+            events,
+            [
+                PhysicsEvent::SensorEnter { sensor, ball: 0 },
+                PhysicsEvent::SensorExit { sensor, ball: 0 }
+            ]
+        );
+    }
+
+    #[test]
+    fn big_balls_touching_a_small_sensor_are_found() {
+        let mut big = ball(100., [505., 300.], [0., 0.], 0);
+        big.do_physics = false;
+        let mut world = world(vec![big, ball(5., [50., 50.], [0., 0.], 1)]);
+        let sensor = world.add_sensor(SensorShape::Circle {
+            center: v(400., 300.),
+            radius: 10.,
+        });
+        world.events().drain();
+
+        run(&mut world, 1);
+        assert_eq!(
+            sensor_events(&world.events().drain()),
+            [PhysicsEvent::SensorEnter { sensor, ball: 0 }]
+        );
+    }
+
+    #[test]
+    fn events_are_sorted_by_ball() {
+        let balls = (0..30)
+            .map(|id| ball(2., [310. + 6. * id as f32, 300.], [0., 0.], id))
+            .collect();
+        let mut world = world(balls);
+        let sensor = world.add_sensor(SensorShape::Aabb {
+            min: v(300., 290.),
+            max: v(500., 320.),
+        });
+        world.events().drain();
+
+        run(&mut world, 1);
+        let entered: Vec<usize> = sensor_events(&world.events().drain())
+            .into_iter()
+            .map(|event| match event {
+                PhysicsEvent::SensorEnter { ball, .. } => ball,
+                event => panic!("unexpected {event:?}"),
+            })
+            .collect();
+        assert_eq!(entered, (0..30).collect::<Vec<_>>());
+        assert_eq!(world.sensor(sensor).unwrap().count(), 30);
+    }
+}
+
+mod rewind {
+    use super::*;
+    use crate::{
+        balls::{history::History, sensor::SensorShape},
+        physics::{events::PhysicsEvent, fluid::FluidParams},
+    };
+
+    #[test]
+    fn restore_goes_back_to_the_snapshot() {
+        let mut world = random_pile();
+        run(&mut world, 30);
+        let snapshot = world.snapshot();
+        run(&mut world, 30);
+        let id = world.next_id();
+        world.push_ball(ball(10., [400., 100.], [0., 0.], id));
+
+        world.restore(&snapshot);
+        assert_eq!(world.time(), snapshot.time);
+        assert_eq!(world.step(), snapshot.step);
+        let restored = world.snapshot();
+        assert_eq!(restored.balls.len(), snapshot.balls.len());
+        for (a, b) in restored.balls.iter().zip(&snapshot.balls) {
+            assert_close(a, b, 0.);
+        }
+    }
+
+    #[test]
+    fn restore_brings_back_the_fluid_mode() {
+        let mut world = random_pile();
+        let snapshot = world.snapshot();
+        world.set_fluid(Some(FluidParams::new(10.)));
+        let mut history = History::new(10.);
+        history.record(&world);
+        run(&mut world, 1);
+        world.set_fluid(None);
+        history.record(&world);
+
+        world.restore(&snapshot);
+        assert!(world.fluid().is_none());
+        assert!(history.get(0).unwrap().fluid.is_some());
+        assert!(history.get(1).unwrap().fluid.is_none());
+    }
+
+    #[test]
+    fn rewinding_into_a_sensor_emits_no_event() {
+        let mut world = world(vec![ball(5., [400., 100.], [0., 0.], 0)]);
+        let sensor = world.add_sensor(SensorShape::Aabb {
+            min: [300., 200.].into(),
+            max: [500., 220.].into(),
+        });
+        let mut inside = None;
+        for _ in 0..120 {
+            run(&mut world, 1);
+            if inside.is_none() && world.sensor(sensor).unwrap().contains(0) {
+                inside = Some(world.snapshot());
+            }
+        }
+        assert!(!world.sensor(sensor).unwrap().contains(0));
+
+        world.restore(&inside.expect("the ball went through the sensor"));
+        assert!(world.sensor(sensor).unwrap().contains(0));
+        world.events().drain();
+        run(&mut world, 1);
+        assert!(
+            !world
+                .events()
+                .drain()
+                .iter()
+                .any(|event| matches!(event, PhysicsEvent::SensorEnter { .. })),
+            "the ball entered the sensor twice"
+        );
+    }
+
+    #[test]
+    fn history_frames_match_the_simulation() {
+        let mut world = random_pile();
+        let mut history = History::new(10.);
+        let mut expected = vec![];
+        for _ in 0..200 {
+            run(&mut world, 1);
+            history.record(&world);
+            expected.push(world.snapshot());
+        }
+
+        assert_eq!(history.len(), expected.len());
+        for (i, expected) in expected.iter().enumerate() {
+            let frame = history.get(i).unwrap();
+            assert_eq!(frame.step, expected.step);
+            assert_eq!(history.time(i), Some(expected.time));
+            for (a, b) in frame.balls.iter().zip(&expected.balls) {
+                //half of the quantization step of the speeds
+                assert_close(a, b, 1. / 32.);
+            }
+        }
+        assert!(history.get(expected.len()).is_none());
+    }
+
+    #[test]
+    fn spawned_balls_do_not_start_keyframes() {
+        let mut world = world(vec![]);
+        world.auto_spawn = true;
+        let mut history = History::new(10.);
+        let mut expected = vec![];
+        for _ in 0..120 {
+            run(&mut world, 1);
+            history.record(&world);
+            expected.push(world.snapshot());
+        }
+
+        assert!(world.balls.len() >= 40);
+        assert_eq!(history.keyframes(), 2);
+        for (i, expected) in expected.iter().enumerate() {
+            let frame = history.get(i).unwrap();
+            assert_eq!(frame.next_id, expected.next_id);
+            assert_eq!(frame.last_ball_spawn_time, expected.last_ball_spawn_time);
+            assert_eq!(frame.balls.len(), expected.balls.len());
+            for (a, b) in frame.balls.iter().zip(&expected.balls) {
+                assert_close(a, b, 1. / 32.);
+            }
+        }
+    }
+
+    #[test]
+    fn history_keeps_only_the_last_seconds() {
+        let mut world = random_pile();
+        let mut history = History::new(1.);
+        for _ in 0..600 {
+            run(&mut world, 1);
+            history.record(&world);
+        }
+
+        let last = history.len() - 1;
+        let kept = history.time(last).unwrap() - history.time(0).unwrap();
+        assert!((1. ..2.).contains(&kept), "kept {kept} seconds");
+    }
+
+    #[test]
+    fn truncated_history_resumes_from_the_last_frame() {
+        let mut world = random_pile();
+        let mut history = History::new(10.);
+        for _ in 0..150 {
+            run(&mut world, 1);
+            history.record(&world);
+        }
+
+        history.truncate(100);
+        assert_eq!(history.len(), 100);
+        world.restore(&history.get(99).unwrap());
+        assert_eq!(world.step(), 100);
+
+        run(&mut world, 1);
+        history.record(&world);
+        assert_eq!(history.len(), 101);
+        assert_eq!(history.get(100).unwrap().step, 101);
+    }
+}
+
+mod soft_bodies {
+    use std::collections::HashMap;
+
+    use my_glium_util::math::{EuclidianSpace, Vec2};
+
+    use super::*;
+    use crate::{
+        balls::soft::{PARTICLE_SPACING, SoftBody, SoftKind, SoftLayout, forces},
+        scene::{Scene, SceneError},
+    };
+
+    fn spring_lengths(world: &Balls) -> Vec<(f32, f32)> {
+        world.soft_bodies()[0]
+            .springs
+            .iter()
+            .map(|spring| {
+                let (a, b) = (world.ball(spring.a).unwrap(), world.ball(spring.b).unwrap());
+                (a.position.distance(b.position), spring.rest_length)
+            })
+            .collect()
+    }
+
+    ///The balls of `layout` moved `scale` times away from `center`.
+    fn scaled_particles(layout: &SoftLayout, center: [f32; 2], scale: f32) -> HashMap<usize, Ball> {
+        layout
+            .positions
+            .iter()
+            .enumerate()
+            .map(|(id, p)| {
+                let position = [
+                    center[0] + (p[0] - center[0]) * scale,
+                    center[1] + (p[1] - center[1]) * scale,
+                ];
+                (id, Ball::new(5., position, id))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn lattices_link_their_neighbours() {
+        let jelly = SoftLayout::new(SoftKind::Jelly, [0., 0.], [25., 25.], 5.);
+        assert_eq!(jelly.positions.len(), 9);
+        //6 horizontal, 6 vertical and 8 diagonal
+        assert_eq!(jelly.springs.len(), 20);
+        assert!(jelly.pinned.is_empty() && jelly.outline.is_none());
+
+        let cloth = SoftLayout::new(SoftKind::Cloth, [25., 25.], [0., 0.], 5.);
+        assert_eq!(cloth.positions.len(), 9);
+        //12 between neighbours and 6 skipping a ball
+        assert_eq!(cloth.springs.len(), 18);
+        assert_eq!(cloth.pinned, vec![0, 1, 2]);
+
+        //a click still gives a body
+        let dot = SoftLayout::new(SoftKind::Jelly, [10., 10.], [10., 10.], 5.);
+        assert_eq!(dot.positions.len(), 4);
+    }
+
+    #[test]
+    fn blobs_are_rings_of_spaced_balls() {
+        for (a, b) in [([0., 0.], [0., 0.]), ([0., 0.], [100., 100.])] {
+            let blob = SoftLayout::new(SoftKind::Blob, a, b, 5.);
+            let count = blob.positions.len();
+            assert!(count >= 6);
+            assert_eq!(blob.outline, Some((0..count).collect()));
+            for i in 0..count {
+                let (p, q) = (
+                    Vec2::from(blob.positions[i]),
+                    Vec2::from(blob.positions[(i + 1) % count]),
+                );
+                let gap = p.distance(q) / (5. * PARTICLE_SPACING);
+                assert!((0.75..1.05).contains(&gap), "balls {i} spaced by {gap}");
+            }
+        }
+    }
+
+    #[test]
+    fn pressure_pushes_a_squeezed_blob_out() {
+        let layout = SoftLayout::new(SoftKind::Blob, [0., 0.], [100., 100.], 5.);
+        let ids: Vec<usize> = (0..layout.positions.len()).collect();
+        let mut body = SoftBody::new(SoftKind::Blob, &layout, &ids);
+        body.springs.clear();
+        let center = [50., 50.];
+
+        for (scale, outward) in [(0.8, true), (1.2, false), (1., false)] {
+            let particles = scaled_particles(&layout, center, scale);
+            let forces = forces(std::slice::from_ref(&body), &particles);
+            for (id, ball) in &particles {
+                let radial = forces[id].dot(ball.position - Vec2::from(center));
+                if scale == 1. {
+                    assert!(radial.abs() < 1e-2, "force on a blob at rest : {radial}");
+                } else {
+                    assert_eq!(radial > 0., outward, "ball {id} at scale {scale}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn jelly_falls_and_keeps_its_shape() {
+        let mut world = world(vec![]);
+        let ids = world.add_soft_body(SoftKind::Jelly, [100., 100.], [150., 150.], 5.);
+        assert_eq!(ids.len(), 25);
+        assert_eq!(world.soft_bodies().len(), 1);
+
+        run(&mut world, 300);
+
+        for id in &ids {
+            let ball = world.ball(*id).unwrap();
+            assert!(!ball.sleeping);
+            assert!(ball.position[1] > 150., "ball {id} did not fall");
+            assert!(ball.position[1] <= BORDER.1);
+        }
+        for (length, rest) in spring_lengths(&world) {
+            assert!(
+                (length - rest).abs() < rest * 0.3,
+                "spring of {rest} stretched to {length}"
+            );
+        }
+    }
+
+    #[test]
+    fn cloth_hangs_from_its_pinned_row() {
+        let mut world = world(vec![]);
+        let ids = world.add_soft_body(SoftKind::Cloth, [300., 100.], [400., 200.], 5.);
+        let pinned: Vec<Ball> = ids
+            .iter()
+            .map(|id| *world.ball(*id).unwrap())
+            .filter(|ball| !ball.do_physics)
+            .collect();
+        assert_eq!(pinned.len(), 9);
+
+        run(&mut world, 120);
+
+        for ball in &pinned {
+            assert_eq!(
+                world.ball(ball.id).unwrap().position.as_array(),
+                ball.position.as_array()
+            );
+        }
+        for (length, rest) in spring_lengths(&world) {
+            assert!(
+                length < rest * 1.5,
+                "spring of {rest} stretched to {length}"
+            );
+        }
+    }
+
+    #[test]
+    fn removed_balls_take_their_springs_with_them() {
+        let mut world = world(vec![]);
+        let jelly = world.add_soft_body(SoftKind::Jelly, [100., 100.], [125., 125.], 5.);
+        let blob = world.add_soft_body(SoftKind::Blob, [300., 100.], [350., 150.], 5.);
+
+        world.remove_ball(jelly[4]);
+        world.remove_ball(blob[0]);
+
+        let bodies = world.soft_bodies();
+        assert_eq!(bodies.len(), 2);
+        assert!(!bodies[0].particles.contains(&jelly[4]));
+        assert!(
+            bodies[0]
+                .springs
+                .iter()
+                .all(|spring| spring.a != jelly[4] && spring.b != jelly[4])
+        );
+        //a broken ring holds no pressure
+        assert!(bodies[1].pressure.is_none());
+
+        //a lone ball is not a soft body anymore
+        let square = world.add_soft_body(SoftKind::Jelly, [500., 100.], [500., 100.], 5.);
+        for id in &square[1..] {
+            world.remove_ball(*id);
+        }
+        assert_eq!(world.soft_bodies().len(), 2);
+    }
+
+    #[test]
+    fn restore_brings_back_the_soft_bodies() {
+        let mut world = world(vec![]);
+        let before = world.snapshot();
+        world.add_soft_body(SoftKind::Blob, [100., 100.], [150., 150.], 5.);
+        let after = world.snapshot();
+
+        world.restore(&before);
+        assert!(world.soft_bodies().is_empty());
+        world.restore(&after);
+        assert_eq!(world.soft_bodies(), after.soft_bodies.as_slice());
+    }
+
+    #[test]
+    fn scenes_describe_soft_bodies() {
+        let scene = Scene::from_json(
+            r#"{ "width": 800, "height": 600,
+                "balls": [{ "size": 10, "position": [50, 50] }],
+                "soft_bodies": [
+                    { "kind": "cloth", "min": [100, 100], "max": [125, 125], "radius": 5 }
+                ]
+            }"#,
+        )
+        .unwrap();
+        let world = scene.build();
+        assert_eq!(world.balls.len(), 10);
+        assert_eq!(world.soft_bodies()[0].kind, SoftKind::Cloth);
+        assert_eq!(
+            world.soft_bodies()[0].particles,
+            (1..10).collect::<Vec<_>>()
+        );
+
+        let flat = r#"{ "width": 800, "height": 600,
+            "soft_bodies": [{ "kind": "jelly", "min": [0, 0], "max": [10, 10], "radius": 0 }]
+        }"#;
+        assert!(matches!(
+            Scene::from_json(flat),
+            Err(SceneError::InvalidSoftBody(0))
+        ));
+    }
+}
@@ -0,0 +1,258 @@
+//! Benchmark mode : the same random scenes at fixed ball counts, run for a fixed number of frames
+//! with a fixed time step, and a JSON report of the physics (and render) times that can be compared
+//! with a baseline.
+
+#[cfg(test)]
+mod test;
+
+use std::{fmt, fs, io, path::Path, time::Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{balls::Balls, physics::clock::STEP_DT, scene::Scene};
+
+///Ball counts of the benchmark scenes.
+pub const BENCH_COUNTS: [usize; 4] = [100, 1_000, 5_000, 20_000];
+///Size of the world of every benchmark scene.
+pub const BENCH_WORLD_SIZE: (f32, f32) = (1600., 1200.);
+///Seed of the benchmark scenes, so every run simulates the same balls.
+pub const BENCH_SEED: u64 = 0xBA11;
+///Frames run before the measures of each scene start.
+pub const WARMUP_FRAMES: u32 = 10;
+
+///Statistics of a set of durations, in milliseconds.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct Percentiles {
+    pub mean: f32,
+    pub p50: f32,
+    pub p90: f32,
+    pub p99: f32,
+    pub max: f32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SceneReport {
+    pub balls: usize,
+    pub frames: u32,
+    ///Time of `Balls::simulate` alone.
+    pub physics_ms: Percentiles,
+    ///Time until the GPU is done drawing the frame, without the buffer swap. `None` when run
+    ///headlessly.
+    pub render_ms: Option<Percentiles>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BenchReport {
+    pub substeps: u16,
+    pub dt: f32,
+    pub scenes: Vec<SceneReport>,
+}
+
+#[derive(Debug)]
+pub enum BenchError {
+    Io(io::Error),
+    Json(serde_json::Error),
+    ///The baseline was run with other sub steps or time step, its times cannot be compared.
+    Mismatch {
+        substeps: (u16, u16),
+        dt: (f32, f32),
+    },
+}
+
+impl fmt::Display for BenchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BenchError::Io(e) => write!(f, "could not access the report : {e}"),
+            BenchError::Json(e) => write!(f, "invalid report : {e}"),
+            BenchError::Mismatch { substeps, dt } => write!(
+                f,
+                "the baseline was run with {} sub steps and a time step of {}s, not {} and {}s",
+                substeps.1, dt.1, substeps.0, dt.0
+            ),
+        }
+    }
+}
+
+impl std::error::Error for BenchError {}
+
+impl From<io::Error> for BenchError {
+    fn from(e: io::Error) -> Self {
+        BenchError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for BenchError {
+    fn from(e: serde_json::Error) -> Self {
+        BenchError::Json(e)
+    }
+}
+
+impl Percentiles {
+    ///Statistics of `samples`, in seconds.
+    pub fn from_samples(samples: &[f32]) -> Self {
+        if samples.is_empty() {
+            return Self::default();
+        }
+        let mut sorted = samples.to_vec();
+        sorted.sort_unstable_by(f32::total_cmp);
+        //nearest rank
+        let percentile =
+            |p: f32| sorted[((p * sorted.len() as f32 / 100.).ceil() as usize).max(1) - 1] * 1000.;
+
+        Self {
+            mean: sorted.iter().sum::<f32>() / sorted.len() as f32 * 1000.,
+            p50: percentile(50.),
+            p90: percentile(90.),
+            p99: percentile(99.),
+            max: sorted[sorted.len() - 1] * 1000.,
+        }
+    }
+}
+
+impl BenchReport {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, BenchError> {
+        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), BenchError> {
+        Ok(fs::write(path, self.to_json())?)
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("a report is always serializable") + "\n"
+    }
+
+    ///One line per scene of both reports, with the change of the median times since `baseline`.
+    ///Fails if the baseline was not run with the same sub steps and time step.
+    pub fn compare(&self, baseline: &BenchReport) -> Result<Vec<String>, BenchError> {
+        if self.substeps != baseline.substeps || self.dt != baseline.dt {
+            return Err(BenchError::Mismatch {
+                substeps: (self.substeps, baseline.substeps),
+                dt: (self.dt, baseline.dt),
+            });
+        }
+        let change = |name: &str, now: f32, before: f32| {
+            //a time too short to be measured has no relative change
+            let relative = if before > 0. {
+                format!("{:+.1}%", (now / before - 1.) * 100.)
+            } else {
+                "n/a".to_owned()
+            };
+            format!("{name} p50 {before:.3}ms -> {now:.3}ms ({relative})")
+        };
+
+        Ok(self
+            .scenes
+            .iter()
+            .filter_map(|scene| {
+                let before = baseline.scenes.iter().find(|b| b.balls == scene.balls)?;
+                let mut line = format!(
+                    "{} balls : {}",
+                    scene.balls,
+                    change("physics", scene.physics_ms.p50, before.physics_ms.p50)
+                );
+                if let (Some(now), Some(before)) = (scene.render_ms, before.render_ms) {
+                    line += &format!(", {}", change("render", now.p50, before.p50));
+                }
+                Some(line)
+            })
+            .collect())
+    }
+}
+
+///Progress of a benchmark : the scenes left to run and the times measured on the current one.
+pub struct BenchRun {
+    ///In the order they are run, the current one first.
+    counts: Vec<usize>,
+    frames: u32,
+    substeps: u16,
+    ///Frames run on the current scene, warm up included.
+    frame: u32,
+    physics: Vec<f32>,
+    render: Vec<f32>,
+    scenes: Vec<SceneReport>,
+}
+
+impl BenchRun {
+    ///Run `frames` frames of each scene of `counts`, with `substeps` physics sub steps.
+    pub fn new(counts: &[usize], frames: u32, substeps: u16) -> Self {
+        Self {
+            counts: counts.to_vec(),
+            frames: frames.max(1),
+            substeps,
+            frame: 0,
+            physics: vec![],
+            render: vec![],
+            scenes: vec![],
+        }
+    }
+
+    ///The world of the current scene, `None` once every scene was run.
+    pub fn world(&self) -> Option<Balls> {
+        let &count = self.counts.first()?;
+        let (width, height) = BENCH_WORLD_SIZE;
+        let mut balls = Scene::random(width, height, count, BENCH_SEED).build();
+        balls.set_substeps(self.substeps);
+
+        Some(balls)
+    }
+
+    ///Add the times of a frame of the current scene, in seconds. Whether it was its last frame, the
+    ///next scene being the current one.
+    pub fn record(&mut self, physics: f32, render: Option<f32>) -> bool {
+        let Some(&count) = self.counts.first() else {
+            return false;
+        };
+        self.frame += 1;
+        if self.frame <= WARMUP_FRAMES {
+            return false;
+        }
+        self.physics.push(physics);
+        self.render.extend(render);
+        if self.frame < WARMUP_FRAMES + self.frames {
+            return false;
+        }
+
+        self.scenes.push(SceneReport {
+            balls: count,
+            frames: self.frames,
+            physics_ms: Percentiles::from_samples(&self.physics),
+            render_ms: (!self.render.is_empty()).then(|| Percentiles::from_samples(&self.render)),
+        });
+        self.counts.remove(0);
+        self.frame = 0;
+        self.physics.clear();
+        self.render.clear();
+
+        true
+    }
+
+    ///Report of the scenes run so far.
+    pub fn report(&self) -> BenchReport {
+        BenchReport {
+            substeps: self.substeps,
+            dt: STEP_DT,
+            scenes: self.scenes.clone(),
+        }
+    }
+
+    ///Run every scene left without rendering them.
+    pub fn run_headless(mut self) -> BenchReport {
+        while let Some(mut balls) = self.world() {
+            let events = balls.events();
+            let border = balls.border_for(BENCH_WORLD_SIZE);
+            loop {
+                let start = Instant::now();
+                balls.simulate(STEP_DT, border);
+                let physics = start.elapsed().as_secs_f32();
+                // nobody listens to the events here, do not let them pile up
+                events.drain();
+                if self.record(physics, None) {
+                    break;
+                }
+            }
+        }
+
+        self.report()
+    }
+}
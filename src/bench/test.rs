@@ -0,0 +1,115 @@
+#![cfg(test)]
+
+use super::{BenchError, BenchReport, BenchRun, Percentiles, SceneReport, WARMUP_FRAMES};
+
+fn scene(balls: usize, physics_p50: f32, render_p50: Option<f32>) -> SceneReport {
+    let stats = |p50| Percentiles {
+        p50,
+        ..Percentiles::default()
+    };
+    SceneReport {
+        balls,
+        frames: 10,
+        physics_ms: stats(physics_p50),
+        render_ms: render_p50.map(stats),
+    }
+}
+
+#[test]
+fn percentiles_use_the_nearest_rank() {
+    let samples: Vec<f32> = (1..=100).rev().map(|ms| ms as f32 / 1000.).collect();
+    let stats = Percentiles::from_samples(&samples);
+
+    assert!((stats.mean - 50.5).abs() < 1e-3);
+    assert!((stats.p50 - 50.).abs() < 1e-3);
+    assert!((stats.p90 - 90.).abs() < 1e-3);
+    assert!((stats.p99 - 99.).abs() < 1e-3);
+    assert!((stats.max - 100.).abs() < 1e-3);
+
+    assert_eq!(Percentiles::from_samples(&[]), Percentiles::default());
+    assert!((Percentiles::from_samples(&[0.002]).p99 - 2.).abs() < 1e-6);
+}
+
+#[test]
+fn warm_up_frames_are_not_measured() {
+    let mut run = BenchRun::new(&[10, 20], 3, 10);
+
+    for _ in 0..WARMUP_FRAMES {
+        assert!(!run.record(1., Some(1.)));
+    }
+    assert!(!run.record(0.001, Some(0.002)));
+    assert!(!run.record(0.001, Some(0.002)));
+    assert!(run.record(0.001, Some(0.002)));
+
+    let report = run.report();
+    assert_eq!(report.scenes.len(), 1);
+    assert_eq!(report.scenes[0].balls, 10);
+    assert!((report.scenes[0].physics_ms.max - 1.).abs() < 1e-6);
+    assert!((report.scenes[0].render_ms.unwrap().max - 2.).abs() < 1e-6);
+    assert_eq!(run.world().unwrap().balls.len(), 20);
+}
+
+#[test]
+fn headless_runs_cover_every_scene() {
+    let report = BenchRun::new(&[10, 50], 5, 2).run_headless();
+
+    assert_eq!(report.substeps, 2);
+    let balls: Vec<usize> = report.scenes.iter().map(|s| s.balls).collect();
+    assert_eq!(balls, [10, 50]);
+    assert!(report.scenes.iter().all(|s| s.render_ms.is_none()));
+    assert!(report.scenes.iter().all(|s| s.physics_ms.max > 0.));
+}
+
+#[test]
+fn reports_survive_a_round_trip_and_compare_by_ball_count() {
+    let report = BenchReport {
+        substeps: 10,
+        dt: 1. / 60.,
+        scenes: vec![scene(100, 1.1, Some(2.)), scene(1000, 4., None)],
+    };
+    let parsed: BenchReport = serde_json::from_str(&report.to_json()).unwrap();
+    assert_eq!(parsed, report);
+
+    let baseline = BenchReport {
+        scenes: vec![scene(100, 1., Some(2.)), scene(5000, 9., None)],
+        ..report.clone()
+    };
+    assert_eq!(
+        report.compare(&baseline).unwrap(),
+        [
+            "100 balls : physics p50 1.000ms -> 1.100ms (+10.0%), render p50 2.000ms -> 2.000ms (+0.0%)"
+        ]
+    );
+}
+
+#[test]
+fn compare_rejects_other_configs_and_zero_times() {
+    let report = BenchReport {
+        substeps: 10,
+        dt: 1. / 60.,
+        scenes: vec![scene(100, 1., None)],
+    };
+
+    let other_substeps = BenchReport {
+        substeps: 4,
+        ..report.clone()
+    };
+    assert!(matches!(
+        report.compare(&other_substeps),
+        Err(BenchError::Mismatch { .. })
+    ));
+    let other_dt = BenchReport {
+        dt: 1. / 120.,
+        ..report.clone()
+    };
+    assert!(report.compare(&other_dt).is_err());
+
+    let zero = BenchReport {
+        scenes: vec![scene(100, 0., None)],
+        ..report.clone()
+    };
+    assert_eq!(
+        report.compare(&zero).unwrap(),
+        ["100 balls : physics p50 0.000ms -> 1.000ms (n/a)"]
+    );
+}
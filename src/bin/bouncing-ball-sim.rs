@@ -0,0 +1,132 @@
+//! Headless runner : load a scene, run the physics with a fixed time step and export the
+//! trajectories of the balls, without opening any window.
+
+use std::{
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    path::PathBuf,
+    process::ExitCode,
+};
+
+use boucing_ball::{
+    balls::Balls,
+    render::software,
+    scene::Scene,
+    trajectory::{TrajectoryFormat, TrajectoryWriter},
+};
+use clap::Parser;
+
+#[derive(Parser)]
+#[command(version, about)]
+struct Args {
+    ///Scene to simulate (JSON).
+    scene: PathBuf,
+
+    ///Number of physics steps to run.
+    #[arg(short, long, default_value_t = 600)]
+    steps: u64,
+
+    ///Fixed time step, in seconds.
+    #[arg(long, default_value_t = 1. / 60., value_parser = parse_dt)]
+    dt: f32,
+
+    ///Record the balls every N steps.
+    #[arg(long, default_value_t = 1)]
+    every: u64,
+
+    ///Output format : csv or jsonl.
+    #[arg(short, long, default_value = "csv")]
+    format: TrajectoryFormat,
+
+    ///Output file, the standard output if not given.
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+
+    ///Also render numbered PNG frames (`frame_00000.png`...) in this directory.
+    #[arg(long)]
+    frames_dir: Option<PathBuf>,
+
+    ///Render a frame every N steps.
+    #[arg(long, default_value_t = 1)]
+    frame_every: u64,
+}
+
+///A time step that the physics can run with : finite and positive.
+fn parse_dt(s: &str) -> Result<f32, String> {
+    let dt: f32 = s.parse().map_err(|e| format!("{e}"))?;
+    //also false for NaN
+    if dt > 0. && dt.is_finite() {
+        Ok(dt)
+    } else {
+        Err("the time step must be finite and positive".to_owned())
+    }
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+
+    let scene = match Scene::load(&args.scene) {
+        Ok(scene) => scene,
+        Err(e) => {
+            eprintln!("{}: {e}", args.scene.display());
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let output: Box<dyn Write> = match &args.output {
+        Some(path) => match File::create(path) {
+            Ok(file) => Box::new(BufWriter::new(file)),
+            Err(e) => {
+                eprintln!("could not create {} : {e}", path.display());
+                return ExitCode::FAILURE;
+            }
+        },
+        None => Box::new(BufWriter::new(io::stdout().lock())),
+    };
+
+    match run(&args, &scene, output) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("could not write the trajectories : {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(args: &Args, scene: &Scene, output: Box<dyn Write>) -> io::Result<()> {
+    let mut writer = TrajectoryWriter::new(output, args.format)?;
+    let mut balls = scene.build();
+    let events = balls.events();
+    let every = args.every.max(1);
+    let frame_every = args.frame_every.max(1);
+    if let Some(dir) = &args.frames_dir {
+        fs::create_dir_all(dir)?;
+    }
+
+    writer.write_step(&balls)?;
+    write_frame(args, scene, &balls, 0)?;
+    for step in 1..=args.steps {
+        balls.simulate(args.dt, scene.border());
+        // nobody listens to the events here, do not let them pile up
+        events.drain();
+
+        if step.is_multiple_of(every) {
+            writer.write_step(&balls)?;
+        }
+        if step.is_multiple_of(frame_every) {
+            write_frame(args, scene, &balls, step / frame_every)?;
+        }
+    }
+
+    writer.flush()
+}
+
+fn write_frame(args: &Args, scene: &Scene, balls: &Balls, frame_nb: u64) -> io::Result<()> {
+    let Some(dir) = &args.frames_dir else {
+        return Ok(());
+    };
+
+    let (width, height) = scene.border();
+    software::render(balls, width.round() as u32, height.round() as u32)
+        .save_png(dir.join(format!("frame_{frame_nb:05}.png")))
+}
@@ -0,0 +1,508 @@
+//! Key and mouse bindings : which input triggers which action of the app, loaded from a JSON file
+//! mapping action names to their bindings.
+//!
+//! ```json
+//! { "quit": ["Ctrl+KeyQ"], "pause": ["Space", "p"], "use_tool": ["MouseLeft"], "bench": [] }
+//! ```
+//!
+//! A binding is optional modifiers (`Ctrl`, `Shift`, `Alt`, `Super`) and an input joined by `+`.
+//! The input is a physical key named like winit's `KeyCode` (`KeyA`, `Digit1`, `F3`, `Space`...,
+//! the position of the key whatever the layout), a single character (the key writing it in the
+//! current layout) or a mouse button (`MouseLeft`, `MouseRight`, `MouseMiddle`, `MouseBack`,
+//! `MouseForward`). Actions missing from the file keep their default bindings, an empty list
+//! unbinds them.
+
+use std::{collections::BTreeMap, fmt, fs, io, path::Path, str::FromStr};
+
+use glium::winit::{
+    event::MouseButton,
+    keyboard::{KeyCode, ModifiersState},
+};
+
+#[cfg(test)]
+mod test;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Action {
+    Quit,
+    FpsReport,
+    ///Measure the fps while held.
+    Bench,
+    ToggleDebug,
+    ToggleHud,
+    Help,
+    Pause,
+    ///Single step, or one frame forward while rewinding.
+    Step,
+    Rewind,
+    Faster,
+    Slower,
+    NormalSpeed,
+    ToggleSpawner,
+    ///Switch the circles between rigid balls and particles of a fluid.
+    ToggleFluid,
+    ToolGrab,
+    ToolThrow,
+    ToolDelete,
+    ToolSpawn,
+    ToolSelect,
+    ToolSoft,
+    SpawnSmaller,
+    SpawnBigger,
+    ///Next shape for the spawn tool.
+    SpawnShape,
+    ///Next kind of body for the soft body tool.
+    SoftKind,
+    InspectorPrevious,
+    InspectorNext,
+    InspectorDecrease,
+    InspectorIncrease,
+    ///Held to use the current tool.
+    UseTool,
+    ///Held to pan the camera.
+    Pan,
+}
+
+impl Action {
+    pub const ALL: [Action; 30] = [
+        Action::Quit,
+        Action::FpsReport,
+        Action::Bench,
+        Action::ToggleDebug,
+        Action::ToggleHud,
+        Action::Help,
+        Action::Pause,
+        Action::Step,
+        Action::Rewind,
+        Action::Faster,
+        Action::Slower,
+        Action::NormalSpeed,
+        Action::ToggleSpawner,
+        Action::ToggleFluid,
+        Action::ToolGrab,
+        Action::ToolThrow,
+        Action::ToolDelete,
+        Action::ToolSpawn,
+        Action::ToolSelect,
+        Action::ToolSoft,
+        Action::SpawnSmaller,
+        Action::SpawnBigger,
+        Action::SpawnShape,
+        Action::SoftKind,
+        Action::InspectorPrevious,
+        Action::InspectorNext,
+        Action::InspectorDecrease,
+        Action::InspectorIncrease,
+        Action::UseTool,
+        Action::Pan,
+    ];
+
+    ///Name in the bindings file.
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::Quit => "quit",
+            Action::FpsReport => "fps_report",
+            Action::Bench => "bench",
+            Action::ToggleDebug => "toggle_debug",
+            Action::ToggleHud => "toggle_hud",
+            Action::Help => "help",
+            Action::Pause => "pause",
+            Action::Step => "step",
+            Action::Rewind => "rewind",
+            Action::Faster => "faster",
+            Action::Slower => "slower",
+            Action::NormalSpeed => "normal_speed",
+            Action::ToggleSpawner => "toggle_spawner",
+            Action::ToggleFluid => "toggle_fluid",
+            Action::ToolGrab => "tool_grab",
+            Action::ToolThrow => "tool_throw",
+            Action::ToolDelete => "tool_delete",
+            Action::ToolSpawn => "tool_spawn",
+            Action::ToolSelect => "tool_select",
+            Action::ToolSoft => "tool_soft",
+            Action::SpawnSmaller => "spawn_smaller",
+            Action::SpawnBigger => "spawn_bigger",
+            Action::SpawnShape => "spawn_shape",
+            Action::SoftKind => "soft_kind",
+            Action::InspectorPrevious => "inspector_previous",
+            Action::InspectorNext => "inspector_next",
+            Action::InspectorDecrease => "inspector_decrease",
+            Action::InspectorIncrease => "inspector_increase",
+            Action::UseTool => "use_tool",
+            Action::Pan => "pan",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Action> {
+        Self::ALL.into_iter().find(|action| action.name() == name)
+    }
+
+    ///Whether the action lasts while its input is held, and is released with it. Key repeats do
+    ///not trigger it again.
+    pub fn is_held(self) -> bool {
+        matches!(self, Action::Bench | Action::UseTool | Action::Pan)
+    }
+
+    fn default_bindings(self) -> &'static [&'static str] {
+        match self {
+            Action::Quit => &["Escape"],
+            Action::FpsReport => &["KeyF"],
+            Action::Bench => &["KeyD"],
+            Action::ToggleDebug => &["F3"],
+            Action::ToggleHud => &["KeyH"],
+            Action::Help => &["F1"],
+            Action::Pause => &["Space"],
+            Action::Step => &["Period"],
+            Action::Rewind => &["Comma"],
+            Action::Faster => &["Equal"],
+            Action::Slower => &["Minus"],
+            Action::NormalSpeed => &["Digit0"],
+            Action::ToggleSpawner => &["KeyS"],
+            Action::ToggleFluid => &["KeyL"],
+            Action::ToolGrab => &["Digit1"],
+            Action::ToolThrow => &["Digit2"],
+            Action::ToolDelete => &["Digit3"],
+            Action::ToolSpawn => &["Digit4"],
+            Action::ToolSelect => &["Digit5"],
+            Action::ToolSoft => &["Digit6"],
+            Action::SpawnSmaller => &["BracketLeft"],
+            Action::SpawnBigger => &["BracketRight"],
+            Action::SpawnShape => &["KeyB"],
+            Action::SoftKind => &["KeyJ"],
+            Action::InspectorPrevious => &["ArrowUp"],
+            Action::InspectorNext => &["ArrowDown"],
+            Action::InspectorDecrease => &["ArrowLeft"],
+            Action::InspectorIncrease => &["ArrowRight"],
+            Action::UseTool => &["MouseLeft"],
+            Action::Pan => &["MouseRight"],
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Input {
+    ///Physical key, the same position whatever the layout.
+    Key(KeyCode),
+    ///Key writing this (lowercase) character in the current layout.
+    Char(char),
+    Mouse(MouseButton),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Binding {
+    ///Modifiers that must be held, others may be held too.
+    pub modifiers: ModifiersState,
+    pub input: Input,
+}
+
+const MODIFIERS: [(&str, ModifiersState); 4] = [
+    ("Ctrl", ModifiersState::CONTROL),
+    ("Alt", ModifiersState::ALT),
+    ("Shift", ModifiersState::SHIFT),
+    ("Super", ModifiersState::SUPER),
+];
+
+const MOUSE_BUTTONS: [(&str, MouseButton); 5] = [
+    ("MouseLeft", MouseButton::Left),
+    ("MouseRight", MouseButton::Right),
+    ("MouseMiddle", MouseButton::Middle),
+    ("MouseBack", MouseButton::Back),
+    ("MouseForward", MouseButton::Forward),
+];
+
+macro_rules! key_codes {
+    ($($key:ident),* $(,)?) => {
+        ///Keys that can be bound by name.
+        const KEY_CODES: &[(&str, KeyCode)] = &[$((stringify!($key), KeyCode::$key)),*];
+    };
+}
+
+key_codes!(
+    KeyA,
+    KeyB,
+    KeyC,
+    KeyD,
+    KeyE,
+    KeyF,
+    KeyG,
+    KeyH,
+    KeyI,
+    KeyJ,
+    KeyK,
+    KeyL,
+    KeyM,
+    KeyN,
+    KeyO,
+    KeyP,
+    KeyQ,
+    KeyR,
+    KeyS,
+    KeyT,
+    KeyU,
+    KeyV,
+    KeyW,
+    KeyX,
+    KeyY,
+    KeyZ,
+    Digit0,
+    Digit1,
+    Digit2,
+    Digit3,
+    Digit4,
+    Digit5,
+    Digit6,
+    Digit7,
+    Digit8,
+    Digit9,
+    F1,
+    F2,
+    F3,
+    F4,
+    F5,
+    F6,
+    F7,
+    F8,
+    F9,
+    F10,
+    F11,
+    F12,
+    Escape,
+    Space,
+    Enter,
+    Tab,
+    Backspace,
+    Delete,
+    Insert,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+    ArrowUp,
+    ArrowDown,
+    ArrowLeft,
+    ArrowRight,
+    Comma,
+    Period,
+    Minus,
+    Equal,
+    BracketLeft,
+    BracketRight,
+    Semicolon,
+    Quote,
+    Backquote,
+    Backslash,
+    Slash,
+    IntlBackslash,
+    Numpad0,
+    Numpad1,
+    Numpad2,
+    Numpad3,
+    Numpad4,
+    Numpad5,
+    Numpad6,
+    Numpad7,
+    Numpad8,
+    Numpad9,
+    NumpadAdd,
+    NumpadSubtract,
+    NumpadMultiply,
+    NumpadDivide,
+    NumpadDecimal,
+    NumpadEnter,
+);
+
+impl FromStr for Binding {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        //the `+` character is the only input that can end with a `+`
+        let (prefix, input) = if s == "+" {
+            ("", "+")
+        } else if let Some(prefix) = s.strip_suffix("++") {
+            (prefix, "+")
+        } else {
+            s.rsplit_once('+').unwrap_or(("", s))
+        };
+
+        let mut modifiers = ModifiersState::empty();
+        if !prefix.is_empty() {
+            for name in prefix.split('+') {
+                let (_, modifier) = MODIFIERS
+                    .iter()
+                    .find(|(modifier, _)| modifier.eq_ignore_ascii_case(name.trim()))
+                    .ok_or_else(|| format!("unknown modifier {name:?}"))?;
+                modifiers |= *modifier;
+            }
+        }
+
+        let input = if let Some((_, key)) = KEY_CODES.iter().find(|(key, _)| *key == input) {
+            Input::Key(*key)
+        } else if let Some((_, button)) = MOUSE_BUTTONS.iter().find(|(button, _)| *button == input)
+        {
+            Input::Mouse(*button)
+        } else {
+            let mut chars = input.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => Input::Char(c.to_lowercase().next().unwrap_or(c)),
+                _ => return Err(format!("unknown key or mouse button {input:?}")),
+            }
+        };
+
+        Ok(Self { modifiers, input })
+    }
+}
+
+impl fmt::Display for Binding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (name, modifier) in MODIFIERS {
+            if self.modifiers.contains(modifier) {
+                write!(f, "{name}+")?;
+            }
+        }
+
+        match self.input {
+            Input::Key(key) => match KEY_CODES.iter().find(|(_, k)| *k == key) {
+                Some((name, _)) => write!(f, "{name}"),
+                None => write!(f, "{key:?}"),
+            },
+            Input::Char(c) => write!(f, "{c}"),
+            Input::Mouse(button) => match MOUSE_BUTTONS.iter().find(|(_, b)| *b == button) {
+                Some((name, _)) => write!(f, "{name}"),
+                None => write!(f, "{button:?}"),
+            },
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum BindingsError {
+    Io(io::Error),
+    Parse(serde_json::Error),
+    UnknownAction(String),
+    InvalidBinding {
+        action: String,
+        binding: String,
+        error: String,
+    },
+}
+
+impl fmt::Display for BindingsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BindingsError::Io(e) => write!(f, "could not read the bindings : {e}"),
+            BindingsError::Parse(e) => write!(f, "invalid bindings : {e}"),
+            BindingsError::UnknownAction(action) => write!(f, "unknown action {action:?}"),
+            BindingsError::InvalidBinding {
+                action,
+                binding,
+                error,
+            } => write!(f, "invalid binding {binding:?} for {action} : {error}"),
+        }
+    }
+}
+
+impl std::error::Error for BindingsError {}
+
+impl From<io::Error> for BindingsError {
+    fn from(e: io::Error) -> Self {
+        BindingsError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for BindingsError {
+    fn from(e: serde_json::Error) -> Self {
+        BindingsError::Parse(e)
+    }
+}
+
+pub struct Bindings {
+    ///Bindings of every action, in the order of `Action::ALL`.
+    bindings: Vec<Vec<Binding>>,
+}
+
+impl Default for Bindings {
+    fn default() -> Self {
+        Self {
+            bindings: Action::ALL
+                .iter()
+                .map(|action| {
+                    action
+                        .default_bindings()
+                        .iter()
+                        .map(|binding| binding.parse().expect("valid default binding"))
+                        .collect()
+                })
+                .collect(),
+        }
+    }
+}
+
+impl Bindings {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, BindingsError> {
+        Self::from_json(&fs::read_to_string(path)?)
+    }
+
+    ///The default bindings, with the actions of `json` rebound.
+    pub fn from_json(json: &str) -> Result<Self, BindingsError> {
+        let config: BTreeMap<String, Vec<String>> = serde_json::from_str(json)?;
+        let mut bindings = Self::default();
+        for (name, list) in config {
+            let action = Action::from_name(&name)
+                .ok_or_else(|| BindingsError::UnknownAction(name.clone()))?;
+            let list = list
+                .iter()
+                .map(|binding| {
+                    binding
+                        .parse()
+                        .map_err(|error| BindingsError::InvalidBinding {
+                            action: name.clone(),
+                            binding: binding.clone(),
+                            error,
+                        })
+                })
+                .collect::<Result<_, _>>()?;
+            bindings.set(action, list);
+        }
+
+        Ok(bindings)
+    }
+
+    pub fn get(&self, action: Action) -> &[Binding] {
+        &self.bindings[action as usize]
+    }
+
+    pub fn set(&mut self, action: Action, bindings: Vec<Binding>) {
+        self.bindings[action as usize] = bindings;
+    }
+
+    ///Action triggered by one of `inputs` (the physical key and the character of a key press)
+    ///with `modifiers` held. The binding requiring the most modifiers wins, so `Ctrl+KeyS` is
+    ///not also `KeyS`.
+    pub fn action(&self, inputs: &[Input], modifiers: ModifiersState) -> Option<Action> {
+        Action::ALL
+            .into_iter()
+            .flat_map(|action| self.get(action).iter().map(move |b| (action, b)))
+            .filter(|(_, b)| inputs.contains(&b.input) && modifiers.contains(b.modifiers))
+            //`max_by_key` keeps the last of equals, `rev` makes it the first action
+            .rev()
+            .max_by_key(|(_, b)| b.modifiers.bits().count_ones())
+            .map(|(action, _)| action)
+    }
+
+    ///One line per action with its bindings, for the help overlay.
+    pub fn help_lines(&self) -> Vec<String> {
+        Action::ALL
+            .iter()
+            .map(|&action| {
+                let bindings: Vec<String> =
+                    self.get(action).iter().map(|b| b.to_string()).collect();
+                let bindings = if bindings.is_empty() {
+                    "-".to_owned()
+                } else {
+                    bindings.join(", ")
+                };
+                format!("{:<20}{bindings}", action.name().replace('_', " "))
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,143 @@
+#![cfg(test)]
+
+use glium::winit::{
+    event::MouseButton,
+    keyboard::{KeyCode, ModifiersState},
+};
+
+use super::{Action, Binding, Bindings, BindingsError, Input};
+
+fn binding(s: &str) -> Binding {
+    s.parse().unwrap()
+}
+
+#[test]
+fn every_action_has_a_unique_name() {
+    for (i, action) in Action::ALL.into_iter().enumerate() {
+        assert_eq!(action as usize, i);
+        assert_eq!(Action::from_name(action.name()), Some(action));
+    }
+    assert_eq!(Action::from_name("fly"), None);
+}
+
+#[test]
+fn bindings_parse_and_display_back() {
+    assert_eq!(
+        binding("Ctrl+Shift+KeyS"),
+        Binding {
+            modifiers: ModifiersState::CONTROL | ModifiersState::SHIFT,
+            input: Input::Key(KeyCode::KeyS),
+        }
+    );
+    assert_eq!(
+        binding("MouseMiddle").input,
+        Input::Mouse(MouseButton::Middle)
+    );
+    assert_eq!(binding("Q").input, Input::Char('q'));
+    assert_eq!(binding("+").input, Input::Char('+'));
+    assert_eq!(
+        binding("ctrl++"),
+        Binding {
+            modifiers: ModifiersState::CONTROL,
+            input: Input::Char('+'),
+        }
+    );
+
+    for s in ["Ctrl+Shift+KeyS", "Alt+MouseLeft", "F3", "é", "Ctrl++"] {
+        assert_eq!(binding(s).to_string(), s);
+    }
+
+    assert!("Hyper+KeyA".parse::<Binding>().is_err());
+    assert!("KeyAA".parse::<Binding>().is_err());
+    assert!("".parse::<Binding>().is_err());
+}
+
+#[test]
+fn config_rebinds_only_the_given_actions() {
+    let bindings = Bindings::from_json(r#"{ "quit": ["Ctrl+KeyQ", "q"], "bench": [] }"#).unwrap();
+
+    assert_eq!(
+        bindings.get(Action::Quit),
+        [binding("Ctrl+KeyQ"), binding("q")]
+    );
+    assert!(bindings.get(Action::Bench).is_empty());
+    assert_eq!(bindings.get(Action::Pause), [binding("Space")]);
+}
+
+#[test]
+fn invalid_config_is_reported() {
+    assert!(matches!(
+        Bindings::from_json(r#"{ "fly": ["KeyF"] }"#),
+        Err(BindingsError::UnknownAction(action)) if action == "fly"
+    ));
+    assert!(matches!(
+        Bindings::from_json(r#"{ "quit": ["Ctrl+Nope"] }"#),
+        Err(BindingsError::InvalidBinding { binding, .. }) if binding == "Ctrl+Nope"
+    ));
+    assert!(matches!(
+        Bindings::from_json("{ \"quit\": "),
+        Err(BindingsError::Parse(_))
+    ));
+}
+
+#[test]
+fn the_most_specific_binding_wins() {
+    let mut bindings = Bindings::default();
+    bindings.set(Action::FpsReport, vec![binding("Ctrl+KeyS")]);
+    let key_s = [Input::Key(KeyCode::KeyS), Input::Char('s')];
+
+    assert_eq!(
+        bindings.action(&key_s, ModifiersState::empty()),
+        Some(Action::ToggleSpawner)
+    );
+    assert_eq!(
+        bindings.action(&key_s, ModifiersState::CONTROL),
+        Some(Action::FpsReport)
+    );
+    //extra modifiers do not prevent a binding
+    assert_eq!(
+        bindings.action(&[Input::Key(KeyCode::Comma)], ModifiersState::SHIFT),
+        Some(Action::Rewind)
+    );
+    assert_eq!(
+        bindings.action(&[Input::Key(KeyCode::KeyZ)], ModifiersState::empty()),
+        None
+    );
+}
+
+#[test]
+fn layout_characters_can_be_bound() {
+    let bindings = Bindings::from_json(r#"{ "tool_grab": ["&"] }"#).unwrap();
+
+    //the key writing `&` on an AZERTY keyboard is `Digit1` on a QWERTY one
+    assert_eq!(
+        bindings.action(
+            &[Input::Key(KeyCode::Digit1), Input::Char('&')],
+            ModifiersState::empty()
+        ),
+        Some(Action::ToolGrab)
+    );
+    assert_eq!(
+        bindings.action(&[Input::Key(KeyCode::Digit1)], ModifiersState::empty()),
+        None
+    );
+}
+
+#[test]
+fn help_lists_every_action() {
+    let bindings = Bindings::from_json(r#"{ "bench": [] }"#).unwrap();
+    let lines = bindings.help_lines();
+
+    assert_eq!(lines.len(), Action::ALL.len());
+    assert!(lines[0].starts_with("quit") && lines[0].ends_with("Escape"));
+    assert!(
+        lines
+            .iter()
+            .any(|l| l.starts_with("bench") && l.ends_with('-'))
+    );
+    assert!(
+        lines
+            .iter()
+            .any(|l| l.starts_with("use tool") && l.ends_with("MouseLeft"))
+    );
+}
@@ -0,0 +1,14 @@
+//! Balls bouncing between each other, the physics and everything that does not need a window.
+
+pub mod balls;
+pub mod bench;
+pub mod bindings;
+pub mod physics;
+pub mod render;
+pub mod scene;
+pub mod stats;
+pub mod tools;
+pub mod trajectory;
+
+#[cfg(test)]
+mod test_util;
@@ -1,53 +1,258 @@
-use balls::Balls;
-use canvas::{
-    Canvas, CanvasData,
-    traits::{CanvasDrawable, Drawable},
+use boucing_ball::{
+    balls::{Balls, history::History},
+    bench::{BENCH_COUNTS, BENCH_WORLD_SIZE, BenchError, BenchReport, BenchRun},
+    bindings::{Action, Bindings, Input},
+    physics::{
+        clock::{STEP_DT, SimClock},
+        constants::{FRICTION_COEF, GRAVITY_CONST, PHYSIC_SUB_STEP},
+        events::{EventQueue, PhysicsEvent},
+        fluid::FluidParams,
+    },
+    render::{
+        camera::{Camera, ResizePolicy},
+        debug::{DebugLayers, DebugLines, DebugRenderer},
+        fluid::FluidRenderer,
+        hud::{self, FrameTime, Hud, HudInfo},
+        instanced::{BallsRenderer, RenderError},
+        shader::{self, ShaderSources},
+        shapes,
+    },
+    scene::Scene,
+    tools::{Tool, Tools, inspector::Inspector},
 };
+use clap::Parser;
 use glium::{
-    Display, Program, Surface, backend,
-    glutin::surface::WindowSurface,
+    Display, Frame, Surface,
+    glutin::{
+        config::ConfigTemplateBuilder,
+        context::{ContextAttributesBuilder, NotCurrentGlContext},
+        display::{GetGlDisplay, GlDisplay},
+        surface::{GlSurface, SurfaceAttributesBuilder, SwapInterval, WindowSurface},
+    },
     winit::{
         application::ApplicationHandler,
-        event::{ElementState, MouseButton, WindowEvent},
+        dpi::PhysicalSize,
+        event::{ElementState, MouseScrollDelta, WindowEvent},
         event_loop::{self, EventLoop},
-        keyboard,
-        window::Window,
+        keyboard::{self, ModifiersState},
+        raw_window_handle::HasWindowHandle,
+        window::{Fullscreen, Window},
     },
 };
-use my_glium_util::{canvas, datastruct::aabb::Aabb};
+use glutin_winit::DisplayBuilder;
+use std::{collections::HashMap, error::Error, num::NonZeroU32, path::PathBuf, process::ExitCode};
 
-mod balls;
-mod physics;
+///Balls bouncing in a window.
+#[derive(Parser)]
+#[command(version, about)]
+struct Args {
+    ///Width of the window, in pixels.
+    #[arg(long, default_value_t = 800)]
+    width: u32,
 
-fn main() {
-    let event_loop = EventLoop::new().unwrap();
-    let (window, display) = backend::glutin::SimpleWindowBuilder::new()
-        .with_title("Bouncing ball !")
-        .build(&event_loop);
+    ///Height of the window, in pixels.
+    #[arg(long, default_value_t = 600)]
+    height: u32,
+
+    ///Title of the window.
+    #[arg(long, default_value = "Bouncing ball !")]
+    title: String,
+
+    ///Borderless fullscreen window.
+    #[arg(long)]
+    fullscreen: bool,
+
+    ///Do not wait for the screen refresh between frames, always the case with `--bench`.
+    #[arg(long)]
+    no_vsync: bool,
+
+    ///Scene to start with (JSON), instead of a world as big as the window.
+    #[arg(long)]
+    scene: Option<PathBuf>,
+
+    ///Start with N random balls instead of the spawner.
+    #[arg(long, value_name = "N", conflicts_with = "scene")]
+    balls: Option<usize>,
+
+    ///Seed of the random balls.
+    #[arg(long, default_value_t = 0)]
+    seed: u64,
+
+    ///Stop the spawner once there are this many balls.
+    #[arg(long)]
+    max_balls: Option<usize>,
+
+    ///Physics sub steps per step.
+    #[arg(long, default_value_t = PHYSIC_SUB_STEP, value_parser = clap::value_parser!(u16).range(1..))]
+    substeps: u16,
+
+    ///What the world does when the window is resized : fixed, grow or scale.
+    #[arg(long, env = "BOUNCING_BALL_RESIZE", default_value = "fixed")]
+    resize: ResizePolicy,
 
-    let frag_shad =
-        std::fs::read_to_string("./shaders/ball.frag").expect("could not load ./shaders/ball.frag");
-    let vert_shad = std::fs::read_to_string("./shaders/canva.vert")
-        .expect("could not load ./shaders/ball.vert");
-    let program = Program::from_source(&display, &vert_shad, &frag_shad, None)
-        .expect("could not compile shaders");
+    ///Directory of shaders overriding the embedded ones, reloaded when they change.
+    #[arg(long, env = "BOUNCING_BALL_SHADERS")]
+    shaders: Option<PathBuf>,
 
-    let mut canva = Canvas::new((0., 0.), program);
+    ///Key and mouse bindings (JSON).
+    #[arg(long, env = "BOUNCING_BALL_BINDINGS")]
+    bindings: Option<PathBuf>,
+
+    ///Write the physics stats of every step to this CSV file.
+    #[arg(long, env = "BOUNCING_BALL_STATS_CSV")]
+    stats_csv: Option<PathBuf>,
+
+    ///Run the physics for `--steps` steps without any window, and print how long it took.
+    #[arg(long)]
+    headless: bool,
+
+    ///Run `--steps` frames of each benchmark scene, write a JSON report of the physics and render
+    ///times and quit. Only the physics is measured with `--headless`.
+    #[arg(long)]
+    bench: bool,
+
+    ///Ball counts of the benchmark scenes.
+    #[arg(long, value_delimiter = ',', num_args = 1.., default_values_t = BENCH_COUNTS)]
+    bench_balls: Vec<usize>,
+
+    ///Write the benchmark report to this file instead of the standard output.
+    #[arg(long, requires = "bench")]
+    report: Option<PathBuf>,
+
+    ///Benchmark report to compare the new one with.
+    #[arg(long, requires = "bench")]
+    baseline: Option<PathBuf>,
+
+    ///Steps of `--headless`, or frames of each `--bench` scene.
+    #[arg(long, default_value_t = 600)]
+    steps: u32,
+}
+
+///Where `--bench` writes its report, and the report to compare it with.
+struct ReportFiles {
+    report: Option<PathBuf>,
+    baseline: Option<PathBuf>,
+}
+
+impl ReportFiles {
+    fn output(&self, report: &BenchReport) -> Result<(), BenchError> {
+        match &self.report {
+            Some(path) => report.save(path)?,
+            None => print!("{}", report.to_json()),
+        }
+        if let Some(path) = &self.baseline {
+            //on stderr, not to mix it with the report
+            for line in report.compare(&BenchReport::load(path)?)? {
+                eprintln!("{line}");
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+    let report_files = ReportFiles {
+        report: args.report.clone(),
+        baseline: args.baseline.clone(),
+    };
+
+    if args.bench && args.headless {
+        let report = BenchRun::new(&args.bench_balls, args.steps, args.substeps).run_headless();
+        return match report_files.output(&report) {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(e) => {
+                eprintln!("{e}");
+                ExitCode::FAILURE
+            }
+        };
+    }
+    if args.headless {
+        let world_size = (args.width as f32, args.height as f32);
+        return match create_world(&args, world_size) {
+            Ok((balls, world_size)) => run_headless(&args, balls, world_size),
+            Err(e) => {
+                eprintln!("{e}");
+                ExitCode::FAILURE
+            }
+        };
+    }
+
+    let event_loop = EventLoop::new().unwrap();
+    let (window, display) = match create_window(&event_loop, &args) {
+        Ok(window) => window,
+        Err(e) => {
+            eprintln!("could not create the window : {e}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let mut shaders = match &args.shaders {
+        Some(dir) => ShaderSources::with_dir(dir),
+        None => ShaderSources::embedded(),
+    };
+    let (renderer, debug_renderer, fluid_renderer) = match create_renderers(&display, &mut shaders)
+    {
+        Ok(renderers) => renderers,
+        Err(e) => {
+            eprintln!("{e}");
+            return ExitCode::FAILURE;
+        }
+    };
 
     let (b_x, b_y): (f32, f32) = (
         window.inner_size().width as f32,
         window.inner_size().height as f32,
     );
 
-    let boundary = Aabb::new((b_x / 2., b_y / 2.), b_x.max(b_y));
-    let balls = Balls::empty(boundary);
+    //the world is as big as the window at startup (or the scene), it does not change when the
+    //window is resized
+    let bench = args
+        .bench
+        .then(|| BenchRun::new(&args.bench_balls, args.steps, args.substeps));
+    let (balls, world_size) = match bench.as_ref().and_then(BenchRun::world) {
+        Some(balls) => (balls, BENCH_WORLD_SIZE),
+        None => match create_world(&args, (b_x, b_y)) {
+            Ok(world) => world,
+            Err(e) => {
+                eprintln!("{e}");
+                return ExitCode::FAILURE;
+            }
+        },
+    };
+    let physics_events = balls.events();
+    let bindings = match &args.bindings {
+        Some(path) => match Bindings::load(path) {
+            Ok(bindings) => bindings,
+            Err(e) => {
+                eprintln!("{}: {e}", path.display());
+                return ExitCode::FAILURE;
+            }
+        },
+        None => Bindings::default(),
+    };
 
     println!("window dimension :{b_x},{b_y}");
-    canva.push_elem(Box::new(balls));
 
     let mut app = App {
-        main_canva: canva,
+        balls,
+        world_size,
+        camera: Camera::fit(world_size, [b_x, b_y]),
+        resize_policy: args.resize,
+        renderer,
+        debug_renderer,
+        fluid_renderer,
+        debug_layers: DebugLayers::default(),
+        show_debug: false,
+        hud: Hud::default(),
+        show_hud: true,
+        shaders,
+        last_shader_check: std::time::Instant::now(),
 
+        clock: SimClock::default(),
+        history: History::default(),
+        rewind: None,
         dt: 0.,
         time: std::time::Instant::now(),
         frame_nb_since_startup: 0,
@@ -55,20 +260,178 @@ fn main() {
         f_pressed_time: std::time::Instant::now(),
         frame_nb_since_f: 0,
         benching_fps: false,
+        bench,
+        report_files,
         display,
-        _window: window,
+        window,
 
         mouse_position: (0., 0.),
-        mouse_cliking: false,
+        mouse_panning: false,
+        tools: Tools::default(),
+        bindings,
+        modifiers: ModifiersState::empty(),
+        held_actions: HashMap::new(),
+        show_help: false,
+        inspector: Inspector::default(),
+
+        physics_events,
+        nb_contacts_since_startup: 0,
+        nb_border_hits_since_startup: 0,
     };
 
     event_loop.set_control_flow(event_loop::ControlFlow::Poll);
     event_loop.run_app(&mut app).unwrap();
+
+    ExitCode::SUCCESS
+}
+
+///Window and OpenGL context, like `glium::backend::glutin::SimpleWindowBuilder` but with the
+///options of `args`.
+fn create_window(
+    event_loop: &EventLoop<()>,
+    args: &Args,
+) -> Result<(Window, Display<WindowSurface>), Box<dyn Error>> {
+    let mut attributes = Window::default_attributes()
+        .with_title(&args.title)
+        .with_inner_size(PhysicalSize::new(args.width, args.height));
+    if args.fullscreen {
+        attributes = attributes.with_fullscreen(Some(Fullscreen::Borderless(None)));
+    }
+
+    let (window, gl_config) = DisplayBuilder::new()
+        .with_window_attributes(Some(attributes))
+        .build(event_loop, ConfigTemplateBuilder::new(), |mut configs| {
+            configs.next().unwrap()
+        })?;
+    let window = window.ok_or("no window was created")?;
+    let window_handle = window.window_handle()?.as_raw();
+
+    let (width, height): (u32, u32) = window.inner_size().into();
+    let size = |pixels| NonZeroU32::new(pixels).unwrap_or(NonZeroU32::MIN);
+    let surface_attributes = SurfaceAttributesBuilder::<WindowSurface>::new().build(
+        window_handle,
+        size(width),
+        size(height),
+    );
+    let gl_display = gl_config.display();
+    let surface = unsafe { gl_display.create_window_surface(&gl_config, &surface_attributes)? };
+    let context_attributes = ContextAttributesBuilder::new().build(Some(window_handle));
+    let context = unsafe { gl_display.create_context(&gl_config, &context_attributes)? }
+        .make_current(&surface)?;
+
+    let swap_interval = if args.no_vsync || args.bench {
+        SwapInterval::DontWait
+    } else {
+        SwapInterval::Wait(NonZeroU32::MIN)
+    };
+    if let Err(e) = surface.set_swap_interval(&context, swap_interval) {
+        eprintln!("could not change the vsync : {e}");
+    }
+
+    Ok((window, Display::from_context_surface(context, surface)?))
+}
+
+///World given by `args`, and its size. Without scene nor random balls it is `world_size` big,
+///with the spawner on.
+fn create_world(args: &Args, world_size: (f32, f32)) -> Result<(Balls, (f32, f32)), String> {
+    let (mut balls, world_size) = if let Some(path) = &args.scene {
+        let scene = Scene::load(path).map_err(|e| format!("{}: {e}", path.display()))?;
+        (scene.build(), scene.border())
+    } else if let Some(count) = args.balls {
+        let scene = Scene::random(world_size.0, world_size.1, count, args.seed);
+        (scene.build(), world_size)
+    } else {
+        (Balls::empty(Balls::boundary_for(world_size)), world_size)
+    };
+    balls.max_balls = args.max_balls;
+    balls.set_substeps(args.substeps);
+
+    if let Some(path) = &args.stats_csv {
+        balls
+            .log_stats_to(path)
+            .unwrap_or_else(|e| println!("could not create stats file {} : {e}", path.display()));
+    }
+
+    Ok((balls, world_size))
+}
+
+///Run `args.steps` physics steps without window, and print the time they took.
+fn run_headless(args: &Args, mut balls: Balls, world_size: (f32, f32)) -> ExitCode {
+    let events = balls.events();
+    let border = balls.border_for(world_size);
+
+    let start = std::time::Instant::now();
+    for _ in 0..args.steps {
+        balls.simulate(STEP_DT, border);
+        // nobody listens to the events here, do not let them pile up
+        events.drain();
+    }
+    let elapsed = start.elapsed().as_secs_f32();
+    if let Err(e) = balls.finish_stats_log() {
+        eprintln!("could not write the stats file : {e}");
+    }
+
+    println!(
+        "{} steps in {elapsed:.3}s, {:.3}ms per step, {} balls",
+        args.steps,
+        elapsed * 1000. / args.steps.max(1) as f32,
+        balls.balls.len()
+    );
+
+    ExitCode::SUCCESS
 }
 
+fn create_renderers(
+    display: &Display<WindowSurface>,
+    shaders: &mut ShaderSources,
+) -> Result<(BallsRenderer, DebugRenderer, FluidRenderer), RenderError> {
+    let ball_program = shaders.load_program(display, shader::BALL_PROGRAM)?;
+    let background_program = shaders.load_program(display, shader::BACKGROUND_PROGRAM)?;
+    let debug_program = shaders.load_program(display, shader::DEBUG_PROGRAM)?;
+    let splat_program = shaders.load_program(display, shader::FLUID_SPLAT_PROGRAM)?;
+    let fluid_program = shaders.load_program(display, shader::FLUID_PROGRAM)?;
+
+    Ok((
+        BallsRenderer::new(display, ball_program, background_program)?,
+        DebugRenderer::new(display, debug_program)?,
+        FluidRenderer::new(display, splat_program, fluid_program)?,
+    ))
+}
+
+///How often the shaders override directory is checked for modified files.
+const SHADER_CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_millis(500);
+
+///Zoom factor of one line of mouse wheel.
+const ZOOM_PER_LINE: f32 = 1.1;
+///Pixels of touchpad scrolling counted as one line of mouse wheel.
+const PIXELS_PER_LINE: f32 = 50.;
+
+///Range of the size of the balls added by the spawn tool.
+const MIN_SPAWN_SIZE: f32 = 2.;
+const MAX_SPAWN_SIZE: f32 = 50.;
+
 struct App {
-    main_canva: Canvas,
+    balls: Balls,
+    ///Size of the world, in world units.
+    world_size: (f32, f32),
+    camera: Camera,
+    resize_policy: ResizePolicy,
+    renderer: BallsRenderer,
+    debug_renderer: DebugRenderer,
+    ///Draws the circles as a liquid in fluid mode.
+    fluid_renderer: FluidRenderer,
+    debug_layers: DebugLayers,
+    show_debug: bool,
+    hud: Hud,
+    show_hud: bool,
+    shaders: ShaderSources,
+    last_shader_check: std::time::Instant,
 
+    clock: SimClock,
+    history: History,
+    ///Frame of the history shown while rewinding.
+    rewind: Option<usize>,
+    ///Real duration of the last frame, in seconds.
     dt: f32,
     time: std::time::Instant,
     frame_nb_since_startup: u32,
@@ -76,22 +439,392 @@ struct App {
     f_pressed_time: std::time::Instant,
     frame_nb_since_f: u32,
     benching_fps: bool,
+    bench: Option<BenchRun>,
+    report_files: ReportFiles,
 
     display: Display<WindowSurface>,
-    _window: Window,
+    window: Window,
 
+    ///In pixels.
     mouse_position: (f32, f32),
-    mouse_cliking: bool,
+    mouse_panning: bool,
+    tools: Tools,
+
+    bindings: Bindings,
+    modifiers: ModifiersState,
+    ///Held actions, by the input that started them.
+    held_actions: HashMap<Input, Action>,
+    show_help: bool,
+    inspector: Inspector,
+
+    physics_events: EventQueue,
+    nb_contacts_since_startup: u32,
+    nb_border_hits_since_startup: u32,
 }
 
 impl App {
-    fn draw(&mut self) {
+    ///Issue the draw calls of the frame, which is swapped by `Frame::finish`.
+    fn draw(&mut self) -> Frame {
         let mut target = self.display.draw();
 
         target.clear_color(0.03, 0.03, 0.03, 1.);
-        self.main_canva.draw(&self.display, &mut target).unwrap();
+        if self.balls.fluid().is_some() {
+            self.renderer.draw_background(&mut target).unwrap();
+            self.fluid_renderer
+                .draw(&self.display, &mut target, &self.camera, &self.balls)
+                .unwrap();
+        } else {
+            self.renderer
+                .draw(&self.display, &mut target, &self.camera, &self.balls)
+                .unwrap();
+        }
+        let shapes = shapes::shapes_mesh(&self.balls);
+        self.debug_renderer
+            .draw_triangles(&self.display, &mut target, &self.camera, &shapes)
+            .unwrap();
+        let mut lines = if self.show_debug {
+            DebugLines::build(&self.balls, self.debug_layers)
+        } else {
+            DebugLines::default()
+        };
+        lines.soft_bodies(&self.balls);
+        self.tools.overlay(&self.balls, &mut lines);
+        if !lines.is_empty() {
+            self.debug_renderer
+                .draw(&self.display, &mut target, &self.camera, &lines)
+                .unwrap();
+        }
+        let screen = Camera::screen(self.camera.viewport);
+        if self.show_hud {
+            let mesh = self.hud.mesh(&self.hud_info());
+            self.debug_renderer
+                .draw_triangles(&self.display, &mut target, &screen, &mesh)
+                .unwrap();
+        }
+        if let Some(index) = self.rewind {
+            let last = self.history.len().saturating_sub(1);
+            let behind = self.history.time(last).unwrap_or(0.) - self.balls.time();
+            let mesh = hud::timeline_mesh(
+                index as f32 / last.max(1) as f32,
+                &format!("REWIND -{behind:.2}S  FRAME {index}/{last}"),
+                self.camera.viewport,
+            );
+            self.debug_renderer
+                .draw_triangles(&self.display, &mut target, &screen, &mesh)
+                .unwrap();
+        }
+        let selected = self.tools.selected_balls(&self.balls);
+        let mesh = self.inspector.mesh(&selected, self.camera.viewport);
+        self.debug_renderer
+            .draw_triangles(&self.display, &mut target, &screen, &mesh)
+            .unwrap();
+        if self.show_help {
+            let mesh = hud::centered_text_mesh(&self.bindings.help_lines(), self.camera.viewport);
+            self.debug_renderer
+                .draw_triangles(&self.display, &mut target, &screen, &mesh)
+                .unwrap();
+        }
+
+        target
+    }
 
-        target.finish().unwrap()
+    ///World coordinates of a point of the window.
+    fn to_world(&self, screen: (f32, f32)) -> [f32; 2] {
+        self.camera.screen_to_world([screen.0, screen.1])
+    }
+
+    fn handle_input(
+        &mut self,
+        event_loop: &event_loop::ActiveEventLoop,
+        inputs: &[Input],
+        state: ElementState,
+        repeat: bool,
+    ) {
+        match state {
+            ElementState::Pressed => {
+                let Some(action) = self.bindings.action(inputs, self.modifiers) else {
+                    return;
+                };
+                if action.is_held() {
+                    if repeat {
+                        return;
+                    }
+                    if let Some(&input) = inputs.first() {
+                        self.held_actions.insert(input, action);
+                    }
+                }
+                self.action_pressed(event_loop, action);
+            }
+            ElementState::Released => {
+                for input in inputs {
+                    if let Some(action) = self.held_actions.remove(input) {
+                        self.action_released(action);
+                    }
+                }
+            }
+        }
+    }
+
+    fn action_pressed(&mut self, event_loop: &event_loop::ActiveEventLoop, action: Action) {
+        match action {
+            Action::Quit => event_loop.exit(),
+            Action::FpsReport => self.print_avg_fps(),
+            Action::Bench => self.starting_fps_bench(),
+            Action::ToggleDebug => self.show_debug = !self.show_debug,
+            Action::ToggleHud => self.show_hud = !self.show_hud,
+            Action::Help => self.show_help = !self.show_help,
+            Action::Pause => self.resume_or_pause(),
+            Action::Step => {
+                if self.rewind.is_some() {
+                    self.scrub(self.scrub_frames())
+                } else {
+                    self.clock.step()
+                }
+            }
+            Action::Rewind => self.scrub(-self.scrub_frames()),
+            Action::Faster => self.clock.faster(),
+            Action::Slower => self.clock.slower(),
+            Action::NormalSpeed => self.clock.reset_speed(),
+            Action::ToggleSpawner => self.balls.auto_spawn = !self.balls.auto_spawn,
+            Action::ToggleFluid => self.toggle_fluid(),
+            Action::ToolGrab => self.set_tool(Tool::Grab),
+            Action::ToolThrow => self.set_tool(Tool::Throw),
+            Action::ToolDelete => self.set_tool(Tool::Delete),
+            Action::ToolSpawn => self.set_tool(Tool::Spawn),
+            Action::ToolSelect => self.set_tool(Tool::Select),
+            Action::ToolSoft => self.set_tool(Tool::Soft),
+            Action::SpawnSmaller => {
+                self.tools.spawn.size = (self.tools.spawn.size - 1.).max(MIN_SPAWN_SIZE)
+            }
+            Action::SpawnBigger => {
+                self.tools.spawn.size = (self.tools.spawn.size + 1.).min(MAX_SPAWN_SIZE)
+            }
+            Action::SpawnShape => self.tools.spawn.shape = self.tools.spawn.shape.next(),
+            Action::SoftKind => self.tools.soft = self.tools.soft.next(),
+            Action::InspectorPrevious => self.inspector.previous_field(),
+            Action::InspectorNext => self.inspector.next_field(),
+            Action::InspectorDecrease => self.edit_selection(-1.),
+            Action::InspectorIncrease => self.edit_selection(1.),
+            Action::UseTool => {
+                let (world_pos, time) = (self.to_world(self.mouse_position), self.tool_time());
+                self.tools.press(&mut self.balls, world_pos, time);
+            }
+            Action::Pan => self.mouse_panning = true,
+        }
+    }
+
+    fn action_released(&mut self, action: Action) {
+        match action {
+            Action::Bench => self.ending_fps_bench(),
+            Action::UseTool => {
+                let time = self.tool_time();
+                self.tools.release(&mut self.balls, time);
+            }
+            Action::Pan => self.mouse_panning = false,
+            _ => (),
+        }
+    }
+
+    ///Edit the field of the inspector for the selected balls, shift makes 10 times bigger steps.
+    fn edit_selection(&mut self, steps: f32) {
+        let steps = if self.tools.additive {
+            steps * 10.
+        } else {
+            steps
+        };
+        self.inspector
+            .edit(&mut self.balls, self.tools.selection(), steps);
+    }
+
+    ///Frames moved by one press of the rewind keys.
+    fn scrub_frames(&self) -> isize {
+        if self.tools.additive { 10 } else { 1 }
+    }
+
+    ///Show the frame `frames` away from the current one in the history, pausing the simulation.
+    fn scrub(&mut self, frames: isize) {
+        let Some(last) = self.history.len().checked_sub(1) else {
+            return;
+        };
+        let index = self
+            .rewind
+            .unwrap_or(last)
+            .saturating_add_signed(frames)
+            .min(last);
+        let Some(snapshot) = self.history.get(index) else {
+            return;
+        };
+
+        self.balls.restore(&snapshot);
+        //the window may have been resized since
+        if snapshot.border != self.world_size {
+            self.balls
+                .resize_world(snapshot.border, self.world_size, false);
+        }
+        self.rewind = Some(index);
+        self.clock.paused = true;
+    }
+
+    ///Resume from the frame shown while rewinding, forgetting the frames after it, or toggle the
+    ///pause.
+    fn resume_or_pause(&mut self) {
+        match self.rewind.take() {
+            Some(index) => {
+                self.history.truncate(index + 1);
+                self.clock.paused = false;
+            }
+            None => self.clock.toggle_pause(),
+        }
+    }
+
+    fn finish_stats_log(&mut self) {
+        if let Err(e) = self.balls.finish_stats_log() {
+            eprintln!("could not write the stats file : {e}");
+        }
+    }
+
+    ///Replace the world by `balls`, the view fitting it.
+    fn load_world(&mut self, balls: Balls, world_size: (f32, f32)) {
+        self.finish_stats_log();
+        self.balls = balls;
+        self.physics_events = self.balls.events();
+        self.world_size = world_size;
+        self.camera = Camera::fit(world_size, self.camera.viewport);
+        self.history.clear();
+        self.rewind = None;
+    }
+
+    ///Load the next benchmark scene, or write the report and quit after the last one.
+    fn next_bench_scene(&mut self, event_loop: &event_loop::ActiveEventLoop) {
+        let Some(bench) = &self.bench else {
+            return;
+        };
+        match bench.world() {
+            Some(balls) => self.load_world(balls, BENCH_WORLD_SIZE),
+            None => {
+                if let Err(e) = self.report_files.output(&bench.report()) {
+                    eprintln!("{e}");
+                }
+                event_loop.exit();
+            }
+        }
+    }
+
+    ///Switch between rigid balls and a fluid of particles of their mean size.
+    fn toggle_fluid(&mut self) {
+        let fluid = match self.balls.fluid() {
+            Some(_) => None,
+            None => {
+                let sizes: Vec<f32> = self.balls.iter().map(|ball| ball.size).collect();
+                let radius = if sizes.is_empty() {
+                    self.tools.spawn.size
+                } else {
+                    sizes.iter().sum::<f32>() / sizes.len() as f32
+                };
+                Some(FluidParams::new(radius))
+            }
+        };
+        self.balls.set_fluid(fluid);
+    }
+
+    ///Time given to the tools, for the cursor velocity.
+    fn tool_time(&self) -> f32 {
+        self.start_time.elapsed().as_secs_f32()
+    }
+
+    fn set_tool(&mut self, tool: Tool) {
+        let time = self.tool_time();
+        self.tools.set_tool(tool, &mut self.balls, time);
+    }
+
+    ///Frames per second since startup, 0 before any time has passed.
+    fn average_fps(&self) -> f32 {
+        fps(
+            self.frame_nb_since_startup,
+            self.time.duration_since(self.start_time),
+        )
+    }
+
+    fn hud_info(&self) -> HudInfo {
+        let on_off = |on: bool| if on { "ON" } else { "OFF" }.to_owned();
+
+        HudInfo {
+            average_fps: self.average_fps(),
+            ball_count: self.balls.balls.len(),
+            parameters: vec![
+                ("GRAVITY", format!("{GRAVITY_CONST:.2}")),
+                ("FRICTION", format!("{FRICTION_COEF}")),
+                ("SUBSTEPS", format!("{}", self.balls.substeps())),
+                ("SPAWN", on_off(self.balls.auto_spawn)),
+                ("FLUID", on_off(self.balls.fluid().is_some())),
+                ("RESIZE", format!("{:?}", self.resize_policy)),
+                ("DEBUG", on_off(self.show_debug)),
+                ("SPEED", format!("X{}", self.clock.time_scale())),
+                ("PAUSED", on_off(self.clock.paused)),
+                ("TOOL", self.tools.tool.name().to_uppercase()),
+                ("SIZE", format!("{}", self.tools.spawn.size)),
+                ("SHAPE", self.tools.spawn.shape.name().to_uppercase()),
+                ("SOFT", self.tools.soft.name().to_uppercase()),
+            ],
+        }
+    }
+
+    ///Recompile the shaders if their files changed, keeping the current ones on error.
+    fn reload_shaders_if_changed(&mut self) {
+        if self.shaders.dir().is_none() || self.last_shader_check.elapsed() < SHADER_CHECK_INTERVAL
+        {
+            return;
+        }
+        self.last_shader_check = std::time::Instant::now();
+        if !self.shaders.changed() {
+            return;
+        }
+
+        match self.reload_shaders() {
+            Ok(()) => println!("shaders reloaded"),
+            Err(e) => eprintln!("{e}, keeping the previous shaders"),
+        }
+    }
+
+    fn reload_shaders(&mut self) -> Result<(), RenderError> {
+        let ball_program = self
+            .shaders
+            .load_program(&self.display, shader::BALL_PROGRAM)?;
+        let background_program = self
+            .shaders
+            .load_program(&self.display, shader::BACKGROUND_PROGRAM)?;
+        let debug_program = self
+            .shaders
+            .load_program(&self.display, shader::DEBUG_PROGRAM)?;
+        let splat_program = self
+            .shaders
+            .load_program(&self.display, shader::FLUID_SPLAT_PROGRAM)?;
+        let fluid_program = self
+            .shaders
+            .load_program(&self.display, shader::FLUID_PROGRAM)?;
+
+        self.renderer
+            .set_programs(ball_program, background_program)?;
+        self.debug_renderer.set_program(debug_program)?;
+        self.fluid_renderer
+            .set_programs(splat_program, fluid_program)
+    }
+
+    fn handle_physics_events(&mut self) {
+        for event in self.physics_events.drain() {
+            match event {
+                PhysicsEvent::ContactBegin { .. } => self.nb_contacts_since_startup += 1,
+                PhysicsEvent::BorderHit { .. } => self.nb_border_hits_since_startup += 1,
+                PhysicsEvent::ContactPersist { .. }
+                | PhysicsEvent::ContactEnd { .. }
+                | PhysicsEvent::Spawned { .. }
+                | PhysicsEvent::Removed { .. }
+                | PhysicsEvent::Sleep { .. }
+                | PhysicsEvent::Wake { .. }
+                | PhysicsEvent::SensorEnter { .. }
+                | PhysicsEvent::SensorExit { .. } => (),
+            }
+        }
     }
 }
 
@@ -108,58 +841,72 @@ impl ApplicationHandler for App {
     ) {
         match event {
             WindowEvent::CloseRequested => event_loop.exit(),
-            WindowEvent::KeyboardInput {
-                device_id: _,
-                event,
-                is_synthetic: _,
-            } => match event.physical_key {
-                keyboard::PhysicalKey::Code(key_code) => match (event.state, key_code) {
-                    (ElementState::Pressed, keyboard::KeyCode::Escape) => event_loop.exit(),
-                    (ElementState::Pressed, keyboard::KeyCode::KeyF) => self.print_avg_fps(),
-                    (ElementState::Pressed, keyboard::KeyCode::KeyD) => self.starting_fps_bench(),
-                    (ElementState::Released, keyboard::KeyCode::KeyD) => self.ending_fps_bench(),
-                    _ => (),
-                },
-                keyboard::PhysicalKey::Unidentified(_) => (),
-            },
+            WindowEvent::KeyboardInput { event, .. } => {
+                let mut inputs = vec![];
+                if let keyboard::PhysicalKey::Code(key_code) = event.physical_key {
+                    inputs.push(Input::Key(key_code));
+                }
+                if let keyboard::Key::Character(text) = &event.logical_key {
+                    inputs.extend(
+                        text.chars()
+                            .flat_map(char::to_lowercase)
+                            .take(1)
+                            .map(Input::Char),
+                    );
+                }
+                self.handle_input(event_loop, &inputs, event.state, event.repeat);
+            }
+
+            WindowEvent::ModifiersChanged(modifiers) => {
+                self.modifiers = modifiers.state();
+                self.tools.additive = self.modifiers.shift_key();
+            }
 
             WindowEvent::Resized(new_size) => {
                 self.display.resize(new_size.into());
-                self.main_canva.on_window_resized(new_size.into());
-            }
-            WindowEvent::Moved(pos) => {
-                self.main_canva.on_window_moved(pos.into());
+                let viewport = [new_size.width as f32, new_size.height as f32];
+                let world_size =
+                    self.resize_policy
+                        .resize(self.world_size, &mut self.camera, viewport);
+                if world_size != self.world_size {
+                    self.balls.resize_world(
+                        self.world_size,
+                        world_size,
+                        self.resize_policy.scales_balls(),
+                    );
+                    self.world_size = world_size;
+                }
             }
 
             WindowEvent::CursorMoved {
                 device_id: _,
                 position,
             } => {
-                let new_pos = position.into();
-                //draging
-                if self.mouse_cliking && self.main_canva.is_absolute_coord_in(self.mouse_position) {
-                    self.main_canva.on_drag(self.mouse_position.into(), new_pos);
+                let new_pos: (f32, f32) = position.into();
+                if self.mouse_panning {
+                    self.camera.pan([
+                        new_pos.0 - self.mouse_position.0,
+                        new_pos.1 - self.mouse_position.1,
+                    ]);
                 }
+                let (world_pos, time) = (self.to_world(new_pos), self.tool_time());
+                self.tools.cursor_moved(&mut self.balls, world_pos, time);
 
-                self.mouse_position = new_pos.into();
+                self.mouse_position = new_pos;
+            }
+            WindowEvent::MouseInput { state, button, .. } => {
+                self.handle_input(event_loop, &[Input::Mouse(button)], state, false)
+            }
+            WindowEvent::MouseWheel { delta, .. } => {
+                let lines = match delta {
+                    MouseScrollDelta::LineDelta(_, y) => y,
+                    MouseScrollDelta::PixelDelta(pos) => pos.y as f32 / PIXELS_PER_LINE,
+                };
+                self.camera.zoom_at(
+                    [self.mouse_position.0, self.mouse_position.1],
+                    ZOOM_PER_LINE.powf(lines),
+                );
             }
-            WindowEvent::MouseInput {
-                device_id: _,
-                state,
-                button,
-            } => match (button, state) {
-                (MouseButton::Left, ElementState::Pressed) => {
-                    self.mouse_cliking = true;
-                    if self.main_canva.is_absolute_coord_in(self.mouse_position) {
-                        self.main_canva.on_click(self.mouse_position);
-                    }
-                }
-                (MouseButton::Left, ElementState::Released) => {
-                    self.mouse_cliking = false;
-                    self.main_canva.on_click_release();
-                }
-                _ => (),
-            },
 
             _ => (),
         };
@@ -168,12 +915,13 @@ impl ApplicationHandler for App {
     fn exiting(&mut self, event_loop: &event_loop::ActiveEventLoop) {
         println!("exiting...");
         self.print_avg_fps();
+        self.finish_stats_log();
         event_loop.exit();
     }
 
     fn new_events(
         &mut self,
-        _event_loop: &event_loop::ActiveEventLoop,
+        event_loop: &event_loop::ActiveEventLoop,
         cause: glium::winit::event::StartCause,
     ) {
         match cause {
@@ -185,35 +933,63 @@ impl ApplicationHandler for App {
                 self.time = now;
                 self.frame_nb_since_startup += 1;
 
-                //ball
-                self.main_canva.update(&DUMMY_CANVA_INFO, self.dt);
+                //ball, only the steps are timed
+                let border = self.balls.border_for(self.world_size);
+                let mut physics_time = 0.;
+                if self.bench.is_some() {
+                    //the same steps on every run
+                    let physics_start = std::time::Instant::now();
+                    self.balls.simulate(STEP_DT, border);
+                    physics_time = physics_start.elapsed().as_secs_f32();
+                } else {
+                    for step_dt in self.clock.advance(self.dt) {
+                        let physics_start = std::time::Instant::now();
+                        self.balls.simulate(step_dt, border);
+                        physics_time += physics_start.elapsed().as_secs_f32();
+                        self.history.record(&self.balls);
+                    }
+                }
+                self.handle_physics_events();
 
                 //draw
-                self.draw();
+                self.reload_shaders_if_changed();
+                let render_start = std::time::Instant::now();
+                let target = self.draw();
+                if self.bench.is_some() {
+                    //wait for the GPU, so the bench measures the rendering and not its submission
+                    self.display.finish();
+                }
+                //measured before the swap, which waits for vsync
+                let render_time = render_start.elapsed().as_secs_f32();
+                target.finish().unwrap();
+                self.hud.record(FrameTime {
+                    total: self.dt,
+                    physics: physics_time,
+                    render: render_time,
+                });
 
                 if self.benching_fps {
                     self.frame_nb_since_f += 1;
                 }
+                let scene_done = self
+                    .bench
+                    .as_mut()
+                    .is_some_and(|bench| bench.record(physics_time, Some(render_time)));
+                if scene_done {
+                    self.next_bench_scene(event_loop);
+                }
             }
             _ => (),
         }
     }
 }
 
-const DUMMY_CANVA_INFO: CanvasData = CanvasData {
-    size: (0., 0.),
-    position: (0., 0.),
-    frame_nb: 0,
-
-    window_resolution: (0, 0),
-};
-
 impl App {
     fn print_avg_fps(&self) {
+        println!("average fps since startup :{}", self.average_fps());
         println!(
-            "average fps since startup :{}",
-            self.frame_nb_since_startup as f32
-                / self.time.duration_since(self.start_time).as_secs_f32()
+            "contacts since startup :{}, border hits since startup :{}",
+            self.nb_contacts_since_startup, self.nb_border_hits_since_startup
         );
     }
 
@@ -229,11 +1005,23 @@ impl App {
     fn ending_fps_bench(&mut self) {
         println!(
             "average fps :{}",
-            self.frame_nb_since_f as f32
-                / self.time.duration_since(self.f_pressed_time).as_secs_f32()
+            fps(
+                self.frame_nb_since_f,
+                self.time.duration_since(self.f_pressed_time)
+            )
         );
 
         self.benching_fps = false;
         self.frame_nb_since_f = 0;
     }
 }
+
+///`frames` frames per `duration`, 0 if no time has passed.
+fn fps(frames: u32, duration: std::time::Duration) -> f32 {
+    let duration = duration.as_secs_f32();
+    if duration <= 0. {
+        return 0.;
+    }
+
+    frames as f32 / duration
+}
@@ -0,0 +1,77 @@
+//! Simulation clock : turns the real duration of a frame into the physics steps to run, with pause,
+//! single stepping and time scaling. Everything driven by `Balls::time` (like the spawner) follows
+//! the simulation time.
+
+///Time scales available, from slow motion to fast forward.
+pub const TIME_SCALES: [f32; 7] = [0.1, 0.25, 0.5, 1., 2., 4., 8.];
+const NORMAL_SPEED: usize = 3;
+
+///Duration of a single step, in simulation seconds.
+pub const STEP_DT: f32 = 1. / 60.;
+///Longest step given to the physics, longer frames are split.
+pub const MAX_STEP_DT: f32 = 1. / 60.;
+///Steps run in one frame at most, the rest of the time is dropped so a slow frame (or a dragged
+///window) does not make the next ones slower.
+pub const MAX_STEPS_PER_FRAME: usize = 16;
+
+#[derive(Clone, Debug)]
+pub struct SimClock {
+    pub paused: bool,
+    ///Index in `TIME_SCALES`.
+    speed: usize,
+    ///Single steps asked while paused, run on the next frame.
+    pending_steps: u32,
+}
+
+impl Default for SimClock {
+    fn default() -> Self {
+        Self {
+            paused: false,
+            speed: NORMAL_SPEED,
+            pending_steps: 0,
+        }
+    }
+}
+
+impl SimClock {
+    pub fn time_scale(&self) -> f32 {
+        TIME_SCALES[self.speed]
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+        self.pending_steps = 0;
+    }
+
+    ///Run one step of `STEP_DT` on the next frame, pausing the simulation.
+    pub fn step(&mut self) {
+        self.paused = true;
+        self.pending_steps += 1;
+    }
+
+    pub fn faster(&mut self) {
+        self.speed = (self.speed + 1).min(TIME_SCALES.len() - 1);
+    }
+
+    pub fn slower(&mut self) {
+        self.speed = self.speed.saturating_sub(1);
+    }
+
+    pub fn reset_speed(&mut self) {
+        self.speed = NORMAL_SPEED;
+    }
+
+    ///Steps to give to `Balls::simulate` for a frame of `real_dt` seconds.
+    pub fn advance(&mut self, real_dt: f32) -> Vec<f32> {
+        if self.paused {
+            let steps = std::mem::take(&mut self.pending_steps) as usize;
+            return vec![STEP_DT; steps.min(MAX_STEPS_PER_FRAME)];
+        }
+
+        let sim_dt = real_dt.max(0.) * self.time_scale();
+        let nb_steps = ((sim_dt / MAX_STEP_DT).ceil() as usize).clamp(1, MAX_STEPS_PER_FRAME);
+        let step = (sim_dt / nb_steps as f32).min(MAX_STEP_DT);
+
+        vec![step; nb_steps]
+    }
+}
@@ -0,0 +1,298 @@
+//! Narrow phase between any two shapes : separating axis test on their cores, clipping of the
+//! incident edge against the reference face, and closest features when only the radii overlap.
+
+use my_glium_util::math::{EuclidianSpace, Vec2};
+
+use super::geometry::{Shape, closest_on_segment, cross, segment_distance_sq};
+
+///Contact points a manifold holds at most, enough for two faces resting on each other.
+pub const MAX_CONTACT_POINTS: usize = 2;
+///Contact points closer than this are merged.
+const MERGE_DISTANCE: f32 = 0.01;
+///Tolerance favoring the faces of `a` as the reference, so the manifold does not flip between
+///nearly equal faces from one step to the next.
+const REFERENCE_TOLERANCE: f32 = 0.01;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ContactPoint {
+    ///Middle of the two surfaces, in world coordinates.
+    pub position: Vec2,
+    ///How deep the shapes are inside each other at this point, along the normal.
+    pub depth: f32,
+}
+
+///Where two shapes touch.
+#[derive(Debug, Clone, Copy)]
+pub struct Manifold {
+    ///Unit vector from the first shape toward the second.
+    pub normal: Vec2,
+    points: [ContactPoint; MAX_CONTACT_POINTS],
+    len: usize,
+}
+
+impl Manifold {
+    pub fn new(normal: Vec2) -> Self {
+        Self {
+            normal,
+            points: [ContactPoint {
+                position: normal,
+                depth: 0.,
+            }; MAX_CONTACT_POINTS],
+            len: 0,
+        }
+    }
+
+    pub fn points(&self) -> &[ContactPoint] {
+        &self.points[..self.len]
+    }
+
+    ///Deepest contact point.
+    pub fn depth(&self) -> f32 {
+        self.points().iter().map(|p| p.depth).fold(0., f32::max)
+    }
+
+    ///Add a contact point, merged with an existing one if they are at the same place. Once full,
+    ///the shallowest point of the manifold is replaced if the new one is deeper.
+    pub fn push(&mut self, point: ContactPoint) {
+        if let Some(same) = self.points[..self.len]
+            .iter_mut()
+            .find(|p| p.position.distance(point.position) < MERGE_DISTANCE)
+        {
+            same.depth = same.depth.max(point.depth);
+            return;
+        }
+        if self.len < MAX_CONTACT_POINTS {
+            self.points[self.len] = point;
+            self.len += 1;
+            return;
+        }
+
+        let shallowest = (0..self.len)
+            .min_by(|&i, &j| self.points[i].depth.total_cmp(&self.points[j].depth))
+            .expect("a full manifold has points");
+        if self.points[shallowest].depth < point.depth {
+            self.points[shallowest] = point;
+        }
+    }
+
+    fn flipped(mut self) -> Self {
+        self.normal = self.normal * -1.;
+        self
+    }
+}
+
+///Where `a` and `b` touch, `None` if they do not.
+pub fn collide(a: &Shape, b: &Shape) -> Option<Manifold> {
+    let (core_a, core_b) = (a.core(), b.core());
+
+    if cores_overlap(core_a, core_b) {
+        clip_faces(a, b)
+    } else {
+        closest_features(a, b)
+    }
+}
+
+fn cores_overlap(a: &[Vec2], b: &[Vec2]) -> bool {
+    let touching = || core_distance(a, b).0 <= f32::EPSILON;
+
+    if a.len() == 1 || b.len() == 1 {
+        let inside = |polygon: &[Vec2], points: &[Vec2]| {
+            polygon.len() >= 3
+                && points.iter().any(|&p| {
+                    edges(polygon).all(|(start, end)| cross(end - start, p - start) >= 0.)
+                })
+        };
+        return inside(a, b) || inside(b, a) || touching();
+    }
+
+    //no separating axis among the edge normals (crossing segments have no point near the other)
+    let separated = |reference, incident| {
+        least_penetration(reference, incident).is_some_and(|(separation, _)| separation >= 0.)
+    };
+    !(separated(a, b) || separated(b, a)) || touching()
+}
+
+///Segments of a core : the edges of a polygon, the segment both ways, nothing for a point.
+fn edges(core: &[Vec2]) -> impl Iterator<Item = (Vec2, Vec2)> + '_ {
+    let count = if core.len() >= 2 { core.len() } else { 0 };
+    (0..count).map(move |i| (core[i], core[(i + 1) % core.len()]))
+}
+
+///Normal of an edge pointing out of the core.
+fn outward_normal(start: Vec2, end: Vec2) -> Vec2 {
+    let edge = end - start;
+    let normal: Vec2 = [edge[1], -edge[0]].into();
+
+    normal / normal.dot(normal).sqrt().max(f32::EPSILON)
+}
+
+///Distance between two cores not overlapping, with their closest points.
+fn core_distance(a: &[Vec2], b: &[Vec2]) -> (f32, Vec2, Vec2) {
+    let closest_on = |core: &[Vec2], p: Vec2| {
+        edges(core)
+            .map(|(start, end)| closest_on_segment(p, start, end))
+            .chain((core.len() == 1).then_some(core[0]))
+            .min_by(|x, y| (*x - p).dot(*x - p).total_cmp(&((*y - p).dot(*y - p))))
+            .expect("a core has at least a point")
+    };
+
+    let from_a = a.iter().map(|&p| (p, closest_on(b, p)));
+    let from_b = b.iter().map(|&q| (closest_on(a, q), q));
+    let (p, q) = from_a
+        .chain(from_b)
+        .min_by(|(p1, q1), (p2, q2)| {
+            (*q1 - *p1)
+                .dot(*q1 - *p1)
+                .total_cmp(&(*q2 - *p2).dot(*q2 - *p2))
+        })
+        .expect("a core has at least a point");
+
+    (p.distance(q), p, q)
+}
+
+///Shapes whose cores are apart but whose radii overlap : the contact points are the points of
+///each core touching the other shape.
+fn closest_features(a: &Shape, b: &Shape) -> Option<Manifold> {
+    let (core_a, core_b) = (a.core(), b.core());
+    let (radius_a, radius_b) = (a.radius(), b.radius());
+    let radii = radius_a + radius_b;
+
+    let (distance, closest_a, closest_b) = core_distance(core_a, core_b);
+    if distance >= radii {
+        return None;
+    }
+    let normal = (closest_b - closest_a) / distance.max(f32::EPSILON);
+
+    let mut manifold = Manifold::new(normal);
+    for &p in core_a {
+        let distance = segment_distance_sq_to(core_b, p).sqrt();
+        if distance < radii {
+            manifold.push(ContactPoint {
+                position: p + normal * ((distance + radius_a - radius_b) / 2.),
+                depth: radii - distance,
+            });
+        }
+    }
+    for &q in core_b {
+        let distance = segment_distance_sq_to(core_a, q).sqrt();
+        if distance < radii {
+            manifold.push(ContactPoint {
+                position: q - normal * ((distance + radius_b - radius_a) / 2.),
+                depth: radii - distance,
+            });
+        }
+    }
+
+    Some(manifold)
+}
+
+///Squared distance between a point and the outline of a core.
+fn segment_distance_sq_to(core: &[Vec2], p: Vec2) -> f32 {
+    if core.len() == 1 {
+        return (p - core[0]).dot(p - core[0]);
+    }
+
+    edges(core)
+        .map(|(start, end)| segment_distance_sq(p, start, end))
+        .fold(f32::INFINITY, f32::min)
+}
+
+///Face of `reference` along which `incident` is the least inside, with its separation.
+fn least_penetration(reference: &[Vec2], incident: &[Vec2]) -> Option<(f32, usize)> {
+    edges(reference)
+        .enumerate()
+        .map(|(i, (start, end))| {
+            let normal = outward_normal(start, end);
+            let separation = incident
+                .iter()
+                .map(|&p| normal.dot(p - start))
+                .fold(f32::INFINITY, f32::min);
+            (separation, i)
+        })
+        .max_by(|(x, _), (y, _)| x.total_cmp(y))
+}
+
+///Shapes whose cores overlap : the contact points are the incident edge clipped to the sides of
+///the reference face.
+fn clip_faces(a: &Shape, b: &Shape) -> Option<Manifold> {
+    let (core_a, core_b) = (a.core(), b.core());
+    let face_a = least_penetration(core_a, core_b);
+    let face_b = least_penetration(core_b, core_a);
+
+    match (face_a, face_b) {
+        (None, None) => {
+            //two points at the same place
+            let mut manifold = Manifold::new([0., 1.].into());
+            manifold.push(ContactPoint {
+                position: core_a[0],
+                depth: a.radius() + b.radius(),
+            });
+            Some(manifold)
+        }
+        (Some((separation_a, face)), Some((separation_b, _)))
+            if separation_a + REFERENCE_TOLERANCE >= separation_b =>
+        {
+            clip_against(a, b, face)
+        }
+        (Some((_, face)), None) => clip_against(a, b, face),
+        (_, Some((_, face))) => clip_against(b, a, face).map(Manifold::flipped),
+    }
+}
+
+///Manifold from the face `face` of `reference` toward `incident`.
+fn clip_against(reference: &Shape, incident: &Shape, face: usize) -> Option<Manifold> {
+    let (core_ref, core_inc) = (reference.core(), incident.core());
+    let start = core_ref[face];
+    let end = core_ref[(face + 1) % core_ref.len()];
+    let normal = outward_normal(start, end);
+    let radii = reference.radius() + incident.radius();
+
+    //the incident feature is the edge the most facing the reference face, or the single point
+    let feature: [Vec2; 2] = match core_inc.len() {
+        1 => [core_inc[0]; 2],
+        _ => {
+            let (inc_start, inc_end) = edges(core_inc)
+                .min_by(|(s1, e1), (s2, e2)| {
+                    normal
+                        .dot(outward_normal(*s1, *e1))
+                        .total_cmp(&normal.dot(outward_normal(*s2, *e2)))
+                })
+                .expect("a core of two points has edges");
+            [inc_start, inc_end]
+        }
+    };
+
+    //keep the part of the feature between the sides of the face
+    let tangent = (end - start) / end.distance(start).max(f32::EPSILON);
+    let (min, max) = (tangent.dot(start), tangent.dot(end));
+    let mut clipped = feature;
+    for (bound, keep_above) in [(min, true), (max, false)] {
+        let side = |p: Vec2| {
+            let d = tangent.dot(p) - bound;
+            if keep_above { d } else { -d }
+        };
+        let (d0, d1) = (side(clipped[0]), side(clipped[1]));
+        if d0 < 0. && d1 < 0. {
+            //entirely past a side, only its closest end touches the face
+            let closest = if d0 > d1 { clipped[0] } else { clipped[1] };
+            clipped = [closest; 2];
+        } else if d0 < 0. {
+            clipped[0] = clipped[0] + (clipped[1] - clipped[0]) * (d0 / (d0 - d1));
+        } else if d1 < 0. {
+            clipped[1] = clipped[1] + (clipped[0] - clipped[1]) * (d1 / (d1 - d0));
+        }
+    }
+
+    let mut manifold = Manifold::new(normal);
+    for p in clipped {
+        let separation = normal.dot(p - start);
+        if separation < radii {
+            manifold.push(ContactPoint {
+                position: p - normal * ((separation + incident.radius() - reference.radius()) / 2.),
+                depth: radii - separation,
+            });
+        }
+    }
+
+    (!manifold.points().is_empty()).then_some(manifold)
+}
@@ -1,4 +1,5 @@
 pub const FRICTION_COEF: f32 = 0.03;
 pub const GRAVITY_CONST: f32 = 9.80665;
-pub const MOUSE_ACCELERATION_FACTOR: f32 = 50.;
 pub const LIGHT_SPEED: f32 = 299_792_458.;
+///Physics sub steps of `Balls::simulate` per frame.
+pub const PHYSIC_SUB_STEP: u16 = 10;
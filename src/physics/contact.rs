@@ -0,0 +1,211 @@
+//! Collision response between two bodies, whatever their type.
+
+use my_glium_util::math::{EuclidianSpace, Vec2, VectorSpace};
+
+use super::{
+    collision::{self, MAX_CONTACT_POINTS, Manifold},
+    geometry::{Shape, cross},
+    traits::Body,
+};
+
+///Distance under which two bodies are considered at the same place.
+pub const MIN_DISTANCE: f32 = 0.001;
+///Relative speed along the normal over which two bodies are moving apart, and do not bounce.
+const RESTITUTION_THRESHOLD: f32 = 0.5;
+///Coulomb friction coefficient between two shapes that are not both circles.
+pub const CONTACT_FRICTION: f32 = 0.3;
+
+///A contact that was resolved.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Contact {
+    ///Magnitude of the impulse exchanged.
+    pub impulse: f32,
+    ///How deep the bodies were inside each other before being pushed apart.
+    pub depth: f32,
+}
+
+///Push `a` and `b` out of each other and exchange the impulse of their collision (see
+///https://en.wikipedia.org/wiki/Inelastic_collision).
+///
+///Returns the contact, or `None` if the bodies were not touching.
+pub fn resolve_contact<A: Body, B: Body>(a: &mut A, b: &mut B) -> Option<Contact> {
+    match (a.bounding_shape(), b.bounding_shape()) {
+        (
+            Shape::Circle {
+                center: center_a,
+                radius: radius_a,
+            },
+            Shape::Circle {
+                center: center_b,
+                radius: radius_b,
+            },
+        ) => {
+            let delta = center_a - center_b;
+            let radii = radius_a + radius_b;
+            let dist_sq = delta[0] * delta[0] + delta[1] * delta[1];
+            if dist_sq >= radii * radii {
+                return None;
+            }
+            if a.inverse_mass() + b.inverse_mass() <= 0. {
+                return None;
+            }
+            let depth = radii - dist_sq.sqrt();
+            separate_circles(a, b, radii);
+
+            Some(Contact {
+                impulse: exchange_impulse(a, b),
+                depth,
+            })
+        }
+        (shape_a, shape_b) => {
+            let manifold = collision::collide(&shape_a, &shape_b)?;
+            Some(Contact {
+                impulse: resolve_manifold(a, b, &manifold),
+                depth: manifold.depth(),
+            })
+        }
+    }
+}
+
+///Move two overlapping circles of radii summing to `radii` apart, the lightest moving the most.
+fn separate_circles<A: Body, B: Body>(a: &mut A, b: &mut B, radii: f32) {
+    let (inv_mass_a, inv_mass_b) = (a.inverse_mass(), b.inverse_mass());
+    let dist = a.position().distance(b.position()).max(MIN_DISTANCE);
+    let overlap = (radii - dist).max(0.0) / (inv_mass_a + inv_mass_b);
+    let normal = (a.position() - b.position()) / dist;
+
+    a.set_position(a.position() + normal * (overlap * inv_mass_a));
+    b.set_position(b.position() - normal * (overlap * inv_mass_b));
+}
+
+///Inelastic response along the line between the two bodies, returns the impulse magnitude.
+fn exchange_impulse<A: Body, B: Body>(a: &mut A, b: &mut B) -> f32 {
+    let bounce = (a.bounce() + b.bounce()) / 2.;
+    let (inv_mass_a, inv_mass_b) = (a.inverse_mass(), b.inverse_mass());
+
+    let dist = a.position().distance(b.position()).max(MIN_DISTANCE);
+    let rel_speed = a.velocity() - b.velocity();
+    let norm_p = (a.position() - b.position()) / dist;
+
+    let rel_vel_along_normal = rel_speed.dot(norm_p);
+    if rel_vel_along_normal > RESTITUTION_THRESHOLD {
+        return 0.;
+    }
+
+    let norm_impulse = rel_vel_along_normal / (inv_mass_a + inv_mass_b) * (1.0 + bounce);
+
+    a.set_velocity(a.velocity() - norm_p * (norm_impulse * inv_mass_a));
+    b.set_velocity(b.velocity() + norm_p * (norm_impulse * inv_mass_b));
+
+    norm_impulse.abs()
+}
+
+///Push `a` and `b` out of each other along the normal of `manifold`, then apply the impulses of the
+///collision and of the friction at each of its points. The bodies turn when hit off center.
+///
+///Returns the sum of the normal impulses.
+pub fn resolve_manifold<A: Body, B: Body>(a: &mut A, b: &mut B, manifold: &Manifold) -> f32 {
+    let (inv_mass_a, inv_mass_b) = (a.inverse_mass(), b.inverse_mass());
+    let inv_masses = inv_mass_a + inv_mass_b;
+    if inv_masses <= 0. {
+        return 0.;
+    }
+    let normal = manifold.normal;
+
+    let depth = manifold.depth();
+    a.set_position(a.position() - normal * (depth * inv_mass_a / inv_masses));
+    b.set_position(b.position() + normal * (depth * inv_mass_b / inv_masses));
+
+    let bounce = (a.bounce() + b.bounce()) / 2.;
+    let (inv_inertia_a, inv_inertia_b) = (a.inverse_inertia(), b.inverse_inertia());
+    let points = manifold.points();
+    //mass seen by an impulse along `direction` at `point`
+    let effective_inverse_mass = |point: Vec2, direction: Vec2, a: &A, b: &B| {
+        inv_masses
+            + cross(point - a.position(), direction).powi(2) * inv_inertia_a
+            + cross(point - b.position(), direction).powi(2) * inv_inertia_b
+    };
+    let relative_velocity = |point: Vec2, a: &A, b: &B| b.velocity_at(point) - a.velocity_at(point);
+
+    //every impulse of a pass is computed from the same velocities, so that the points are treated
+    //the same whatever their order
+    let mut impulses = [0.; MAX_CONTACT_POINTS];
+    for (impulse, point) in impulses.iter_mut().zip(points) {
+        let along_normal = relative_velocity(point.position, a, b).dot(normal);
+        if along_normal <= RESTITUTION_THRESHOLD {
+            *impulse = (-(1. + bounce) * along_normal
+                / effective_inverse_mass(point.position, normal, a, b)
+                / points.len() as f32)
+                .max(0.);
+        }
+    }
+    for (&impulse, point) in impulses.iter().zip(points) {
+        a.apply_impulse_at(normal * -impulse, point.position);
+        b.apply_impulse_at(normal * impulse, point.position);
+    }
+
+    let mut frictions = [Vec2::v_space_zero(); MAX_CONTACT_POINTS];
+    for ((friction, &impulse), point) in frictions.iter_mut().zip(&impulses).zip(points) {
+        let relative = relative_velocity(point.position, a, b);
+        let sliding = relative - normal * relative.dot(normal);
+        let speed = sliding.dot(sliding).sqrt();
+        if speed > f32::EPSILON {
+            let tangent = sliding / speed;
+            let stop = speed / effective_inverse_mass(point.position, tangent, a, b);
+            *friction = tangent * (stop / points.len() as f32).min(CONTACT_FRICTION * impulse);
+        }
+    }
+    for (&friction, point) in frictions.iter().zip(points) {
+        a.apply_impulse_at(friction, point.position);
+        b.apply_impulse_at(friction * -1., point.position);
+    }
+
+    impulses.iter().sum()
+}
+
+///Body that never moves, like the walls of the world. It only touches other bodies through
+///manifolds built by hand, its shape is a point.
+#[derive(Debug, Clone, Copy)]
+pub struct StaticBody {
+    pub position: Vec2,
+    pub bounce: f32,
+}
+
+impl Body for StaticBody {
+    fn position(&self) -> Vec2 {
+        self.position
+    }
+
+    fn set_position(&mut self, _: Vec2) {}
+
+    fn velocity(&self) -> Vec2 {
+        Vec2::v_space_zero()
+    }
+
+    fn set_velocity(&mut self, _: Vec2) {}
+
+    fn mass(&self) -> f32 {
+        f32::INFINITY
+    }
+
+    fn bounce(&self) -> f32 {
+        self.bounce
+    }
+
+    fn bounding_shape(&self) -> Shape {
+        Shape::Circle {
+            center: self.position,
+            radius: 0.,
+        }
+    }
+
+    fn is_dynamic(&self) -> bool {
+        false
+    }
+
+    fn reset_forces(&mut self) {}
+
+    fn apply_force(&mut self, _: Vec2) {}
+
+    fn integrate(&mut self, _: f32) {}
+}
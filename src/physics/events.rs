@@ -0,0 +1,99 @@
+//! Events emitted by the physics step, so the rest of the app can react to what happened
+//! (sounds, scoring, analytics...) without touching the simulation itself.
+
+use std::sync::{Arc, Mutex};
+
+///Wall of the simulation boundary, as seen on screen (`y` grows downward).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Wall {
+    Left,
+    Right,
+    Top,
+    Bottom,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PhysicsEvent {
+    ///Two balls started touching during this frame, `impulse` is the total impulse magnitude exchanged.
+    ContactBegin {
+        a: usize,
+        b: usize,
+        impulse: f32,
+    },
+    ///Two balls that were already touching last frame are still touching.
+    ContactPersist {
+        a: usize,
+        b: usize,
+        impulse: f32,
+    },
+    ///Two balls that were touching last frame are not touching anymore.
+    ContactEnd {
+        a: usize,
+        b: usize,
+    },
+
+    ///A ball bounced on one of the walls of the boundary.
+    BorderHit {
+        id: usize,
+        wall: Wall,
+        impulse: f32,
+    },
+
+    Spawned {
+        id: usize,
+    },
+    Removed {
+        id: usize,
+    },
+
+    Sleep {
+        id: usize,
+    },
+    Wake {
+        id: usize,
+    },
+
+    ///A ball started touching a sensor.
+    SensorEnter {
+        sensor: usize,
+        ball: usize,
+    },
+    ///A ball stopped touching a sensor (or was removed while touching it).
+    SensorExit {
+        sensor: usize,
+        ball: usize,
+    },
+}
+
+///Shared queue of [`PhysicsEvent`], filled by the physics step and drained by the app each frame.
+///
+///Cloning the queue gives another handle on the same events.
+#[derive(Clone, Default)]
+pub struct EventQueue {
+    events: Arc<Mutex<Vec<PhysicsEvent>>>,
+}
+
+impl EventQueue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&self, event: PhysicsEvent) {
+        self.events
+            .lock()
+            .expect("physics event queue poisoned")
+            .push(event);
+    }
+
+    pub fn extend<I: IntoIterator<Item = PhysicsEvent>>(&self, events: I) {
+        self.events
+            .lock()
+            .expect("physics event queue poisoned")
+            .extend(events);
+    }
+
+    ///Take every pending event, leaving the queue empty.
+    pub fn drain(&self) -> Vec<PhysicsEvent> {
+        std::mem::take(&mut *self.events.lock().expect("physics event queue poisoned"))
+    }
+}
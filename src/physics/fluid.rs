@@ -0,0 +1,152 @@
+//! Smoothed particle hydrodynamics (SPH) : balls seen as particles of a weakly compressible fluid.
+//!
+//! The density of a particle is the sum of the masses of its neighbours weighted by a kernel of
+//! radius `smoothing_radius`, its pressure grows with the density over `rest_density`, and the
+//! pressure and viscosity forces come from the kernel gradients (see Müller et al., "Particle-Based
+//! Fluid Simulation for Interactive Applications", 2003, with the 2D kernels).
+
+use std::f32::consts::PI;
+
+use my_glium_util::math::{EuclidianSpace, Vec2};
+use serde::{Deserialize, Serialize};
+
+///Smoothing radius, in particle radii : about 12 neighbours per particle.
+const SMOOTHING_RADII: f32 = 4.;
+///Speed of sound of the fluid, in world units per second. The density varies by about
+///`gravity * depth / SOUND_SPEED²`, a few percents for a world full of water.
+const SOUND_SPEED: f32 = 1500.;
+///Kinematic viscosity, in world units² per second.
+const VISCOSITY: f32 = 200.;
+
+///Parameters of the fluid, `Balls::set_fluid` turns every circle into a particle of it.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct FluidParams {
+    ///Distance over which the particles interact.
+    pub smoothing_radius: f32,
+    ///Density at which the pressure is zero, in mass per world unit².
+    pub rest_density: f32,
+    ///Pressure per unit of density over the rest density, in world units² per second².
+    pub stiffness: f32,
+    ///Kinematic viscosity, in world units² per second.
+    pub viscosity: f32,
+}
+
+///A ball seen as a particle of the fluid.
+#[derive(Clone, Copy, Debug)]
+pub struct FluidParticle {
+    pub position: Vec2,
+    pub velocity: Vec2,
+    pub mass: f32,
+}
+
+impl FluidParams {
+    ///Fluid of balls of `radius` (and of mass `radius²`), at rest when they touch each other in a
+    ///square grid.
+    pub fn new(radius: f32) -> Self {
+        let mut params = Self {
+            smoothing_radius: SMOOTHING_RADII * radius,
+            rest_density: 1.,
+            stiffness: SOUND_SPEED * SOUND_SPEED,
+            viscosity: VISCOSITY,
+        };
+        let reach = SMOOTHING_RADII.ceil() as i32;
+        params.rest_density = (-reach..=reach)
+            .flat_map(|x| (-reach..=reach).map(move |y| (x, y)))
+            .map(|(x, y)| {
+                let distance = 2. * radius * ((x * x + y * y) as f32).sqrt();
+                radius * radius * params.kernel(distance)
+            })
+            .sum();
+
+        params
+    }
+
+    ///Poly6 kernel at `distance`, its integral over the plane is 1.
+    pub fn kernel(&self, distance: f32) -> f32 {
+        let h = self.smoothing_radius;
+        if distance >= h {
+            return 0.;
+        }
+        4. / (PI * h.powi(8)) * (h * h - distance * distance).powi(3)
+    }
+
+    ///Derivative of the spiky kernel at `distance`, negative : the pressure force pushes along it.
+    fn kernel_slope(&self, distance: f32) -> f32 {
+        let h = self.smoothing_radius;
+        if distance >= h {
+            return 0.;
+        }
+        -30. / (PI * h.powi(5)) * (h - distance).powi(2)
+    }
+
+    ///Laplacian of the viscosity kernel at `distance`.
+    fn kernel_laplacian(&self, distance: f32) -> f32 {
+        let h = self.smoothing_radius;
+        if distance >= h {
+            return 0.;
+        }
+        40. / (PI * h.powi(5)) * (h - distance)
+    }
+
+    ///Pressure of the fluid at `density`, never negative so that the particles do not clump.
+    pub fn pressure(&self, density: f32) -> f32 {
+        (self.stiffness * (density - self.rest_density)).max(0.)
+    }
+
+    ///Density of every particle, `neighbours[i]` being the indices of the particles within
+    ///`smoothing_radius` of particle `i` (itself included).
+    pub fn densities(&self, particles: &[FluidParticle], neighbours: &[Vec<usize>]) -> Vec<f32> {
+        particles
+            .iter()
+            .zip(neighbours)
+            .map(|(particle, neighbours)| {
+                neighbours
+                    .iter()
+                    .map(|&j| {
+                        let delta = particles[j].position - particle.position;
+                        particles[j].mass * self.kernel(delta.dot(delta).sqrt())
+                    })
+                    .sum()
+            })
+            .collect()
+    }
+
+    ///Acceleration of every particle from the pressure and the viscosity, equal and opposite
+    ///forces between each pair.
+    pub fn accelerations(
+        &self,
+        particles: &[FluidParticle],
+        neighbours: &[Vec<usize>],
+        densities: &[f32],
+    ) -> Vec<Vec2> {
+        let pressures: Vec<f32> = densities.iter().map(|&d| self.pressure(d)).collect();
+
+        (0..particles.len())
+            .map(|i| {
+                let particle = particles[i];
+                let mut acceleration = Vec2::from([0.; 2]);
+                for &j in neighbours[i].iter().filter(|&&j| j != i) {
+                    let other = particles[j];
+                    let delta = particle.position - other.position;
+                    let distance = delta.dot(delta).sqrt();
+                    let (density, mean_density) = (
+                        densities[i] * densities[j],
+                        (densities[i] + densities[j]) / 2.,
+                    );
+                    if distance <= f32::EPSILON || density <= f32::EPSILON {
+                        continue;
+                    }
+
+                    let pressure = -other.mass * (pressures[i] + pressures[j]) / (2. * density)
+                        * self.kernel_slope(distance);
+                    let viscosity = self.viscosity * other.mass / mean_density
+                        * self.kernel_laplacian(distance);
+                    acceleration += delta * (pressure / distance)
+                        + (other.velocity - particle.velocity) * viscosity;
+                }
+
+                acceleration
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,363 @@
+//! Small geometry helpers shared by the physics, the sensors and the renderers, and the shapes of
+//! the bodies.
+
+use my_glium_util::{
+    datastruct::aabb::Aabb,
+    math::{EuclidianSpace, Vec2},
+};
+
+///Vertices a convex polygon has at most, so that the bodies stay `Copy`.
+pub const MAX_POLYGON_VERTICES: usize = 8;
+
+///Shape of a body around its position, before its rotation.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum BodyShape {
+    ///Of the radius of the body.
+    #[default]
+    Circle,
+    Box {
+        half_extents: [f32; 2],
+    },
+    ///Segment of `2 * half_length` along x, with round ends of `radius`.
+    Capsule {
+        half_length: f32,
+        radius: f32,
+    },
+    Polygon(ConvexPolygon),
+}
+
+///Convex polygon centered on its centroid, counter-clockwise (with y going up).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ConvexPolygon {
+    vertices: [[f32; 2]; MAX_POLYGON_VERTICES],
+    len: usize,
+}
+
+///Shape of a body, in world coordinates. Every shape is a point, a segment or a polygon (its core)
+///inflated by a radius.
+#[derive(Debug, Clone, Copy)]
+pub enum Shape {
+    Circle {
+        center: Vec2,
+        radius: f32,
+    },
+    Capsule {
+        ends: [Vec2; 2],
+        radius: f32,
+    },
+    ///Counter-clockwise (with y going up).
+    Polygon {
+        vertices: [Vec2; MAX_POLYGON_VERTICES],
+        len: usize,
+    },
+}
+
+impl BodyShape {
+    pub fn name(&self) -> &'static str {
+        match self {
+            BodyShape::Circle => "circle",
+            BodyShape::Box { .. } => "box",
+            BodyShape::Capsule { .. } => "capsule",
+            BodyShape::Polygon(_) => "polygon",
+        }
+    }
+
+    ///Radius of the smallest circle around the position holding the shape, `radius` being the
+    ///radius of a circle.
+    pub fn bounding_radius(&self, radius: f32) -> f32 {
+        match self {
+            BodyShape::Circle => radius,
+            BodyShape::Box {
+                half_extents: [x, y],
+            } => x.hypot(*y),
+            BodyShape::Capsule {
+                half_length,
+                radius,
+            } => half_length + radius,
+            BodyShape::Polygon(polygon) => polygon
+                .vertices()
+                .iter()
+                .map(|[x, y]| x.hypot(*y))
+                .fold(0., f32::max),
+        }
+    }
+
+    pub fn area(&self, radius: f32) -> f32 {
+        use std::f32::consts::PI;
+
+        match self {
+            BodyShape::Circle => PI * radius * radius,
+            BodyShape::Box {
+                half_extents: [x, y],
+            } => 4. * x * y,
+            BodyShape::Capsule {
+                half_length,
+                radius,
+            } => 4. * half_length * radius + PI * radius * radius,
+            BodyShape::Polygon(polygon) => polygon.area(),
+        }
+    }
+
+    ///Moment of inertia per unit of mass around the position.
+    pub fn inertia_per_mass(&self, radius: f32) -> f32 {
+        use std::f32::consts::PI;
+
+        match self {
+            BodyShape::Circle => radius * radius / 2.,
+            BodyShape::Box {
+                half_extents: [x, y],
+            } => (x * x + y * y) / 3.,
+            BodyShape::Capsule {
+                half_length: l,
+                radius: r,
+            } => {
+                //a rectangle, and two half discs counted as discs at the ends
+                let (rectangle, discs) = (4. * l * r, PI * r * r);
+                (rectangle * (l * l + r * r) / 3. + discs * (r * r / 2. + l * l))
+                    / (rectangle + discs)
+            }
+            BodyShape::Polygon(polygon) => polygon.inertia_per_mass(),
+        }
+    }
+
+    ///The same shape `factor` times bigger.
+    pub fn scaled(&self, factor: f32) -> Self {
+        match *self {
+            BodyShape::Circle => BodyShape::Circle,
+            BodyShape::Box {
+                half_extents: [x, y],
+            } => BodyShape::Box {
+                half_extents: [x * factor, y * factor],
+            },
+            BodyShape::Capsule {
+                half_length,
+                radius,
+            } => BodyShape::Capsule {
+                half_length: half_length * factor,
+                radius: radius * factor,
+            },
+            BodyShape::Polygon(mut polygon) => {
+                for [x, y] in &mut polygon.vertices[..polygon.len] {
+                    *x *= factor;
+                    *y *= factor;
+                }
+                BodyShape::Polygon(polygon)
+            }
+        }
+    }
+
+    ///The shape of a body at `position` turned by `angle` radians, `radius` being the radius of a
+    ///circle.
+    pub fn to_world(&self, radius: f32, position: Vec2, angle: f32) -> Shape {
+        let (sin, cos) = angle.sin_cos();
+        let place =
+            |[x, y]: [f32; 2]| position + Vec2::from([x * cos - y * sin, x * sin + y * cos]);
+        let polygon = |local: &[[f32; 2]]| {
+            let mut vertices = [position; MAX_POLYGON_VERTICES];
+            for (vertex, &point) in vertices.iter_mut().zip(local) {
+                *vertex = place(point);
+            }
+            Shape::Polygon {
+                vertices,
+                len: local.len(),
+            }
+        };
+
+        match self {
+            BodyShape::Circle => Shape::Circle {
+                center: position,
+                radius,
+            },
+            &BodyShape::Box {
+                half_extents: [x, y],
+            } => polygon(&[[-x, -y], [x, -y], [x, y], [-x, y]]),
+            &BodyShape::Capsule {
+                half_length,
+                radius,
+            } => Shape::Capsule {
+                ends: [place([-half_length, 0.]), place([half_length, 0.])],
+                radius,
+            },
+            BodyShape::Polygon(local) => polygon(local.vertices()),
+        }
+    }
+}
+
+impl ConvexPolygon {
+    ///Convex hull of `points`, moved so that its centroid is the origin. `None` if the hull is flat
+    ///or has more than `MAX_POLYGON_VERTICES` vertices.
+    pub fn new(points: &[[f32; 2]]) -> Option<Self> {
+        let hull = convex_hull(points);
+        if !(3..=MAX_POLYGON_VERTICES).contains(&hull.len()) {
+            return None;
+        }
+        let mut polygon = Self {
+            vertices: [[0.; 2]; MAX_POLYGON_VERTICES],
+            len: hull.len(),
+        };
+        polygon.vertices[..hull.len()].copy_from_slice(&hull);
+
+        let area = polygon.area();
+        if area <= f32::EPSILON {
+            return None;
+        }
+        let mut centroid = [0.; 2];
+        for (a, b) in polygon.edges() {
+            let cross = a[0] * b[1] - a[1] * b[0];
+            centroid[0] += (a[0] + b[0]) * cross / (6. * area);
+            centroid[1] += (a[1] + b[1]) * cross / (6. * area);
+        }
+        for [x, y] in &mut polygon.vertices[..polygon.len] {
+            *x -= centroid[0];
+            *y -= centroid[1];
+        }
+
+        Some(polygon)
+    }
+
+    ///Polygon of `sides` vertices at `radius` from its center.
+    pub fn regular(sides: usize, radius: f32) -> Option<Self> {
+        let points: Vec<[f32; 2]> = (0..sides)
+            .map(|i| {
+                let angle = i as f32 * std::f32::consts::TAU / sides as f32;
+                [radius * angle.cos(), radius * angle.sin()]
+            })
+            .collect();
+
+        Self::new(&points)
+    }
+
+    pub fn vertices(&self) -> &[[f32; 2]] {
+        &self.vertices[..self.len]
+    }
+
+    fn edges(&self) -> impl Iterator<Item = ([f32; 2], [f32; 2])> + '_ {
+        let vertices = self.vertices();
+        (0..vertices.len()).map(|i| (vertices[i], vertices[(i + 1) % vertices.len()]))
+    }
+
+    pub fn area(&self) -> f32 {
+        self.edges()
+            .map(|(a, b)| a[0] * b[1] - a[1] * b[0])
+            .sum::<f32>()
+            / 2.
+    }
+
+    ///Moment of inertia around the origin per unit of mass.
+    fn inertia_per_mass(&self) -> f32 {
+        let inertia: f32 = self
+            .edges()
+            .map(|(a, b)| {
+                let cross = a[0] * b[1] - a[1] * b[0];
+                let dot = |u: [f32; 2], v: [f32; 2]| u[0] * v[0] + u[1] * v[1];
+                cross * (dot(a, a) + dot(a, b) + dot(b, b)) / 12.
+            })
+            .sum();
+
+        inertia / self.area()
+    }
+}
+
+///Counter-clockwise convex hull (monotone chain), without aligned vertices.
+fn convex_hull(points: &[[f32; 2]]) -> Vec<[f32; 2]> {
+    let mut sorted = points.to_vec();
+    sorted.sort_by(|a, b| a[0].total_cmp(&b[0]).then(a[1].total_cmp(&b[1])));
+    sorted.dedup();
+    if sorted.len() < 3 {
+        return sorted;
+    }
+    let turn = |o: [f32; 2], a: [f32; 2], b: [f32; 2]| {
+        (a[0] - o[0]) * (b[1] - o[1]) - (a[1] - o[1]) * (b[0] - o[0])
+    };
+
+    let mut hull: Vec<[f32; 2]> = vec![];
+    for pass in [sorted.clone(), sorted.into_iter().rev().collect()] {
+        let start = hull.len();
+        for point in pass {
+            while hull.len() >= start + 2
+                && turn(hull[hull.len() - 2], hull[hull.len() - 1], point) <= 0.
+            {
+                hull.pop();
+            }
+            hull.push(point);
+        }
+        //the last point of a pass is the first of the other
+        hull.pop();
+    }
+
+    hull
+}
+
+impl Shape {
+    ///The point, segment or polygon inflated by `radius()` to give the shape.
+    pub fn core(&self) -> &[Vec2] {
+        match self {
+            Shape::Circle { center, .. } => std::slice::from_ref(center),
+            Shape::Capsule { ends, .. } => ends,
+            Shape::Polygon { vertices, len } => &vertices[..*len],
+        }
+    }
+
+    pub fn radius(&self) -> f32 {
+        match *self {
+            Shape::Circle { radius, .. } | Shape::Capsule { radius, .. } => radius,
+            Shape::Polygon { .. } => 0.,
+        }
+    }
+
+    ///Smallest square holding the shape, as the quadtree wants them.
+    pub fn bounding_box(&self) -> Aabb<f32> {
+        if let Shape::Circle { center, radius } = *self {
+            return Aabb::new((center[0], center[1]), radius);
+        }
+        let radius = self.radius();
+        let (mut min, mut max) = ([f32::INFINITY; 2], [f32::NEG_INFINITY; 2]);
+        for point in self.core() {
+            for axis in 0..2 {
+                min[axis] = min[axis].min(point[axis] - radius);
+                max[axis] = max[axis].max(point[axis] + radius);
+            }
+        }
+
+        Aabb::new(
+            ((min[0] + max[0]) / 2., (min[1] + max[1]) / 2.),
+            ((max[0] - min[0]).max(max[1] - min[1])) / 2.,
+        )
+    }
+
+    pub fn contains(&self, point: Vec2) -> bool {
+        let core = self.core();
+        if core.len() >= 3 {
+            return (0..core.len()).all(|i| {
+                let (a, b) = (core[i], core[(i + 1) % core.len()]);
+                cross(b - a, point - a) >= 0.
+            });
+        }
+
+        let radius = self.radius();
+        segment_distance_sq(point, core[0], core[core.len() - 1]) <= radius * radius
+    }
+}
+
+///z coordinate of the cross product of `a` and `b`.
+pub fn cross(a: Vec2, b: Vec2) -> f32 {
+    a[0] * b[1] - a[1] * b[0]
+}
+
+///Closest point to `p` of the segment `[a, b]`.
+pub fn closest_on_segment(p: Vec2, a: Vec2, b: Vec2) -> Vec2 {
+    let ab = b - a;
+    let t = ((p - a).dot(ab) / ab.dot(ab).max(f32::EPSILON)).clamp(0., 1.);
+
+    a + ab * t
+}
+
+///Squared distance between the point `p` and the segment `[a, b]`.
+pub fn segment_distance_sq(p: Vec2, a: Vec2, b: Vec2) -> f32 {
+    let ab = b - a;
+    let ap = p - a;
+    let t = (ap.dot(ab) / ab.dot(ab).max(f32::EPSILON)).clamp(0., 1.);
+    let d = ap - ab * t;
+
+    d.dot(d)
+}
@@ -1,5 +1,10 @@
+pub mod clock;
+pub mod collision;
 pub mod constants;
-// pub mod fix_physics;
+pub mod contact;
+pub mod events;
+pub mod fluid;
+pub mod geometry;
 pub mod traits;
 
 #[cfg(test)]
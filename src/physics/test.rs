@@ -1,59 +1,1058 @@
 #![cfg(test)]
 
-// mod fix_physics_test {
-//     use std::default;
-
-//     use super::{
-//         constants::Vec2,
-//         fix_physics::{PhysicsFixUpdates, PhysicsWrapper},
-//         traits::Physics,
-//     };
-
-//     const TEST_PHYSIC_STRUCT_ONE_MASS: f32 = 10.;
-//     struct TestPhysicStructOne {
-//         position: Vec2,
-//     }
-//     impl Default for TestPhysicStructOne {
-//         fn default() -> Self {
-//             Self {
-//                 position: Default::default(),
-//             }
-//         }
-//     }
-
-//     struct TestPhysicStructTwo {
-//         position: Vec2,
-//     }
-
-//     impl Default for TestPhysicStructTwo {
-//         fn default() -> Self {
-//             Self {
-//                 position: Default::default(),
-//             }
-//         }
-//     }
-
-//     impl Physics for TestPhysicStructOne {
-//         fn physics_update(&mut self, dt: f32) {
-//             self.position -= Vec2::from([0., 1.]) * TEST_PHYSIC_STRUCT_ONE_MASS * dt * dt;
-//         }
-//     }
-
-//     impl Physics for TestPhysicStructTwo {
-//         fn physics_update(&mut self, dt: f32) {
-//             self.position -= Vec2::from([f32::sin(dt), f32::cos(dt)]);
-//         }
-//     }
-
-//     #[test]
-//     fn test_fix_physic_update() {
-//         let mut wrapper = PhysicsWrapper::empty();
-//         let struct_1 = TestPhysicStructOne::default();
-//         let struct_2 = TestPhysicStructTwo::default();
-
-//         wrapper.push(struct_1);
-//         wrapper.push(struct_2);
-
-//         let fix_phy = PhysicsFixUpdates::new(wrapper, 60_f32.recip());
-//     }
-// }
+//! Physics regression tests.
+//!
+//! Named scenarios are run headlessly for a fixed number of steps and compared with the golden
+//! snapshots of `tests/golden`. A missing snapshot fails the test, set `UPDATE_GOLDEN=1` to write
+//! them after an intended change of the physics.
+
+use crate::{
+    balls::{Balls, one_ball::Ball},
+    test_util::{BORDER, DT, XorShift, ball, grid, momentum, random_balls, run, world},
+};
+
+const GOLDEN_POS_TOLERANCE: f32 = 0.5;
+const GOLDEN_SPEED_TOLERANCE: f32 = 1.;
+
+mod golden {
+    use std::{fs, path::PathBuf};
+
+    use super::*;
+
+    ///`id x y speed_x speed_y` for every ball, sorted by id.
+    fn snapshot(world: &Balls) -> Vec<[f32; 5]> {
+        let mut snapshot: Vec<[f32; 5]> = world
+            .iter()
+            .map(|b| {
+                [
+                    b.id as f32,
+                    b.position[0],
+                    b.position[1],
+                    b.speed[0],
+                    b.speed[1],
+                ]
+            })
+            .collect();
+        snapshot.sort_by(|a, b| a[0].total_cmp(&b[0]));
+
+        snapshot
+    }
+
+    fn check(name: &str, world: &Balls) {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/golden")
+            .join(format!("{name}.golden"));
+        let snapshot = snapshot(world);
+
+        if std::env::var_os("UPDATE_GOLDEN").is_some() {
+            let content: String = snapshot
+                .iter()
+                .map(|[id, x, y, s_x, s_y]| format!("{id} {x:.3} {y:.3} {s_x:.3} {s_y:.3}\n"))
+                .collect();
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(&path, content).unwrap();
+            println!("written golden snapshot {}", path.display());
+            return;
+        }
+
+        let Ok(golden) = fs::read_to_string(&path) else {
+            panic!(
+                "{name}: missing golden snapshot {}, run the tests with UPDATE_GOLDEN=1 to write it",
+                path.display()
+            );
+        };
+        let golden: Vec<Vec<f32>> = golden
+            .lines()
+            .map(|line| line.split(' ').map(|v| v.parse().unwrap()).collect())
+            .collect();
+
+        let tolerances = [
+            0.,
+            GOLDEN_POS_TOLERANCE,
+            GOLDEN_POS_TOLERANCE,
+            GOLDEN_SPEED_TOLERANCE,
+            GOLDEN_SPEED_TOLERANCE,
+        ];
+
+        assert_eq!(
+            golden.len(),
+            snapshot.len(),
+            "{name}: wrong number of balls"
+        );
+        for (expected, got) in golden.iter().zip(&snapshot) {
+            for ((e, g), tolerance) in expected.iter().zip(got).zip(tolerances) {
+                assert!(
+                    (e - g).abs() <= tolerance,
+                    "{name}: ball {} moved from {expected:?} to {got:?}",
+                    got[0]
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn two_ball_head_on() {
+        let mut world = world(vec![
+            ball(10., [300., 300.], [200., 0.], 0),
+            ball(10., [500., 300.], [-200., 0.], 1),
+        ]);
+        run(&mut world, 120);
+
+        check("two_ball_head_on", &world);
+    }
+
+    #[test]
+    fn newtons_cradle() {
+        let floor = BORDER.1 - 10.;
+        let mut balls = vec![ball(10., [100., floor], [300., 0.], 0)];
+        for i in 1..6 {
+            balls.push(ball(10., [200. + 20. * i as f32, floor], [0., 0.], i));
+        }
+        let mut world = world(balls);
+        run(&mut world, 180);
+
+        check("newtons_cradle", &world);
+    }
+
+    #[test]
+    fn pile_settling() {
+        let mut world = world(grid(60, 10, [300., 100.], [25., 25.], 10.));
+        run(&mut world, 600);
+
+        check("pile_settling", &world);
+    }
+
+    #[test]
+    fn border_bounce() {
+        let mut world = world(vec![ball(10., [400., 100.], [150., 0.], 0)]);
+        run(&mut world, 240);
+
+        check("border_bounce", &world);
+    }
+}
+
+mod properties {
+    use super::*;
+
+    #[test]
+    fn free_fall_matches_analytic_solution() {
+        use crate::physics::constants::{FRICTION_COEF, GRAVITY_CONST};
+
+        let mut world = world(vec![ball(5., [400., 50.], [0., 0.], 0)]);
+        run(&mut world, 30);
+
+        let b = world.iter().next().unwrap();
+        let t = 30. * DT;
+        // dv/dt = a - k*v, see `Body::apply_gravity` and `Body::apply_drag`
+        let (a, k) = (GRAVITY_CONST * b.mass, FRICTION_COEF);
+        let expected_speed = a / k * (1. - (-k * t).exp());
+        let expected_y = 50. + a / k * (t - (1. - (-k * t).exp()) / k);
+
+        assert!(
+            (b.speed[1] - expected_speed).abs() < expected_speed * 0.02,
+            "speed {} instead of {expected_speed}",
+            b.speed[1]
+        );
+        assert!(
+            (b.position[1] - expected_y).abs() < (expected_y - 50.) * 0.05,
+            "position {} instead of {expected_y}",
+            b.position[1]
+        );
+        assert_eq!(b.speed[0], 0.);
+    }
+
+    #[test]
+    fn border_bounce_keeps_the_bounce_factor() {
+        let mut b = ball(5., [400., BORDER.1 - 4.], [0., 200.], 0);
+        b.handle_border_colision_ball(BORDER);
+
+        assert_eq!(b.position[1], BORDER.1 - 5.);
+        assert!((b.speed[1] + 200. * b.bounce).abs() < 1e-3);
+    }
+
+    #[test]
+    fn collision_conserves_momentum() {
+        let mut rng = XorShift(0x2545_f491);
+
+        for _ in 0..1_000 {
+            let mut a = ball(
+                rng.range(1., 20.),
+                [rng.range(100., 700.), rng.range(100., 500.)],
+                [rng.range(-500., 500.), rng.range(-500., 500.)],
+                0,
+            );
+            let angle = rng.range(0., std::f32::consts::TAU);
+            let dist = rng.range(0.1, 0.99) * (a.size + 10.);
+            let mut b = ball(
+                10.,
+                [
+                    a.position[0] + dist * angle.cos(),
+                    a.position[1] + dist * angle.sin(),
+                ],
+                [rng.range(-500., 500.), rng.range(-500., 500.)],
+                1,
+            );
+
+            let before = momentum([&a, &b]);
+            assert!(a.handle_collision_balls(&mut b, DT).is_some());
+            let after = momentum([&a, &b]);
+
+            let tolerance = 1e-4 * (a.mass + b.mass) * 1_000.;
+            for (p_before, p_after) in before.iter().zip(after) {
+                assert!(
+                    (p_before - p_after).abs() < tolerance,
+                    "momentum changed from {before:?} to {after:?}"
+                );
+            }
+            assert!(
+                a.penetration_depth(&b) < 1e-2,
+                "balls still overlapping after collision"
+            );
+        }
+    }
+
+    #[test]
+    fn collision_of_coincident_balls_is_not_nan() {
+        let mut a = ball(10., [100., 100.], [0., 0.], 0);
+        let mut b = ball(10., [100., 100.], [0., 0.], 1);
+        a.handle_collision_balls(&mut b, DT);
+
+        for v in [a.position, a.speed, b.position, b.speed] {
+            assert!(v[0].is_finite() && v[1].is_finite());
+        }
+    }
+
+    #[test]
+    fn random_worlds_stay_finite_and_inside() {
+        let mut rng = XorShift(0x9e37_79b9);
+
+        for _ in 0..5 {
+            let balls = random_balls(
+                &mut rng,
+                200,
+                (2., 10.),
+                [0., 0.],
+                [BORDER.0, BORDER.1],
+                300.,
+            );
+            let mut world = world(balls);
+            run(&mut world, 300);
+
+            let [p_x, p_y] = momentum(world.iter());
+            assert!(p_x.is_finite() && p_y.is_finite());
+            for b in world.iter() {
+                let margin = 4. * b.size;
+                let inside = |v: f32, max: f32| (-margin..=max + margin).contains(&v);
+                assert!(
+                    inside(b.position[0], BORDER.0) && inside(b.position[1], BORDER.1),
+                    "ball {} escaped at {:?}",
+                    b.id,
+                    b.position
+                );
+                assert!(b.speed[0].is_finite() && b.speed[1].is_finite());
+            }
+        }
+    }
+
+    #[test]
+    fn sleeping_ball_is_not_integrated() {
+        let mut world = world(vec![ball(5., [400., BORDER.1 - 5.], [0., 0.], 0)]);
+        run(&mut world, 120);
+
+        let b = *world.iter().next().unwrap();
+        assert!(b.sleeping);
+        run(&mut world, 60);
+        let after = world.iter().next().unwrap();
+        assert_eq!(*after.position.as_array(), *b.position.as_array());
+    }
+}
+
+mod clock {
+    use super::*;
+    use crate::physics::clock::{MAX_STEP_DT, MAX_STEPS_PER_FRAME, STEP_DT, SimClock};
+
+    fn total(steps: &[f32]) -> f32 {
+        steps.iter().sum()
+    }
+
+    #[test]
+    fn paused_clock_only_runs_the_asked_steps() {
+        let mut clock = SimClock::default();
+        clock.toggle_pause();
+        assert!(clock.advance(DT).is_empty());
+
+        clock.step();
+        clock.step();
+        assert_eq!(clock.advance(DT), vec![STEP_DT; 2]);
+        assert!(clock.advance(DT).is_empty());
+        assert!(clock.paused);
+    }
+
+    #[test]
+    fn time_scale_scales_the_simulated_time() {
+        let mut clock = SimClock::default();
+        assert!((total(&clock.advance(0.01)) - 0.01).abs() < 1e-6);
+
+        clock.slower();
+        clock.slower();
+        assert_eq!(clock.time_scale(), 0.25);
+        assert!((total(&clock.advance(0.02)) - 0.005).abs() < 1e-6);
+
+        clock.reset_speed();
+        clock.faster();
+        clock.faster();
+        let steps = clock.advance(0.05);
+        assert!((total(&steps) - 0.2).abs() < 1e-5);
+        assert!(steps.iter().all(|&dt| dt <= MAX_STEP_DT));
+    }
+
+    #[test]
+    fn long_frames_are_capped() {
+        let mut clock = SimClock::default();
+        let steps = clock.advance(10.);
+        assert_eq!(steps.len(), MAX_STEPS_PER_FRAME);
+        assert!(steps.iter().all(|&dt| dt <= MAX_STEP_DT));
+    }
+
+    #[test]
+    fn spawner_follows_the_simulation_time() {
+        let mut world = world(vec![]);
+        world.auto_spawn = true;
+        let mut clock = SimClock::default();
+        clock.toggle_pause();
+
+        for _ in 0..60 {
+            for dt in clock.advance(DT) {
+                world.simulate(dt, BORDER);
+            }
+        }
+        assert_eq!(world.balls.len(), 0);
+        assert_eq!(world.time(), 0.);
+
+        clock.step();
+        for dt in clock.advance(DT) {
+            world.simulate(dt, BORDER);
+        }
+        assert!((world.time() - STEP_DT).abs() < 1e-6);
+    }
+}
+
+mod events {
+    use super::*;
+    use crate::{
+        balls::MouseSpring,
+        physics::events::{PhysicsEvent, Wall},
+    };
+
+    const FLOOR: f32 = BORDER.1 - 10.;
+
+    ///Events of the next `steps` steps.
+    fn step_events(world: &mut Balls, steps: u32) -> Vec<PhysicsEvent> {
+        world.events().drain();
+        run(world, steps);
+        world.events().drain()
+    }
+
+    #[test]
+    fn contact_events_are_sorted_and_reproducible() {
+        let pile = || world(grid(30, 5, [350., 400.], [21., 21.], 10.));
+        let (mut first, mut second) = (pile(), pile());
+
+        for _ in 0..60 {
+            let events = step_events(&mut first, 1);
+            assert_eq!(events, step_events(&mut second, 1));
+
+            let pairs: Vec<(usize, usize)> = events
+                .iter()
+                .filter_map(|event| match *event {
+                    PhysicsEvent::ContactBegin { a, b, .. }
+                    | PhysicsEvent::ContactPersist { a, b, .. } => Some((a, b)),
+                    _ => None,
+                })
+                .collect();
+            assert!(pairs.is_sorted(), "contacts out of order : {pairs:?}");
+        }
+    }
+
+    #[test]
+    fn spawns_and_removals_are_reported() {
+        let mut world = world(vec![]);
+        let id = world.next_id();
+        world.push_ball(ball(10., [400., 300.], [0., 0.], id));
+        assert_eq!(world.events().drain(), [PhysicsEvent::Spawned { id }]);
+
+        world.remove_ball(id);
+        assert_eq!(world.events().drain(), [PhysicsEvent::Removed { id }]);
+        assert!(world.remove_ball(id).is_none());
+        assert!(world.events().drain().is_empty());
+    }
+
+    #[test]
+    fn contacts_begin_persist_and_end() {
+        //ball 1 rests on ball 0, pressed into it by gravity every step
+        let mut world = world(vec![
+            ball(10., [400., FLOOR], [0., 0.], 0),
+            ball(10., [400., FLOOR - 20.], [0., 0.], 1),
+        ]);
+
+        let events = step_events(&mut world, 1);
+        assert!(events.iter().any(|event| matches!(
+            event,
+            PhysicsEvent::ContactBegin { a: 0, b: 1, impulse } if *impulse > 0.
+        )));
+
+        let events = step_events(&mut world, 1);
+        assert!(
+            events
+                .iter()
+                .any(|event| matches!(event, PhysicsEvent::ContactPersist { a: 0, b: 1, .. }))
+        );
+        assert!(
+            !events
+                .iter()
+                .any(|event| matches!(event, PhysicsEvent::ContactBegin { .. }))
+        );
+
+        world.edit_ball(1, |ball| ball.position = [100., 100.].into());
+        let events = step_events(&mut world, 1);
+        assert!(events.contains(&PhysicsEvent::ContactEnd { a: 0, b: 1 }));
+        assert!(step_events(&mut world, 1).iter().all(|event| !matches!(
+            event,
+            PhysicsEvent::ContactBegin { .. }
+                | PhysicsEvent::ContactPersist { .. }
+                | PhysicsEvent::ContactEnd { .. }
+        )));
+    }
+
+    #[test]
+    fn wall_hits_are_reported() {
+        let mut world = world(vec![
+            ball(10., [400., BORDER.1 - 15.], [0., 300.], 0),
+            ball(10., [15., 300.], [-300., 0.], 1),
+        ]);
+
+        let events = step_events(&mut world, 1);
+        assert!(events.iter().any(|event| matches!(
+            event,
+            PhysicsEvent::BorderHit { id: 0, wall: Wall::Bottom, impulse } if *impulse > 0.
+        )));
+        assert!(events.iter().any(|event| matches!(
+            event,
+            PhysicsEvent::BorderHit {
+                id: 1,
+                wall: Wall::Left,
+                ..
+            }
+        )));
+    }
+
+    #[test]
+    fn resting_balls_sleep_and_wake_when_hit() {
+        let mut world = world(vec![ball(10., [400., FLOOR], [0., 0.], 0)]);
+        let events = step_events(&mut world, 120);
+        assert!(events.contains(&PhysicsEvent::Sleep { id: 0 }));
+        assert!(world.ball(0).unwrap().sleeping);
+
+        world.push_ball(ball(10., [400., FLOOR - 25.], [0., 800.], 1));
+        let events = step_events(&mut world, 1);
+        assert!(events.contains(&PhysicsEvent::Wake { id: 0 }));
+        assert!(!world.ball(0).unwrap().sleeping);
+    }
+
+    #[test]
+    fn soft_hits_leave_sleeping_balls_still() {
+        let mut world = world(vec![ball(10., [400., FLOOR], [0., 0.], 0)]);
+        run(&mut world, 120);
+        assert!(world.ball(0).unwrap().sleeping);
+
+        world.push_ball(ball(10., [380.5, FLOOR], [5., 0.], 1));
+        run(&mut world, 1);
+        let sleeper = world.ball(0).unwrap();
+        assert!(sleeper.sleeping);
+        assert_eq!((sleeper.speed[0], sleeper.speed[1]), (0., 0.));
+    }
+
+    #[test]
+    fn sleeping_balls_fall_when_their_support_moves_away() {
+        //ball 0 is held by the mouse so it never sleeps, ball 1 sleeps on top of it
+        let mut world = world(vec![
+            ball(10., [400., FLOOR], [0., 0.], 0),
+            ball(10., [400., FLOOR - 20.], [0., 0.], 1),
+        ]);
+        let hold = |x: f32| {
+            Some(MouseSpring {
+                id: 0,
+                target: [x, FLOOR],
+                stiffness: 400.,
+                damping: 40.,
+            })
+        };
+        world.set_mouse_spring(hold(400.));
+        run(&mut world, 150);
+        assert!(world.ball(1).unwrap().sleeping);
+        let rest = world.ball(1).unwrap().position[1];
+
+        world.events().drain();
+        for i in 1..=60 {
+            world.set_mouse_spring(hold(400. + i as f32));
+            run(&mut world, 1);
+        }
+        assert!(
+            world
+                .events()
+                .drain()
+                .contains(&PhysicsEvent::Wake { id: 1 })
+        );
+        assert!(world.ball(1).unwrap().position[1] > rest + 1.);
+    }
+}
+
+mod body {
+    use my_glium_util::math::Vec2;
+
+    use super::*;
+    use crate::physics::{
+        constants::{FRICTION_COEF, GRAVITY_CONST},
+        contact::resolve_contact,
+        geometry::Shape,
+        traits::Body,
+    };
+
+    ///A body that is not a ball : a heavy disc without forces nor sleep.
+    struct Puck {
+        position: Vec2,
+        velocity: Vec2,
+        force: Vec2,
+    }
+
+    impl Body for Puck {
+        fn position(&self) -> Vec2 {
+            self.position
+        }
+
+        fn set_position(&mut self, position: Vec2) {
+            self.position = position;
+        }
+
+        fn velocity(&self) -> Vec2 {
+            self.velocity
+        }
+
+        fn set_velocity(&mut self, velocity: Vec2) {
+            self.velocity = velocity;
+        }
+
+        fn mass(&self) -> f32 {
+            1000.
+        }
+
+        fn bounce(&self) -> f32 {
+            1.
+        }
+
+        fn bounding_shape(&self) -> Shape {
+            Shape::Circle {
+                center: self.position,
+                radius: 20.,
+            }
+        }
+
+        fn is_dynamic(&self) -> bool {
+            true
+        }
+
+        fn reset_forces(&mut self) {
+            self.force = [0.; 2].into();
+        }
+
+        fn apply_force(&mut self, force: Vec2) {
+            self.force += force;
+        }
+
+        fn integrate(&mut self, dt: f32) {
+            self.velocity += self.force * (dt / self.mass());
+            self.position += self.velocity * dt;
+        }
+    }
+
+    fn puck(pos: [f32; 2], speed: [f32; 2]) -> Puck {
+        Puck {
+            position: pos.into(),
+            velocity: speed.into(),
+            force: [0.; 2].into(),
+        }
+    }
+
+    #[test]
+    fn any_bodies_collide() {
+        let mut puck = puck([100., 100.], [50., 0.]);
+        let mut ball = ball(10., [125., 100.], [-50., 0.], 0);
+        let before = [
+            puck.mass() * puck.velocity[0] + ball.mass * ball.speed[0],
+            puck.mass() * puck.velocity[1] + ball.mass * ball.speed[1],
+        ];
+
+        let impulse = resolve_contact(&mut puck, &mut ball)
+            .expect("the bodies touch")
+            .impulse;
+        assert!(impulse > 0.);
+        assert!(ball.speed[0] > 0., "the ball bounced back");
+        assert!(ball.position[0] - puck.position[0] >= 30. - 1e-3);
+        let after = [
+            puck.mass() * puck.velocity[0] + ball.mass * ball.speed[0],
+            puck.mass() * puck.velocity[1] + ball.mass * ball.speed[1],
+        ];
+        assert!((after[0] - before[0]).abs() < 1e-2 * before[0].abs());
+        assert!((after[1] - before[1]).abs() < 1e-3);
+
+        let mut far = ball(10., [300., 100.], [0., 0.], 1);
+        assert!(resolve_contact(&mut puck, &mut far).is_none());
+    }
+
+    #[test]
+    fn forces_and_impulses_scale_with_the_mass() {
+        let mut light = ball(2., [100., 100.], [0., 0.], 0);
+        let mut heavy = ball(20., [200., 100.], [0., 0.], 1);
+        for ball in [&mut light, &mut heavy] {
+            ball.apply_impulse([ball.mass, 0.].into());
+            assert!((ball.speed[0] - 1.).abs() < 1e-5);
+
+            ball.reset_forces();
+            ball.apply_force([0., ball.mass * 60.].into());
+            ball.integrate(0.5);
+            assert!((ball.speed[1] - 30.).abs() < 1e-3);
+        }
+
+        light.do_physics = false;
+        assert_eq!(light.inverse_mass(), 0.);
+    }
+
+    #[test]
+    fn gravity_and_drag_are_forces() {
+        let mut puck = puck([100., 100.], [10., 0.]);
+        puck.apply_gravity();
+        puck.apply_drag();
+        let mass = puck.mass();
+
+        assert!((puck.force[0] + 10. * FRICTION_COEF * mass).abs() < 1e-3);
+        assert!((puck.force[1] - GRAVITY_CONST * mass * mass).abs() < 1.);
+    }
+
+    #[test]
+    fn static_balls_are_not_moved_by_collisions() {
+        let mut wall = ball(10., [100., 100.], [0., 0.], 0);
+        wall.do_physics = false;
+        let mut ball = ball(10., [115., 100.], [-50., 0.], 1);
+
+        resolve_contact(&mut wall, &mut ball).expect("the balls touch");
+        assert_eq!((wall.position[0], wall.position[1]), (100., 100.));
+        assert_eq!((wall.speed[0], wall.speed[1]), (0., 0.));
+        assert!(ball.position[0] >= 120. - 1e-3);
+        assert!(ball.speed[0] > 0.);
+
+        ball.do_physics = false;
+        ball.position = [110., 100.].into();
+        assert!(resolve_contact(&mut wall, &mut ball).is_none());
+    }
+
+    #[test]
+    fn bounding_box_follows_the_ball() {
+        let ball = ball(7., [30., 40.], [0., 0.], 0);
+        let aabb = ball.bounding_box();
+
+        assert_eq!(
+            (aabb.center.x, aabb.center.y, aabb.half_dim),
+            (30., 40., 7.)
+        );
+        assert_eq!(
+            (aabb.center.x, aabb.half_dim),
+            (
+                Balls::broad_phase_range(&ball).center.x,
+                Balls::broad_phase_range(&ball).half_dim
+            )
+        );
+    }
+}
+
+mod shapes {
+    use my_glium_util::math::Vec2;
+
+    use super::*;
+    use crate::{
+        balls::history::History,
+        physics::{
+            collision::collide,
+            geometry::{BodyShape, ConvexPolygon, Shape},
+            traits::Body,
+        },
+        scene::{Scene, SceneError},
+    };
+
+    fn shaped(shape: BodyShape, pos: [f32; 2], id: usize) -> Ball {
+        Ball::new(1., pos, id).with_shape(shape)
+    }
+
+    fn crate_at(pos: [f32; 2], id: usize) -> Ball {
+        shaped(
+            BodyShape::Box {
+                half_extents: [10., 10.],
+            },
+            pos,
+            id,
+        )
+    }
+
+    fn capsule(a: [f32; 2], b: [f32; 2], radius: f32) -> Shape {
+        Shape::Capsule {
+            ends: [a.into(), b.into()],
+            radius,
+        }
+    }
+
+    fn assert_near(got: Vec2, expected: [f32; 2]) {
+        assert!(
+            (got[0] - expected[0]).abs() < 1e-3 && (got[1] - expected[1]).abs() < 1e-3,
+            "got {got:?} instead of {expected:?}"
+        );
+    }
+
+    #[test]
+    fn stacked_boxes_touch_on_two_points() {
+        let (a, b) = (crate_at([100., 100.], 0), crate_at([103., 119.], 1));
+        let manifold =
+            collide(&a.bounding_shape(), &b.bounding_shape()).expect("the boxes overlap");
+
+        assert_near(manifold.normal, [0., 1.]);
+        assert_eq!(manifold.points().len(), 2);
+        for point in manifold.points() {
+            assert!((point.depth - 1.).abs() < 1e-3);
+            assert!((point.position[1] - 109.5).abs() < 1e-3);
+        }
+        let mut xs: Vec<f32> = manifold.points().iter().map(|p| p.position[0]).collect();
+        xs.sort_by(f32::total_cmp);
+        assert!((xs[0] - 93.).abs() < 1e-3 && (xs[1] - 110.).abs() < 1e-3);
+
+        let apart = crate_at([100., 121.], 2);
+        assert!(collide(&a.bounding_shape(), &apart.bounding_shape()).is_none());
+    }
+
+    #[test]
+    fn circles_hit_box_faces_and_corners() {
+        let crate_shape = crate_at([100., 100.], 0).bounding_shape();
+        let circle = |center: [f32; 2]| Shape::Circle {
+            center: center.into(),
+            radius: 5.,
+        };
+
+        let face = collide(&crate_shape, &circle([100., 87.])).expect("touching the top face");
+        assert_near(face.normal, [0., -1.]);
+        assert_eq!(face.points().len(), 1);
+        assert!((face.depth() - 2.).abs() < 1e-3);
+
+        let corner = collide(&crate_shape, &circle([113., 87.])).expect("touching the corner");
+        let diagonal = std::f32::consts::FRAC_1_SQRT_2;
+        assert_near(corner.normal, [diagonal, -diagonal]);
+        assert!((corner.depth() - (5. - 18f32.sqrt())).abs() < 1e-3);
+
+        //the center inside the box is pushed out through the closest face
+        let inside = collide(&crate_shape, &circle([107., 100.])).expect("inside the box");
+        assert_near(inside.normal, [1., 0.]);
+        assert!((inside.depth() - 8.).abs() < 1e-3);
+
+        assert!(collide(&crate_shape, &circle([117., 83.])).is_none());
+    }
+
+    #[test]
+    fn capsules_touch_along_their_sides() {
+        let lying = capsule([0., 0.], [20., 0.], 5.);
+
+        let parallel = collide(&lying, &capsule([10., 8.], [30., 8.], 5.)).expect("8 < 5 + 5");
+        assert_near(parallel.normal, [0., 1.]);
+        assert_eq!(parallel.points().len(), 2);
+        assert!(
+            parallel
+                .points()
+                .iter()
+                .all(|p| (p.depth - 2.).abs() < 1e-3)
+        );
+
+        let crossing = collide(&lying, &capsule([10., -10.], [10., 10.], 5.));
+        assert!(crossing.is_some_and(|manifold| manifold.depth() > 5.));
+
+        assert!(collide(&lying, &capsule([0., 11.], [20., 11.], 5.)).is_none());
+    }
+
+    #[test]
+    fn polygons_are_convex_hulls_around_their_centroid() {
+        let square = ConvexPolygon::new(&[[0., 0.], [4., 0.], [2., 1.], [4., 4.], [0., 4.]])
+            .expect("a square with a point inside");
+        assert_eq!(square.vertices().len(), 4);
+        assert!((square.area() - 16.).abs() < 1e-4);
+        let centroid = square
+            .vertices()
+            .iter()
+            .fold([0.; 2], |sum, v| [sum[0] + v[0], sum[1] + v[1]]);
+        assert!(centroid[0].abs() < 1e-4 && centroid[1].abs() < 1e-4);
+
+        assert!(ConvexPolygon::new(&[[0., 0.], [1., 1.], [2., 2.]]).is_none());
+        assert!(ConvexPolygon::regular(9, 10.).is_none());
+        assert!(ConvexPolygon::regular(8, 10.).is_some());
+    }
+
+    #[test]
+    fn dropped_box_rests_flat_on_the_floor() {
+        let plank = BodyShape::Box {
+            half_extents: [30., 10.],
+        };
+        let mut world = world(vec![shaped(plank, [400., 400.], 0)]);
+        run(&mut world, 300);
+
+        let plank = world.ball(0).unwrap();
+        assert!(
+            (plank.position[1] - (BORDER.1 - 10.)).abs() < 0.5,
+            "{:?}",
+            plank.position
+        );
+        assert!(plank.angle.abs() < 1e-2, "turned by {}", plank.angle);
+        assert!((plank.position[0] - 400.).abs() < 1.);
+    }
+
+    #[test]
+    fn box_hit_off_center_turns() {
+        let mut a = crate_at([100., 100.], 0);
+        let mut b = crate_at([115., 82.], 1);
+        b.speed = [0., 200.].into();
+
+        assert!(a.handle_collision_balls(&mut b, DT).is_some());
+        assert!(b.speed[1] < 200. && a.speed[1] > 0.);
+        //a is pushed down on its right side and b up on its left side, both turn clockwise
+        assert!(a.angular_speed > 0. && b.angular_speed > 0.);
+    }
+
+    #[test]
+    fn shapes_are_picked_by_their_outline() {
+        let mut capsule_ball = shaped(
+            BodyShape::Capsule {
+                half_length: 30.,
+                radius: 5.,
+            },
+            [200., 200.],
+            0,
+        );
+        capsule_ball.angle = std::f32::consts::FRAC_PI_2;
+        let world = world(vec![capsule_ball]);
+
+        assert_eq!(world.ball_at([200., 230.]), Some(0));
+        assert_eq!(world.ball_at([220., 200.]), None);
+    }
+
+    #[test]
+    fn scenes_describe_shapes() {
+        let scene = Scene::from_json(
+            r#"{
+                "width": 800, "height": 600,
+                "balls": [
+                    {
+                        "shape": { "box": { "half_extents": [40, 10] } },
+                        "position": [400, 100], "angle": 0.5
+                    },
+                    {
+                        "shape": { "capsule": { "half_length": 20, "radius": 5 } },
+                        "position": [100, 100]
+                    },
+                    {
+                        "shape": { "polygon": { "vertices": [[0, 0], [30, 0], [0, 30]] } },
+                        "position": [600, 100], "mass": 3
+                    },
+                    { "size": 10, "position": [200, 100] }
+                ]
+            }"#,
+        )
+        .unwrap();
+        let world = scene.build();
+
+        let plank = world.ball(0).unwrap();
+        assert_eq!(plank.angle, 0.5);
+        assert!((plank.size - 1700f32.sqrt()).abs() < 1e-3);
+        assert!((plank.mass - 1600. / std::f32::consts::PI).abs() < 1e-2);
+        assert_eq!(world.ball(1).unwrap().size, 25.);
+        assert_eq!(world.ball(2).unwrap().mass, 3.);
+        assert!(world.ball(3).unwrap().is_circle());
+
+        let flat = r#"{ "width": 800, "height": 600, "balls": [
+            { "size": 10, "position": [200, 100] },
+            { "shape": { "polygon": { "vertices": [[0, 0], [1, 1], [2, 2]] } }, "position": [0, 0] }
+        ] }"#;
+        assert!(matches!(
+            Scene::from_json(flat),
+            Err(SceneError::InvalidShape(1))
+        ));
+    }
+
+    #[test]
+    fn history_keeps_the_rotation() {
+        let mut spinning = crate_at([400., 200.], 0);
+        spinning.angular_speed = 3.;
+        let mut world = world(vec![spinning]);
+        let mut history = History::new(10.);
+        let mut expected = vec![];
+        for _ in 0..30 {
+            run(&mut world, 1);
+            history.record(&world);
+            expected.push(world.snapshot());
+        }
+
+        for (i, expected) in expected.iter().enumerate() {
+            let (got, expected) = (&history.get(i).unwrap().balls[0], &expected.balls[0]);
+            assert_eq!(got.shape, expected.shape);
+            //one quantization step
+            assert!((got.angle - expected.angle).abs() <= 1. / 1024.);
+            assert!((got.angular_speed - expected.angular_speed).abs() <= 1. / 256.);
+        }
+    }
+}
+
+mod fluid {
+    use my_glium_util::math::{EuclidianSpace, Vec2};
+
+    use super::*;
+    use crate::{
+        physics::fluid::{FluidParams, FluidParticle},
+        scene::{Scene, SceneError},
+    };
+
+    fn particle(position: [f32; 2], velocity: [f32; 2]) -> FluidParticle {
+        FluidParticle {
+            position: position.into(),
+            velocity: velocity.into(),
+            mass: 16.,
+        }
+    }
+
+    ///Every particle is a neighbour of every other, the kernels cut off the far ones.
+    fn all_neighbours(count: usize) -> Vec<Vec<usize>> {
+        vec![(0..count).collect(); count]
+    }
+
+    #[test]
+    fn kernel_integrates_to_one() {
+        let params = FluidParams::new(4.);
+        let h = params.smoothing_radius;
+        let step = h / 100.;
+        let integral: f32 = (0..100)
+            .map(|i| {
+                let r = (i as f32 + 0.5) * step;
+                params.kernel(r) * std::f32::consts::TAU * r * step
+            })
+            .sum();
+
+        assert!((integral - 1.).abs() < 1e-2, "{integral}");
+        assert_eq!(params.kernel(h), 0.);
+    }
+
+    #[test]
+    fn touching_particles_are_at_rest_density() {
+        let params = FluidParams::new(4.);
+        let particles: Vec<FluidParticle> = (0..121)
+            .map(|i| particle([8. * (i % 11) as f32, 8. * (i / 11) as f32], [0., 0.]))
+            .collect();
+        let densities = params.densities(&particles, &all_neighbours(particles.len()));
+
+        //the center of the lattice sees all of its neighbours
+        let center = densities[60];
+        assert!(
+            (center - params.rest_density).abs() < 1e-3 * params.rest_density,
+            "{center} {}",
+            params.rest_density
+        );
+        assert!(params.pressure(center) < 1e-2 * params.stiffness * params.rest_density);
+        //the corners see less of them, without pulling
+        assert!(densities[0] < params.rest_density);
+        assert_eq!(params.pressure(densities[0]), 0.);
+    }
+
+    #[test]
+    fn pressure_pushes_close_particles_apart() {
+        let mut params = FluidParams::new(4.);
+        params.rest_density = 0.;
+        params.viscosity = 0.;
+        let particles = [
+            particle([100., 100.], [0., 0.]),
+            particle([104., 103.], [0., 0.]),
+        ];
+        let neighbours = all_neighbours(2);
+        let densities = params.densities(&particles, &neighbours);
+        let accelerations = params.accelerations(&particles, &neighbours, &densities);
+
+        let apart = particles[1].position - particles[0].position;
+        assert!(accelerations[0].dot(apart) < 0.);
+        assert!(accelerations[1].dot(apart) > 0.);
+        let total = accelerations[0] + accelerations[1];
+        assert!(total.dot(total).sqrt() < 1e-3 * accelerations[0].dot(accelerations[0]).sqrt());
+    }
+
+    #[test]
+    fn viscosity_brings_velocities_together() {
+        let mut params = FluidParams::new(4.);
+        params.stiffness = 0.;
+        let particles = [
+            particle([100., 100.], [50., 0.]),
+            particle([106., 100.], [-10., 0.]),
+        ];
+        let neighbours = all_neighbours(2);
+        let densities = params.densities(&particles, &neighbours);
+        let accelerations = params.accelerations(&particles, &neighbours, &densities);
+
+        assert!(accelerations[0][0] < 0.);
+        assert!(accelerations[1][0] > 0.);
+        assert!((accelerations[0][0] + accelerations[1][0]).abs() < 1e-3);
+    }
+
+    #[test]
+    fn fluid_column_spreads_on_the_floor() {
+        let width = |world: &Balls| {
+            let xs: Vec<f32> = world.iter().map(|ball| ball.position[0]).collect();
+            xs.iter().cloned().fold(f32::MIN, f32::max)
+                - xs.iter().cloned().fold(f32::MAX, f32::min)
+        };
+        let column = |fluid: Option<FluidParams>| {
+            let mut world = world(grid(60, 5, [400., BORDER.1 - 4.], [8., -8.], 4.));
+            world.set_fluid(fluid);
+            run(&mut world, 300);
+            world
+        };
+
+        let fluid = column(Some(FluidParams::new(4.)));
+        assert_eq!(fluid.balls.len(), 60);
+        assert!(fluid.iter().all(|ball| {
+            ball.position[1] <= BORDER.1 && ball.speed.distance(Vec2::from([0.; 2])).is_finite()
+        }));
+        assert!(width(&fluid) > 2. * width(&column(None)));
+    }
+
+    #[test]
+    fn scenes_describe_the_fluid() {
+        let scene = Scene::from_json(
+            r#"{ "width": 800, "height": 600,
+                "fluid": { "particle_radius": 4, "viscosity": 50 },
+                "balls": [{ "size": 4, "position": [50, 50] }]
+            }"#,
+        )
+        .unwrap();
+        let params = scene.build().fluid().unwrap();
+        assert_eq!(params.viscosity, 50.);
+        assert_eq!(
+            params,
+            FluidParams {
+                viscosity: 50.,
+                ..FluidParams::new(4.)
+            }
+        );
+
+        let flat = r#"{ "width": 800, "height": 600, "fluid": { "particle_radius": 0 } }"#;
+        assert!(matches!(
+            Scene::from_json(flat),
+            Err(SceneError::InvalidFluid)
+        ));
+    }
+}
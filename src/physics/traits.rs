@@ -1,9 +1,93 @@
-//! Définition of a physics trait, that represent all object that have physics simulation
+//! Définition of the body trait, that represent all object that have physics simulation
 
-pub trait Physics {
-    // fn get_acc(&mut self)->&mut Vec2;
-    // fn get_vel(&mut self)->&mut Vec2;
-    // fn get_pos(&mut self)->&mut Vec2;
+use my_glium_util::{datastruct::aabb::Aabb, math::Vec2};
 
-    fn physics_update(&mut self, dt: f32);
+use super::{
+    constants::{FRICTION_COEF, GRAVITY_CONST},
+    geometry::{Shape, cross},
+};
+
+///A body moved by the world. The world accumulates forces on it during a sub step, then integrates
+///them, and makes it collide with the other bodies through its shape.
+pub trait Body {
+    fn position(&self) -> Vec2;
+    fn set_position(&mut self, position: Vec2);
+    fn velocity(&self) -> Vec2;
+    fn set_velocity(&mut self, velocity: Vec2);
+    fn mass(&self) -> f32;
+    ///Restitution of the collisions of the body, from 0 (no bounce) to 1.
+    fn bounce(&self) -> f32;
+    ///Shape of the body, in world coordinates.
+    fn bounding_shape(&self) -> Shape;
+    ///Whether the world moves the body, a body not moved still collides.
+    fn is_dynamic(&self) -> bool;
+
+    ///Forget the forces of the last sub step.
+    fn reset_forces(&mut self);
+    ///Add a force (in mass units times world units per second squared) for the current sub step.
+    fn apply_force(&mut self, force: Vec2);
+    ///Move the body with its forces and velocity for `dt` seconds.
+    fn integrate(&mut self, dt: f32);
+
+    ///In radians per second, 0 for a body that does not turn.
+    fn angular_velocity(&self) -> f32 {
+        0.
+    }
+    fn set_angular_velocity(&mut self, _angular_velocity: f32) {}
+    ///0 for a body that is not turned by the collisions.
+    fn inverse_inertia(&self) -> f32 {
+        0.
+    }
+
+    ///Pull the body down, with an acceleration of `GRAVITY_CONST` times its mass.
+    fn apply_gravity(&mut self) {
+        if !self.is_dynamic() {
+            return;
+        }
+        let mass = self.mass();
+        self.apply_force(Vec2::from([0., GRAVITY_CONST * mass * mass]));
+    }
+
+    ///Slow the body down in proportion to its velocity.
+    fn apply_drag(&mut self) {
+        if !self.is_dynamic() {
+            return;
+        }
+        let force = self.velocity() * (-FRICTION_COEF * self.mass());
+        self.apply_force(force);
+    }
+
+    ///Change the velocity by `impulse / mass`.
+    fn apply_impulse(&mut self, impulse: Vec2) {
+        let velocity = self.velocity() + impulse * self.inverse_mass();
+        self.set_velocity(velocity);
+    }
+
+    ///Apply `impulse` at `point` (in world coordinates), turning the body when off its position.
+    fn apply_impulse_at(&mut self, impulse: Vec2, point: Vec2) {
+        self.apply_impulse(impulse);
+        let arm = point - self.position();
+        let angular_velocity =
+            self.angular_velocity() + cross(arm, impulse) * self.inverse_inertia();
+        self.set_angular_velocity(angular_velocity);
+    }
+
+    ///Velocity of the point of the body at `point` (in world coordinates).
+    fn velocity_at(&self, point: Vec2) -> Vec2 {
+        let arm = point - self.position();
+        self.velocity() + Vec2::from([-arm[1], arm[0]]) * self.angular_velocity()
+    }
+
+    ///0 for a body that is not moved by the collisions.
+    fn inverse_mass(&self) -> f32 {
+        if self.is_dynamic() {
+            1. / self.mass().max(f32::EPSILON)
+        } else {
+            0.
+        }
+    }
+
+    fn bounding_box(&self) -> Aabb<f32> {
+        self.bounding_shape().bounding_box()
+    }
 }
@@ -0,0 +1,139 @@
+//! Camera : the part of the world shown in the window. The physics is in world units, what happens
+//! to the world when the window is resized is set by a `ResizePolicy`.
+//!
+//! Screen and world coordinates both have `(0, 0)` at the top left and y going down.
+
+use std::str::FromStr;
+
+pub const MIN_ZOOM: f32 = 0.05;
+pub const MAX_ZOOM: f32 = 50.;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Camera {
+    ///World point at the center of the window.
+    pub center: [f32; 2],
+    ///Pixels per world unit.
+    pub zoom: f32,
+    ///Size of the window, in pixels.
+    pub viewport: [f32; 2],
+}
+
+impl Camera {
+    pub fn new(center: [f32; 2], zoom: f32, viewport: [f32; 2]) -> Self {
+        Self {
+            center,
+            zoom: zoom.clamp(MIN_ZOOM, MAX_ZOOM),
+            viewport,
+        }
+    }
+
+    ///Camera drawing screen coordinates as they are, for the overlays placed in pixels.
+    pub fn screen(viewport: [f32; 2]) -> Self {
+        Self::new([viewport[0] / 2., viewport[1] / 2.], 1., viewport)
+    }
+
+    ///Camera showing the whole world, from `(0, 0)` to `world_size`.
+    pub fn fit(world_size: (f32, f32), viewport: [f32; 2]) -> Self {
+        let zoom = (viewport[0] / world_size.0).min(viewport[1] / world_size.1);
+        Self::new([world_size.0 / 2., world_size.1 / 2.], zoom, viewport)
+    }
+
+    pub fn world_to_screen(&self, p: [f32; 2]) -> [f32; 2] {
+        [
+            (p[0] - self.center[0]) * self.zoom + self.viewport[0] / 2.,
+            (p[1] - self.center[1]) * self.zoom + self.viewport[1] / 2.,
+        ]
+    }
+
+    pub fn screen_to_world(&self, p: [f32; 2]) -> [f32; 2] {
+        [
+            (p[0] - self.viewport[0] / 2.) / self.zoom + self.center[0],
+            (p[1] - self.viewport[1] / 2.) / self.zoom + self.center[1],
+        ]
+    }
+
+    ///Keep the same center and zoom for the new window size.
+    pub fn set_viewport(&mut self, viewport: [f32; 2]) {
+        self.viewport = viewport;
+    }
+
+    ///Move the view with a mouse drag of `delta` pixels, the world follows the mouse.
+    pub fn pan(&mut self, delta: [f32; 2]) {
+        self.center[0] -= delta[0] / self.zoom;
+        self.center[1] -= delta[1] / self.zoom;
+    }
+
+    ///Multiply the zoom by `factor`, the world point under `screen_point` staying in place.
+    pub fn zoom_at(&mut self, screen_point: [f32; 2], factor: f32) {
+        let before = self.screen_to_world(screen_point);
+        self.zoom = (self.zoom * factor).clamp(MIN_ZOOM, MAX_ZOOM);
+        let after = self.screen_to_world(screen_point);
+
+        self.center[0] += before[0] - after[0];
+        self.center[1] += before[1] - after[1];
+    }
+}
+
+///How the world follows the window when it is resized.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ResizePolicy {
+    ///The world keeps its size, and is shown whole with bars on the sides.
+    #[default]
+    Fixed,
+    ///The world grows (or shrinks) with the window, the balls stay in place.
+    Grow,
+    ///The world grows (or shrinks) with the window, the balls move with it.
+    Scale,
+}
+
+impl FromStr for ResizePolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "fixed" => Ok(Self::Fixed),
+            "grow" => Ok(Self::Grow),
+            "scale" => Ok(Self::Scale),
+            _ => Err(format!(
+                "unknown resize policy {s:?}, expected fixed, grow or scale"
+            )),
+        }
+    }
+}
+
+impl ResizePolicy {
+    ///Update the camera for a window resized to `viewport`, and give the new size of the world.
+    pub fn resize(
+        self,
+        world_size: (f32, f32),
+        camera: &mut Camera,
+        viewport: [f32; 2],
+    ) -> (f32, f32) {
+        // minimized window
+        if viewport[0] <= 0. || viewport[1] <= 0. {
+            camera.set_viewport(viewport);
+            return world_size;
+        }
+
+        match self {
+            ResizePolicy::Fixed => {
+                *camera = Camera::fit(world_size, viewport);
+                world_size
+            }
+            ResizePolicy::Grow | ResizePolicy::Scale => {
+                let world_size = (viewport[0] / camera.zoom, viewport[1] / camera.zoom);
+                *camera = Camera::new(
+                    [world_size.0 / 2., world_size.1 / 2.],
+                    camera.zoom,
+                    viewport,
+                );
+                world_size
+            }
+        }
+    }
+
+    ///Whether the balls move with the world.
+    pub fn scales_balls(self) -> bool {
+        self == ResizePolicy::Scale
+    }
+}
@@ -0,0 +1,387 @@
+//! Debug overlay : lines showing the physics internals (quadtree nodes, broad phase, contacts,
+//! velocities, sleeping balls and ball ids), drawn on top of the balls.
+
+use std::collections::{HashMap, HashSet};
+
+use glium::{
+    DrawParameters, Program, Surface, VertexBuffer,
+    backend::Facade,
+    implement_vertex,
+    index::{NoIndices, PrimitiveType},
+};
+use my_glium_util::math::{EuclidianSpace, Vec2};
+
+use super::{
+    camera::Camera,
+    instanced::RenderError,
+    shader::{self, DebugUniforms},
+};
+use crate::balls::{Balls, QUADTREE_CAPACITY, one_ball::Ball};
+
+const QUADTREE_COLOR: [f32; 3] = [0.3, 0.3, 0.3];
+const AWAKE_COLOR: [f32; 3] = [0.9, 0.9, 0.2];
+const SLEEPING_COLOR: [f32; 3] = [0.2, 0.4, 1.];
+const PAIR_COLOR: [f32; 3] = [0.6, 0.2, 0.8];
+const CONTACT_COLOR: [f32; 3] = [1., 0.5, 0.];
+const VELOCITY_COLOR: [f32; 3] = [0.2, 1., 0.2];
+const TEXT_COLOR: [f32; 3] = [1.; 3];
+const SOFT_BODY_COLOR: [f32; 3] = [0.7, 0.7, 0.7];
+
+///Length of the velocity vectors, in seconds of movement.
+const VELOCITY_SCALE: f32 = 0.1;
+///Length of the contact normals per unit of impulse over the mass of the pair.
+const IMPULSE_SCALE: f32 = 0.05;
+///Width of a digit, they are twice as high.
+const DIGIT_WIDTH: f32 = 4.;
+///Depth after which the quadtree nodes are not split anymore (coincident balls).
+const QUADTREE_MAX_DEPTH: u32 = 10;
+
+#[derive(Clone, Copy)]
+pub struct DebugVertex {
+    pub position: [f32; 2],
+    pub color: [f32; 3],
+}
+implement_vertex!(DebugVertex, position, color);
+
+///What the overlay shows.
+#[derive(Clone, Copy, Debug)]
+pub struct DebugLayers {
+    pub quadtree: bool,
+    ///Broad phase range of every ball (blue when sleeping) and the pairs it finds.
+    pub broad_phase: bool,
+    ///Contact points and normals, their length and label show the impulse.
+    pub contacts: bool,
+    pub velocities: bool,
+    pub ids: bool,
+}
+
+impl Default for DebugLayers {
+    fn default() -> Self {
+        Self {
+            quadtree: true,
+            broad_phase: true,
+            contacts: true,
+            velocities: true,
+            ids: true,
+        }
+    }
+}
+
+///Line list, in world coordinates like the physics.
+#[derive(Default)]
+pub struct DebugLines {
+    vertices: Vec<DebugVertex>,
+}
+
+impl DebugLines {
+    pub fn build(balls: &Balls, layers: DebugLayers) -> Self {
+        let mut lines = Self::default();
+        let all_balls: Vec<&Ball> = balls.iter().collect();
+
+        if layers.quadtree {
+            let positions: Vec<[f32; 2]> =
+                all_balls.iter().map(|b| *b.position.as_array()).collect();
+            let boundary = balls.boundary;
+            lines.quadtree_nodes(
+                [boundary.center.x, boundary.center.y],
+                boundary.half_dim,
+                &positions,
+                0,
+            );
+        }
+
+        if layers.broad_phase {
+            let mut pairs = HashSet::new();
+            for ball in &all_balls {
+                let range = Balls::broad_phase_range(ball);
+                let (min, max) = (
+                    [
+                        range.center.x - range.half_dim,
+                        range.center.y - range.half_dim,
+                    ],
+                    [
+                        range.center.x + range.half_dim,
+                        range.center.y + range.half_dim,
+                    ],
+                );
+                let color = if ball.sleeping {
+                    SLEEPING_COLOR
+                } else {
+                    AWAKE_COLOR
+                };
+                lines.rect(min.into(), max.into(), color);
+
+                for other in balls.balls.query_range(range) {
+                    if other.id != ball.id
+                        && pairs.insert((ball.id.min(other.id), ball.id.max(other.id)))
+                    {
+                        lines.line(ball.position, other.position, PAIR_COLOR);
+                    }
+                }
+            }
+        }
+
+        if layers.contacts {
+            let by_id: HashMap<usize, &Ball> = all_balls.iter().map(|b| (b.id, *b)).collect();
+            for ((a, b), impulse) in balls.contacts() {
+                let (Some(a), Some(b)) = (by_id.get(&a), by_id.get(&b)) else {
+                    continue;
+                };
+                let delta = b.position - a.position;
+                let dist = delta.dot(delta).sqrt();
+                if dist <= f32::EPSILON {
+                    continue;
+                }
+
+                let normal = delta * (1. / dist);
+                let point = a.position + normal * a.size;
+                let length = impulse / (a.mass + b.mass) * IMPULSE_SCALE;
+                lines.line(
+                    point - normal * length,
+                    point + normal * length,
+                    CONTACT_COLOR,
+                );
+                lines.number(
+                    impulse.round() as usize,
+                    point + Vec2::from([0., -3. * DIGIT_WIDTH]),
+                    CONTACT_COLOR,
+                );
+            }
+        }
+
+        for ball in &all_balls {
+            if layers.velocities {
+                lines.line(
+                    ball.position,
+                    ball.position + ball.speed * VELOCITY_SCALE,
+                    VELOCITY_COLOR,
+                );
+            }
+            if layers.ids {
+                lines.number(ball.id, ball.position, TEXT_COLOR);
+            }
+        }
+
+        lines
+    }
+
+    ///Springs of the soft bodies, shown with or without the overlay.
+    pub fn soft_bodies(&mut self, balls: &Balls) {
+        if balls.soft_bodies().is_empty() {
+            return;
+        }
+        let positions: HashMap<usize, Vec2> =
+            balls.iter().map(|ball| (ball.id, ball.position)).collect();
+        for spring in balls.soft_bodies().iter().flat_map(|body| &body.springs) {
+            if let (Some(&a), Some(&b)) = (positions.get(&spring.a), positions.get(&spring.b)) {
+                self.line(a, b, SOFT_BODY_COLOR);
+            }
+        }
+    }
+
+    pub fn vertices(&self) -> &[DebugVertex] {
+        &self.vertices
+    }
+
+    ///Number of lines.
+    pub fn len(&self) -> usize {
+        self.vertices.len() / 2
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.vertices.is_empty()
+    }
+
+    pub fn line(&mut self, a: Vec2, b: Vec2, color: [f32; 3]) {
+        self.vertices.push(DebugVertex {
+            position: *a.as_array(),
+            color,
+        });
+        self.vertices.push(DebugVertex {
+            position: *b.as_array(),
+            color,
+        });
+    }
+
+    pub fn rect(&mut self, min: Vec2, max: Vec2, color: [f32; 3]) {
+        let corners: [Vec2; 4] = [min, [max[0], min[1]].into(), max, [min[0], max[1]].into()];
+        for (a, b) in corners.iter().zip(corners.iter().cycle().skip(1)) {
+            self.line(*a, *b, color);
+        }
+    }
+
+    ///Seven segment digits of `value`, centered on `center`.
+    fn number(&mut self, value: usize, center: Vec2, color: [f32; 3]) {
+        ///Segments a to g, in a box one digit wide and two high.
+        const SEGMENTS: [([f32; 2], [f32; 2]); 7] = [
+            ([0., 0.], [1., 0.]),
+            ([1., 0.], [1., 1.]),
+            ([1., 1.], [1., 2.]),
+            ([0., 2.], [1., 2.]),
+            ([0., 1.], [0., 2.]),
+            ([0., 0.], [0., 1.]),
+            ([0., 1.], [1., 1.]),
+        ];
+        ///Bit i is lit when segment i is.
+        const DIGITS: [u8; 10] = [
+            0b0111111, 0b0000110, 0b1011011, 0b1001111, 0b1100110, 0b1101101, 0b1111101, 0b0000111,
+            0b1111111, 0b1101111,
+        ];
+
+        let text = value.to_string();
+        let advance = DIGIT_WIDTH * 1.5;
+        let origin = [
+            center[0] - (advance * text.len() as f32 - DIGIT_WIDTH * 0.5) / 2.,
+            center[1] - DIGIT_WIDTH,
+        ];
+        for (i, digit) in text.bytes().enumerate() {
+            let lit = DIGITS[(digit - b'0') as usize];
+            let x = origin[0] + advance * i as f32;
+            for (segment, (a, b)) in SEGMENTS.iter().enumerate() {
+                if lit & (1 << segment) != 0 {
+                    let point = |p: [f32; 2]| {
+                        Vec2::from([x + p[0] * DIGIT_WIDTH, origin[1] + p[1] * DIGIT_WIDTH])
+                    };
+                    self.line(point(*a), point(*b), color);
+                }
+            }
+        }
+    }
+
+    ///Boxes of the nodes the quadtree splits into for these positions. The quadtree does not
+    ///expose its nodes, so they are recomputed with the same capacity.
+    fn quadtree_nodes(
+        &mut self,
+        center: [f32; 2],
+        half_dim: f32,
+        positions: &[[f32; 2]],
+        depth: u32,
+    ) {
+        self.rect(
+            [center[0] - half_dim, center[1] - half_dim].into(),
+            [center[0] + half_dim, center[1] + half_dim].into(),
+            QUADTREE_COLOR,
+        );
+        if positions.len() <= QUADTREE_CAPACITY || depth >= QUADTREE_MAX_DEPTH {
+            return;
+        }
+
+        let half = half_dim / 2.;
+        for (right, bottom) in [(false, false), (true, false), (false, true), (true, true)] {
+            let quadrant: Vec<[f32; 2]> = positions
+                .iter()
+                .filter(|p| (p[0] >= center[0]) == right && (p[1] >= center[1]) == bottom)
+                .copied()
+                .collect();
+            let child_center = [
+                center[0] + if right { half } else { -half },
+                center[1] + if bottom { half } else { -half },
+            ];
+            self.quadtree_nodes(child_center, half, &quadrant, depth + 1);
+        }
+    }
+}
+
+pub struct DebugRenderer {
+    program: Program,
+    ///Grown when needed, only the vertices of the current draw are written.
+    vertices: VertexBuffer<DebugVertex>,
+}
+
+impl DebugRenderer {
+    ///`program` uses `shaders/debug_lines.*`.
+    pub fn new<F: Facade>(facade: &F, program: Program) -> Result<Self, RenderError> {
+        Self::check_program(&program)?;
+
+        Ok(Self {
+            program,
+            vertices: VertexBuffer::empty_dynamic(facade, 4096)?,
+        })
+    }
+
+    ///Replace the program (after a shader reload), the old one is kept if the new one is invalid.
+    pub fn set_program(&mut self, program: Program) -> Result<(), RenderError> {
+        Self::check_program(&program)?;
+        self.program = program;
+
+        Ok(())
+    }
+
+    fn check_program(program: &Program) -> Result<(), shader::ShaderError> {
+        shader::check_program(
+            "debug_lines",
+            program,
+            &DebugUniforms::default(),
+            &shader::attribute_names::<DebugVertex>(),
+        )
+    }
+
+    pub fn draw<F: Facade, S: Surface>(
+        &mut self,
+        facade: &F,
+        target: &mut S,
+        camera: &Camera,
+        lines: &DebugLines,
+    ) -> Result<(), RenderError> {
+        self.draw_vertices(
+            facade,
+            target,
+            camera,
+            lines.vertices(),
+            PrimitiveType::LinesList,
+        )
+    }
+
+    ///Draw filled triangles (the HUD), `Camera::screen` draws them in pixel coordinates.
+    pub fn draw_triangles<F: Facade, S: Surface>(
+        &mut self,
+        facade: &F,
+        target: &mut S,
+        camera: &Camera,
+        vertices: &[DebugVertex],
+    ) -> Result<(), RenderError> {
+        self.draw_vertices(
+            facade,
+            target,
+            camera,
+            vertices,
+            PrimitiveType::TrianglesList,
+        )
+    }
+
+    fn draw_vertices<F: Facade, S: Surface>(
+        &mut self,
+        facade: &F,
+        target: &mut S,
+        camera: &Camera,
+        vertices: &[DebugVertex],
+        primitive: PrimitiveType,
+    ) -> Result<(), RenderError> {
+        if vertices.is_empty() {
+            return Ok(());
+        }
+        if self.vertices.len() < vertices.len() {
+            self.vertices =
+                VertexBuffer::empty_dynamic(facade, vertices.len().next_power_of_two())?;
+        }
+        let slice = self
+            .vertices
+            .slice(0..vertices.len())
+            .expect("the vertex buffer has just been grown");
+        slice.write(vertices);
+
+        let (width, height) = target.get_dimensions();
+        target.draw(
+            slice,
+            NoIndices(primitive),
+            &self.program,
+            &DebugUniforms {
+                resolution: [width as f32, height as f32],
+                camera: camera.into(),
+            },
+            &DrawParameters::default(),
+        )?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,190 @@
+//! Metaball renderer of the fluid : every circle adds a smooth splat of its color to a field
+//! texture (see `shaders/fluid_splat.*`), and the liquid is drawn where the field is over a
+//! threshold (see `shaders/fluid.*`), so that close particles merge into one surface.
+
+use glium::{
+    Blend, BlendingFunction, DrawParameters, LinearBlendingFactor, Program, Surface, VertexBuffer,
+    backend::Facade,
+    framebuffer::SimpleFrameBuffer,
+    index::{NoIndices, PrimitiveType},
+    texture::{MipmapsOption, Texture2d, UncompressedFloatFormat},
+};
+
+use super::{
+    camera::Camera,
+    instanced::{BallInstance, QUAD, QuadVertex, RenderError},
+    shader::{self, FluidSplatUniforms, FluidUniforms, ShaderError},
+};
+use crate::balls::Balls;
+
+///Radius of the splat of a particle, in particle radii.
+const SPLAT_SCALE: f32 = 2.5;
+///Field over which a pixel is liquid. A lone particle is drawn about 1.2 times its radius, two
+///touching particles are joined.
+const THRESHOLD: f32 = 0.6;
+
+pub struct FluidRenderer {
+    quad: VertexBuffer<QuadVertex>,
+    ///Grown when needed, only the first instances are drawn.
+    instances: VertexBuffer<BallInstance>,
+    ///Sum of the splats, of the size of the target.
+    field: Texture2d,
+
+    splat_program: Program,
+    fluid_program: Program,
+}
+
+impl FluidRenderer {
+    ///`splat_program` uses `shaders/fluid_splat.*`, `fluid_program` uses `shaders/fluid.*`.
+    pub fn new<F: Facade>(
+        facade: &F,
+        splat_program: Program,
+        fluid_program: Program,
+    ) -> Result<Self, RenderError> {
+        let field = Self::field_texture(facade, 1, 1)?;
+        Self::check_programs(&splat_program, &fluid_program, &field)?;
+
+        Ok(Self {
+            quad: VertexBuffer::new(facade, &QUAD)?,
+            instances: VertexBuffer::empty_dynamic(facade, 1024)?,
+            field,
+
+            splat_program,
+            fluid_program,
+        })
+    }
+
+    ///Replace the programs (after a shader reload), the old ones are kept if the new ones are
+    ///invalid.
+    pub fn set_programs(
+        &mut self,
+        splat_program: Program,
+        fluid_program: Program,
+    ) -> Result<(), RenderError> {
+        Self::check_programs(&splat_program, &fluid_program, &self.field)?;
+        self.splat_program = splat_program;
+        self.fluid_program = fluid_program;
+
+        Ok(())
+    }
+
+    fn check_programs(
+        splat_program: &Program,
+        fluid_program: &Program,
+        field: &Texture2d,
+    ) -> Result<(), ShaderError> {
+        let quad_attributes = shader::attribute_names::<QuadVertex>();
+        let mut splat_attributes = shader::attribute_names::<BallInstance>();
+        splat_attributes.extend(quad_attributes.iter().cloned());
+
+        shader::check_program(
+            "fluid_splat",
+            splat_program,
+            &FluidSplatUniforms::default(),
+            &splat_attributes,
+        )?;
+        shader::check_program(
+            "fluid",
+            fluid_program,
+            &FluidUniforms {
+                field: Some(field),
+                ..FluidUniforms::default()
+            },
+            &quad_attributes,
+        )
+    }
+
+    ///Field texture accumulating more than 1 where the splats overlap.
+    fn field_texture<F: Facade>(
+        facade: &F,
+        width: u32,
+        height: u32,
+    ) -> Result<Texture2d, RenderError> {
+        Ok(Texture2d::empty_with_format(
+            facade,
+            UncompressedFloatFormat::F16F16F16F16,
+            MipmapsOption::NoMipmap,
+            width.max(1),
+            height.max(1),
+        )?)
+    }
+
+    ///Draw the circles of `balls` as a liquid, the other shapes are left to the other renderers.
+    pub fn draw<F: Facade, S: Surface>(
+        &mut self,
+        facade: &F,
+        target: &mut S,
+        camera: &Camera,
+        balls: &Balls,
+    ) -> Result<(), RenderError> {
+        let (width, height) = target.get_dimensions();
+        let resolution = [width as f32, height as f32];
+        let strip = NoIndices(PrimitiveType::TriangleStrip);
+        if self.field.dimensions() != (width.max(1), height.max(1)) {
+            self.field = Self::field_texture(facade, width, height)?;
+        }
+
+        let instances: Vec<BallInstance> = balls
+            .iter()
+            .filter(|ball| ball.is_circle())
+            .map(BallInstance::from)
+            .collect();
+        if instances.is_empty() {
+            return Ok(());
+        }
+        if self.instances.len() < instances.len() {
+            self.instances =
+                VertexBuffer::empty_dynamic(facade, instances.len().next_power_of_two())?;
+        }
+        let slice = self
+            .instances
+            .slice(0..instances.len())
+            .expect("the instance buffer has just been grown");
+        slice.write(&instances);
+
+        let add = BlendingFunction::Addition {
+            source: LinearBlendingFactor::One,
+            destination: LinearBlendingFactor::One,
+        };
+        let mut field = SimpleFrameBuffer::new(facade, &self.field)?;
+        field.clear_color(0., 0., 0., 0.);
+        field.draw(
+            (
+                &self.quad,
+                slice
+                    .per_instance()
+                    .map_err(|_| RenderError::InstancingNotSupported)?,
+            ),
+            strip,
+            &self.splat_program,
+            &FluidSplatUniforms {
+                resolution,
+                camera: camera.into(),
+                splat_scale: SPLAT_SCALE,
+            },
+            &DrawParameters {
+                blend: Blend {
+                    color: add,
+                    alpha: add,
+                    constant_value: (0., 0., 0., 0.),
+                },
+                ..DrawParameters::default()
+            },
+        )?;
+        drop(field);
+
+        target.draw(
+            &self.quad,
+            strip,
+            &self.fluid_program,
+            &FluidUniforms {
+                resolution,
+                field: Some(&self.field),
+                threshold: THRESHOLD,
+            },
+            &DrawParameters::default(),
+        )?;
+
+        Ok(())
+    }
+}
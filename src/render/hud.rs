@@ -0,0 +1,242 @@
+//! Heads up display : frame rate, frame timing graph, ball count and the active parameters.
+
+use std::collections::VecDeque;
+
+use super::{
+    debug::DebugVertex,
+    text::{self, GLYPH_HEIGHT},
+};
+
+///Number of frames kept for the current frame rate and the timing graph.
+pub const HUD_FRAMES: usize = 120;
+
+///Pixels per font pixel.
+const TEXT_SCALE: f32 = 2.;
+const MARGIN: f32 = 8.;
+const PADDING: f32 = 6.;
+const LINE_SPACING: f32 = 2. * TEXT_SCALE;
+
+const BAR_WIDTH: f32 = 2.;
+///Height of the rewind timeline bar.
+const BAR_HEIGHT: f32 = 8.;
+const GRAPH_HEIGHT: f32 = 50.;
+///Frame time at the top of the graph, in seconds.
+const GRAPH_MAX_TIME: f32 = 1. / 30.;
+///Frame time of the reference line, in seconds.
+const GRAPH_TARGET_TIME: f32 = 1. / 60.;
+
+const PANEL_COLOR: [f32; 3] = [0.05; 3];
+const TEXT_COLOR: [f32; 3] = [1.; 3];
+const PHYSICS_COLOR: [f32; 3] = [1., 0.5, 0.];
+const RENDER_COLOR: [f32; 3] = [0.2, 0.5, 1.];
+const OTHER_COLOR: [f32; 3] = [0.5; 3];
+const TARGET_COLOR: [f32; 3] = [0.2, 1., 0.2];
+
+///Durations of a frame, in seconds.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct FrameTime {
+    pub total: f32,
+    ///Time spent in the physics steps of the frame.
+    pub physics: f32,
+    ///Time to issue the draw calls, without the wait for vsync of the buffer swap. It includes the
+    ///GPU work only in bench mode, which waits for it.
+    pub render: f32,
+}
+
+///What the HUD shows besides the frame timings.
+pub struct HudInfo {
+    pub average_fps: f32,
+    pub ball_count: usize,
+    ///Name and value of the parameters in use.
+    pub parameters: Vec<(&'static str, String)>,
+}
+
+#[derive(Default)]
+pub struct Hud {
+    ///Last `HUD_FRAMES` frames, the oldest first.
+    frames: VecDeque<FrameTime>,
+}
+
+impl Hud {
+    pub fn record(&mut self, frame: FrameTime) {
+        if self.frames.len() == HUD_FRAMES {
+            self.frames.pop_front();
+        }
+        self.frames.push_back(frame);
+    }
+
+    ///Frame rate over the recorded frames.
+    pub fn current_fps(&self) -> f32 {
+        let total: f32 = self.frames.iter().map(|f| f.total).sum();
+        if total <= 0. {
+            return 0.;
+        }
+
+        self.frames.len() as f32 / total
+    }
+
+    ///Mean durations over the recorded frames.
+    pub fn mean(&self) -> FrameTime {
+        if self.frames.is_empty() {
+            return FrameTime::default();
+        }
+
+        let n = self.frames.len() as f32;
+        let sum = self
+            .frames
+            .iter()
+            .fold(FrameTime::default(), |sum, f| FrameTime {
+                total: sum.total + f.total,
+                physics: sum.physics + f.physics,
+                render: sum.render + f.render,
+            });
+
+        FrameTime {
+            total: sum.total / n,
+            physics: sum.physics / n,
+            render: sum.render / n,
+        }
+    }
+
+    pub fn text(&self, info: &HudInfo) -> Vec<String> {
+        let mean = self.mean();
+        let parameters: Vec<String> = info
+            .parameters
+            .iter()
+            .map(|(name, value)| format!("{name} {value}"))
+            .collect();
+
+        vec![
+            format!("FPS {:.0}  AVG {:.0}", self.current_fps(), info.average_fps),
+            format!(
+                "FRAME {:.2}MS  PHYSICS {:.2}MS  RENDER {:.2}MS",
+                mean.total * 1000.,
+                mean.physics * 1000.,
+                mean.render * 1000.
+            ),
+            format!("BALLS {}", info.ball_count),
+            parameters.join("  "),
+        ]
+    }
+
+    ///Triangles of the HUD, in the top left corner of the window.
+    pub fn mesh(&self, info: &HudInfo) -> Vec<DebugVertex> {
+        let lines = self.text(info);
+        let line_height = GLYPH_HEIGHT as f32 * TEXT_SCALE + LINE_SPACING;
+        let graph_width = BAR_WIDTH * HUD_FRAMES as f32;
+        let text_width = lines
+            .iter()
+            .map(|line| text::text_size(line, TEXT_SCALE)[0])
+            .fold(0., f32::max);
+
+        let mut vertices = vec![];
+        let panel_max = [
+            MARGIN + 2. * PADDING + text_width.max(graph_width),
+            MARGIN + 2. * PADDING + lines.len() as f32 * line_height + GRAPH_HEIGHT,
+        ];
+        text::push_rect(&mut vertices, [MARGIN; 2], panel_max, PANEL_COLOR);
+
+        let left = MARGIN + PADDING;
+        for (i, line) in lines.iter().enumerate() {
+            let top = MARGIN + PADDING + i as f32 * line_height;
+            text::push_text(&mut vertices, line, [left, top], TEXT_SCALE, TEXT_COLOR);
+        }
+
+        //frame time graph, physics then render then the rest stacked from the bottom
+        let bottom = panel_max[1] - PADDING;
+        let height = |time: f32| (time / GRAPH_MAX_TIME * GRAPH_HEIGHT).min(GRAPH_HEIGHT);
+        for (i, frame) in self.frames.iter().enumerate() {
+            let x = left + i as f32 * BAR_WIDTH;
+            let mut top = bottom;
+            for (time, color) in [
+                (frame.physics, PHYSICS_COLOR),
+                (frame.render, RENDER_COLOR),
+                (
+                    (frame.total - frame.physics - frame.render).max(0.),
+                    OTHER_COLOR,
+                ),
+            ] {
+                let bar_top = (top - height(time)).max(bottom - GRAPH_HEIGHT);
+                if bar_top < top {
+                    text::push_rect(&mut vertices, [x, bar_top], [x + BAR_WIDTH, top], color);
+                }
+                top = bar_top;
+            }
+        }
+        let target = bottom - height(GRAPH_TARGET_TIME);
+        text::push_rect(
+            &mut vertices,
+            [left, target],
+            [left + graph_width, target + 1.],
+            TARGET_COLOR,
+        );
+
+        vertices
+    }
+}
+
+///Triangles of the rewind timeline at the bottom of a window of `viewport` pixels, `position`
+///going from 0 (the oldest frame kept) to 1 (the newest).
+pub fn timeline_mesh(position: f32, label: &str, viewport: [f32; 2]) -> Vec<DebugVertex> {
+    let mut vertices = vec![];
+    let line_height = GLYPH_HEIGHT as f32 * TEXT_SCALE + LINE_SPACING;
+    let panel_min = [
+        MARGIN,
+        viewport[1] - MARGIN - 2. * PADDING - line_height - BAR_HEIGHT,
+    ];
+    let panel_max = [viewport[0] - MARGIN, viewport[1] - MARGIN];
+    text::push_rect(&mut vertices, panel_min, panel_max, PANEL_COLOR);
+
+    let left = panel_min[0] + PADDING;
+    let top = panel_min[1] + PADDING;
+    text::push_text(&mut vertices, label, [left, top], TEXT_SCALE, TEXT_COLOR);
+
+    let (bar_min, bar_max) = (
+        [left, top + line_height],
+        [panel_max[0] - PADDING, top + line_height + BAR_HEIGHT],
+    );
+    text::push_rect(&mut vertices, bar_min, bar_max, OTHER_COLOR);
+    let x = bar_min[0] + (bar_max[0] - bar_min[0]) * position.clamp(0., 1.);
+    text::push_rect(
+        &mut vertices,
+        [x - BAR_WIDTH, bar_min[1]],
+        [x + BAR_WIDTH, bar_max[1]],
+        TARGET_COLOR,
+    );
+
+    vertices
+}
+
+///Triangles of a panel of text in the middle of a window of `viewport` pixels (the help).
+pub fn centered_text_mesh(lines: &[String], viewport: [f32; 2]) -> Vec<DebugVertex> {
+    let mut vertices = vec![];
+    let line_height = GLYPH_HEIGHT as f32 * TEXT_SCALE + LINE_SPACING;
+    let text_width = lines
+        .iter()
+        .map(|line| text::text_size(line, TEXT_SCALE)[0])
+        .fold(0., f32::max);
+    let size = [
+        text_width + 2. * PADDING,
+        lines.len() as f32 * line_height + 2. * PADDING,
+    ];
+    let panel_min = [
+        ((viewport[0] - size[0]) / 2.).max(0.),
+        ((viewport[1] - size[1]) / 2.).max(0.),
+    ];
+    text::push_rect(
+        &mut vertices,
+        panel_min,
+        [panel_min[0] + size[0], panel_min[1] + size[1]],
+        PANEL_COLOR,
+    );
+
+    for (i, line) in lines.iter().enumerate() {
+        let pos = [
+            panel_min[0] + PADDING,
+            panel_min[1] + PADDING + i as f32 * line_height,
+        ];
+        text::push_text(&mut vertices, line, pos, TEXT_SCALE, TEXT_COLOR);
+    }
+
+    vertices
+}
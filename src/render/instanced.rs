@@ -0,0 +1,251 @@
+//! Instanced renderer : every ball is a screen space quad, all drawn with a single draw call from
+//! one vertex buffer of per ball attributes (see `shaders/ball_instanced.vert`).
+
+use std::fmt;
+
+use glium::{
+    DrawError, DrawParameters, Program, Surface, VertexBuffer,
+    backend::Facade,
+    framebuffer::ValidationError,
+    implement_vertex,
+    index::{NoIndices, PrimitiveType},
+    texture::TextureCreationError,
+    vertex::BufferCreationError,
+};
+
+use super::{
+    TRAIL_TIME,
+    camera::Camera,
+    shader::{self, BackgroundUniforms, InstancedUniforms, ShaderError},
+};
+use crate::balls::{Balls, one_ball::Ball};
+
+#[derive(Clone, Copy)]
+pub(super) struct QuadVertex {
+    corner: [f32; 2],
+}
+implement_vertex!(QuadVertex, corner);
+
+///Triangle strip of the unit square, also covering the screen with `shaders/background.vert`.
+pub(super) const QUAD: [QuadVertex; 4] = [
+    QuadVertex { corner: [0., 0.] },
+    QuadVertex { corner: [1., 0.] },
+    QuadVertex { corner: [0., 1.] },
+    QuadVertex { corner: [1., 1.] },
+];
+
+#[derive(Clone, Copy)]
+pub struct BallInstance {
+    pub ball_position: [f32; 2],
+    pub radius: f32,
+    pub color: [f32; 3],
+    pub velocity: [f32; 2],
+    pub collision_pos: [f32; 2],
+}
+implement_vertex!(
+    BallInstance,
+    ball_position,
+    radius,
+    color,
+    velocity,
+    collision_pos
+);
+
+impl From<&Ball> for BallInstance {
+    fn from(ball: &Ball) -> Self {
+        Self {
+            ball_position: *ball.position.as_array(),
+            radius: ball.size,
+            color: ball.color,
+            velocity: *ball.speed.as_array(),
+            collision_pos: *ball.coliding_pos.as_array(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum RenderError {
+    Buffer(BufferCreationError),
+    Shader(ShaderError),
+    InstancingNotSupported,
+    Draw(DrawError),
+    Texture(TextureCreationError),
+    Framebuffer(ValidationError),
+}
+
+impl fmt::Display for RenderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RenderError::Buffer(e) => write!(f, "could not create a vertex buffer : {e}"),
+            RenderError::Shader(e) => write!(f, "{e}"),
+            RenderError::InstancingNotSupported => write!(f, "instancing is not supported"),
+            RenderError::Draw(e) => write!(f, "could not draw : {e}"),
+            RenderError::Texture(e) => write!(f, "could not create a texture : {e}"),
+            RenderError::Framebuffer(e) => write!(f, "could not draw to a texture : {e}"),
+        }
+    }
+}
+
+impl std::error::Error for RenderError {}
+
+impl From<BufferCreationError> for RenderError {
+    fn from(e: BufferCreationError) -> Self {
+        RenderError::Buffer(e)
+    }
+}
+
+impl From<ShaderError> for RenderError {
+    fn from(e: ShaderError) -> Self {
+        RenderError::Shader(e)
+    }
+}
+
+impl From<DrawError> for RenderError {
+    fn from(e: DrawError) -> Self {
+        RenderError::Draw(e)
+    }
+}
+
+impl From<TextureCreationError> for RenderError {
+    fn from(e: TextureCreationError) -> Self {
+        RenderError::Texture(e)
+    }
+}
+
+impl From<ValidationError> for RenderError {
+    fn from(e: ValidationError) -> Self {
+        RenderError::Framebuffer(e)
+    }
+}
+
+pub struct BallsRenderer {
+    quad: VertexBuffer<QuadVertex>,
+    ///Grown when needed, only the first `balls.len()` instances are drawn.
+    instances: VertexBuffer<BallInstance>,
+
+    ball_program: Program,
+    background_program: Program,
+}
+
+impl BallsRenderer {
+    ///`ball_program` uses `shaders/ball_instanced.*`, `background_program` uses `shaders/background.*`.
+    ///Both are checked against the uniforms and vertex formats they are drawn with.
+    pub fn new<F: Facade>(
+        facade: &F,
+        ball_program: Program,
+        background_program: Program,
+    ) -> Result<Self, RenderError> {
+        Self::check_programs(&ball_program, &background_program)?;
+
+        Ok(Self {
+            quad: VertexBuffer::new(facade, &QUAD)?,
+            instances: VertexBuffer::empty_dynamic(facade, 1024)?,
+
+            ball_program,
+            background_program,
+        })
+    }
+
+    ///Replace the programs (after a shader reload), the old ones are kept if the new ones are invalid.
+    pub fn set_programs(
+        &mut self,
+        ball_program: Program,
+        background_program: Program,
+    ) -> Result<(), RenderError> {
+        Self::check_programs(&ball_program, &background_program)?;
+        self.ball_program = ball_program;
+        self.background_program = background_program;
+
+        Ok(())
+    }
+
+    fn check_programs(
+        ball_program: &Program,
+        background_program: &Program,
+    ) -> Result<(), ShaderError> {
+        let quad_attributes = shader::attribute_names::<QuadVertex>();
+        let mut ball_attributes = shader::attribute_names::<BallInstance>();
+        ball_attributes.extend(quad_attributes.iter().cloned());
+
+        shader::check_program(
+            "ball_instanced",
+            ball_program,
+            &InstancedUniforms::default(),
+            &ball_attributes,
+        )?;
+        shader::check_program(
+            "background",
+            background_program,
+            &BackgroundUniforms::default(),
+            &quad_attributes,
+        )
+    }
+
+    pub fn draw_background<S: Surface>(&self, target: &mut S) -> Result<(), RenderError> {
+        let (width, height) = target.get_dimensions();
+        target.draw(
+            &self.quad,
+            NoIndices(PrimitiveType::TriangleStrip),
+            &self.background_program,
+            &BackgroundUniforms {
+                resolution: [width as f32, height as f32],
+            },
+            &DrawParameters::default(),
+        )?;
+
+        Ok(())
+    }
+
+    ///Draw the background, then the circles.
+    pub fn draw<F: Facade, S: Surface>(
+        &mut self,
+        facade: &F,
+        target: &mut S,
+        camera: &Camera,
+        balls: &Balls,
+    ) -> Result<(), RenderError> {
+        let (width, height) = target.get_dimensions();
+        let resolution = [width as f32, height as f32];
+        let strip = NoIndices(PrimitiveType::TriangleStrip);
+
+        self.draw_background(target)?;
+
+        //the other shapes are drawn by `shapes::shapes_mesh`
+        let instances: Vec<BallInstance> = balls
+            .iter()
+            .filter(|ball| ball.is_circle())
+            .map(BallInstance::from)
+            .collect();
+        if instances.is_empty() {
+            return Ok(());
+        }
+        if self.instances.len() < instances.len() {
+            self.instances =
+                VertexBuffer::empty_dynamic(facade, instances.len().next_power_of_two())?;
+        }
+        let slice = self
+            .instances
+            .slice(0..instances.len())
+            .expect("the instance buffer has just been grown");
+        slice.write(&instances);
+
+        target.draw(
+            (
+                &self.quad,
+                slice
+                    .per_instance()
+                    .map_err(|_| RenderError::InstancingNotSupported)?,
+            ),
+            strip,
+            &self.ball_program,
+            &InstancedUniforms {
+                resolution,
+                camera: camera.into(),
+                trail_time: TRAIL_TIME,
+            },
+            &DrawParameters::default(),
+        )?;
+
+        Ok(())
+    }
+}
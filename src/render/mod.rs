@@ -0,0 +1,17 @@
+//! Rendering of the balls, on the GPU or in software.
+
+pub mod camera;
+pub mod debug;
+pub mod fluid;
+pub mod hud;
+pub mod instanced;
+pub mod shader;
+pub mod shapes;
+pub mod software;
+pub mod text;
+
+///How long (in seconds) the velocity trail behind the balls lasts.
+pub const TRAIL_TIME: f32 = 0.1;
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,378 @@
+//! Loading of the shader programs, and the check that Rust provides everything they read.
+//!
+//! The shaders are embedded in the binary, an override directory can replace any of them and is
+//! watched so they can be edited while the app runs.
+//!
+//! A uniform or attribute the shader declares but nobody sends is silently zero in OpenGL, so every
+//! program is checked against the typed uniforms and vertex formats it is drawn with.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fmt, fs, io,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use glium::{
+    Program, ProgramCreationError,
+    backend::Facade,
+    texture::Texture2d,
+    uniforms::{AsUniformValue, UniformValue, Uniforms},
+    vertex::Vertex,
+};
+
+use super::camera::Camera;
+
+#[derive(Debug)]
+pub enum ShaderError {
+    Read {
+        path: PathBuf,
+        error: io::Error,
+    },
+    Compile {
+        name: String,
+        error: ProgramCreationError,
+    },
+    ///Active uniforms of the program that are never sent.
+    MissingUniforms {
+        name: String,
+        missing: Vec<String>,
+    },
+    ///Active vertex attributes of the program that no vertex buffer provides.
+    MissingAttributes {
+        name: String,
+        missing: Vec<String>,
+    },
+}
+
+impl fmt::Display for ShaderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ShaderError::Read { path, error } => {
+                write!(f, "could not read shader {} : {error}", path.display())
+            }
+            ShaderError::Compile { name, error } => {
+                write!(f, "could not compile the {name} shaders : {error}")
+            }
+            ShaderError::MissingUniforms { name, missing } => write!(
+                f,
+                "the {name} shaders read uniforms that are never set : {}",
+                missing.join(", ")
+            ),
+            ShaderError::MissingAttributes { name, missing } => write!(
+                f,
+                "the {name} shaders read vertex attributes that are never set : {}",
+                missing.join(", ")
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ShaderError {}
+
+///Uniforms of `shaders/ball_instanced.*`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct InstancedUniforms {
+    pub resolution: [f32; 2],
+    pub camera: CameraUniforms,
+    ///How long (in seconds) the velocity trail behind the balls lasts.
+    pub trail_time: f32,
+}
+
+impl Uniforms for InstancedUniforms {
+    fn visit_values<'a, F: FnMut(&str, UniformValue<'a>)>(&'a self, mut visit: F) {
+        visit("resolution", self.resolution.as_uniform_value());
+        self.camera.visit_values(&mut visit);
+        visit("trail_time", self.trail_time.as_uniform_value());
+    }
+}
+
+///World to screen transform of a `Camera`, see `shaders/debug_lines.vert`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CameraUniforms {
+    pub camera_center: [f32; 2],
+    pub camera_zoom: f32,
+}
+
+impl From<&Camera> for CameraUniforms {
+    fn from(camera: &Camera) -> Self {
+        Self {
+            camera_center: camera.center,
+            camera_zoom: camera.zoom,
+        }
+    }
+}
+
+impl Uniforms for CameraUniforms {
+    fn visit_values<'a, F: FnMut(&str, UniformValue<'a>)>(&'a self, mut visit: F) {
+        visit("camera_center", self.camera_center.as_uniform_value());
+        visit("camera_zoom", self.camera_zoom.as_uniform_value());
+    }
+}
+
+///Uniforms of `shaders/background.*`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct BackgroundUniforms {
+    pub resolution: [f32; 2],
+}
+
+impl Uniforms for BackgroundUniforms {
+    fn visit_values<'a, F: FnMut(&str, UniformValue<'a>)>(&'a self, mut visit: F) {
+        visit("resolution", self.resolution.as_uniform_value());
+    }
+}
+
+///Uniforms of `shaders/debug_lines.*`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DebugUniforms {
+    pub resolution: [f32; 2],
+    pub camera: CameraUniforms,
+}
+
+impl Uniforms for DebugUniforms {
+    fn visit_values<'a, F: FnMut(&str, UniformValue<'a>)>(&'a self, mut visit: F) {
+        visit("resolution", self.resolution.as_uniform_value());
+        self.camera.visit_values(&mut visit);
+    }
+}
+
+///Uniforms of `shaders/fluid_splat.*`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FluidSplatUniforms {
+    pub resolution: [f32; 2],
+    pub camera: CameraUniforms,
+    ///Radius of the splat of a particle, in particle radii.
+    pub splat_scale: f32,
+}
+
+impl Uniforms for FluidSplatUniforms {
+    fn visit_values<'a, F: FnMut(&str, UniformValue<'a>)>(&'a self, mut visit: F) {
+        visit("resolution", self.resolution.as_uniform_value());
+        self.camera.visit_values(&mut visit);
+        visit("splat_scale", self.splat_scale.as_uniform_value());
+    }
+}
+
+///Uniforms of `shaders/fluid.*`.
+#[derive(Clone, Copy, Default)]
+pub struct FluidUniforms<'t> {
+    pub resolution: [f32; 2],
+    ///Drawn by `shaders/fluid_splat.*`, the uniform is only sent when there is one.
+    pub field: Option<&'t Texture2d>,
+    ///Field over which a pixel is liquid.
+    pub threshold: f32,
+}
+
+impl Uniforms for FluidUniforms<'_> {
+    fn visit_values<'a, F: FnMut(&str, UniformValue<'a>)>(&'a self, mut visit: F) {
+        visit("resolution", self.resolution.as_uniform_value());
+        if let Some(field) = self.field {
+            visit("field", field.as_uniform_value());
+        }
+        visit("threshold", self.threshold.as_uniform_value());
+    }
+}
+
+pub const BALL_PROGRAM: &str = "ball_instanced";
+pub const BACKGROUND_PROGRAM: &str = "background";
+pub const DEBUG_PROGRAM: &str = "debug_lines";
+pub const FLUID_SPLAT_PROGRAM: &str = "fluid_splat";
+pub const FLUID_PROGRAM: &str = "fluid";
+///Every program loaded by the app, each from `{name}.vert` and `{name}.frag`.
+pub const PROGRAMS: [&str; 5] = [
+    BALL_PROGRAM,
+    BACKGROUND_PROGRAM,
+    DEBUG_PROGRAM,
+    FLUID_SPLAT_PROGRAM,
+    FLUID_PROGRAM,
+];
+
+///Shaders built in the binary, so the app runs from any working directory.
+const EMBEDDED: &[(&str, &str)] = &[
+    (
+        "ball_instanced.vert",
+        include_str!("../../shaders/ball_instanced.vert"),
+    ),
+    (
+        "ball_instanced.frag",
+        include_str!("../../shaders/ball_instanced.frag"),
+    ),
+    (
+        "background.vert",
+        include_str!("../../shaders/background.vert"),
+    ),
+    (
+        "background.frag",
+        include_str!("../../shaders/background.frag"),
+    ),
+    (
+        "debug_lines.vert",
+        include_str!("../../shaders/debug_lines.vert"),
+    ),
+    (
+        "debug_lines.frag",
+        include_str!("../../shaders/debug_lines.frag"),
+    ),
+    (
+        "fluid_splat.vert",
+        include_str!("../../shaders/fluid_splat.vert"),
+    ),
+    (
+        "fluid_splat.frag",
+        include_str!("../../shaders/fluid_splat.frag"),
+    ),
+    ("fluid.vert", include_str!("../../shaders/fluid.vert")),
+    ("fluid.frag", include_str!("../../shaders/fluid.frag")),
+];
+
+pub fn embedded_source(file: &str) -> Option<&'static str> {
+    EMBEDDED
+        .iter()
+        .find(|(name, _)| *name == file)
+        .map(|(_, source)| *source)
+}
+
+///Where the shader sources come from : the embedded ones, overridden by the files of `dir`.
+pub struct ShaderSources {
+    dir: Option<PathBuf>,
+    ///Modification time of every file looked for in `dir`, `None` if it did not exist.
+    watched: HashMap<PathBuf, Option<SystemTime>>,
+}
+
+impl ShaderSources {
+    pub fn embedded() -> Self {
+        Self {
+            dir: None,
+            watched: HashMap::new(),
+        }
+    }
+
+    ///Files of `dir` replace the embedded shaders with the same name.
+    pub fn with_dir<P: Into<PathBuf>>(dir: P) -> Self {
+        Self {
+            dir: Some(dir.into()),
+            watched: HashMap::new(),
+        }
+    }
+
+    pub fn dir(&self) -> Option<&Path> {
+        self.dir.as_deref()
+    }
+
+    ///Source of `file` (`ball_instanced.frag`...), from the override directory if it is there.
+    pub fn read(&mut self, file: &str) -> Result<String, ShaderError> {
+        if let Some(dir) = &self.dir {
+            let path = dir.join(file);
+            let modified = modified_time(&path);
+            self.watched.insert(path.clone(), modified);
+
+            if modified.is_some() {
+                return fs::read_to_string(&path)
+                    .map_err(|error| ShaderError::Read { path, error });
+            }
+        }
+
+        embedded_source(file)
+            .map(str::to_owned)
+            .ok_or_else(|| ShaderError::Read {
+                path: self.dir.as_deref().unwrap_or(Path::new("")).join(file),
+                error: io::ErrorKind::NotFound.into(),
+            })
+    }
+
+    ///Compile `{name}.vert` and `{name}.frag`.
+    pub fn load_program<F: Facade>(
+        &mut self,
+        facade: &F,
+        name: &str,
+    ) -> Result<Program, ShaderError> {
+        let vert = self.read(&format!("{name}.vert"))?;
+        let frag = self.read(&format!("{name}.frag"))?;
+
+        Program::from_source(facade, &vert, &frag, None).map_err(|error| ShaderError::Compile {
+            name: name.to_owned(),
+            error,
+        })
+    }
+
+    ///Whether a file read from the override directory was modified, created or removed since.
+    pub fn changed(&self) -> bool {
+        self.watched
+            .iter()
+            .any(|(path, modified)| modified_time(path) != *modified)
+    }
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+///Names of the uniforms sent by `uniforms`.
+pub fn uniform_names<U: Uniforms>(uniforms: &U) -> HashSet<String> {
+    let mut names = HashSet::new();
+    uniforms.visit_values(|name, _| {
+        names.insert(name.to_owned());
+    });
+
+    names
+}
+
+///Names of the attributes of a vertex format.
+pub fn attribute_names<V: Vertex>() -> HashSet<String> {
+    V::build_bindings()
+        .iter()
+        .map(|(name, ..)| name.to_string())
+        .collect()
+}
+
+///Check that every active uniform and attribute of `program` is provided.
+pub fn check_program<U: Uniforms>(
+    name: &str,
+    program: &Program,
+    uniforms: &U,
+    attributes: &HashSet<String>,
+) -> Result<(), ShaderError> {
+    let missing = missing_names(
+        program.uniforms().map(|(name, _)| name),
+        &uniform_names(uniforms),
+    );
+    if !missing.is_empty() {
+        return Err(ShaderError::MissingUniforms {
+            name: name.to_owned(),
+            missing,
+        });
+    }
+
+    let missing = missing_names(program.attributes().map(|(name, _)| name), attributes);
+    if !missing.is_empty() {
+        return Err(ShaderError::MissingAttributes {
+            name: name.to_owned(),
+            missing,
+        });
+    }
+
+    Ok(())
+}
+
+///Sorted names of `active` that are not `provided`, OpenGL built-ins excepted.
+fn missing_names<'a>(
+    active: impl Iterator<Item = &'a String>,
+    provided: &HashSet<String>,
+) -> Vec<String> {
+    let mut missing: Vec<String> = active
+        .filter(|name| !name.starts_with("gl_") && !provided.contains(*name))
+        .cloned()
+        .collect();
+    missing.sort();
+
+    missing
+}
+
+///Names of the `uniform` declarations of a GLSL source, for checks that cannot compile it.
+pub fn declared_uniforms(source: &str) -> Vec<&str> {
+    source
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix("uniform "))
+        .filter_map(|decl| decl.trim_end_matches(';').split_whitespace().nth(1))
+        .collect()
+}
@@ -0,0 +1,92 @@
+//! Triangles of the balls that are not circles (boxes, capsules and polygons), drawn with the
+//! debug renderer on top of the instanced circles.
+
+use my_glium_util::math::Vec2;
+
+use super::debug::DebugVertex;
+use crate::{
+    balls::{Balls, one_ball::Ball},
+    physics::{geometry::Shape, traits::Body},
+};
+
+///Segments of each round end of a capsule.
+const CAPSULE_ARC_SEGMENTS: usize = 8;
+///Size of the colored inside of a shape, the rest is its black outline (like `ball_instanced.frag`).
+const INSIDE_SCALE: f32 = 0.95;
+
+///Triangle list of every ball that is not a circle, in world coordinates.
+pub fn shapes_mesh(balls: &Balls) -> Vec<DebugVertex> {
+    let mut vertices = vec![];
+    for ball in balls.iter().filter(|ball| !ball.is_circle()) {
+        push_shape(
+            &mut vertices,
+            &ball.bounding_shape(),
+            ball.position,
+            [0.; 3],
+        );
+        push_shape(&mut vertices, &inside(ball), ball.position, ball.color);
+    }
+
+    vertices
+}
+
+///The shape of the ball without its outline.
+pub fn inside(ball: &Ball) -> Shape {
+    ball.shape
+        .scaled(INSIDE_SCALE)
+        .to_world(ball.size * INSIDE_SCALE, ball.position, ball.angle)
+}
+
+///Points around a shape, in order, so that they can be drawn as a fan.
+pub fn outline(shape: &Shape) -> Vec<Vec2> {
+    match *shape {
+        Shape::Polygon { .. } => shape.core().to_vec(),
+        Shape::Circle { center, radius } => arc(center, radius, 0., std::f32::consts::TAU, 16),
+        Shape::Capsule {
+            ends: [start, end],
+            radius,
+        } => {
+            let axis = end - start;
+            let angle = axis[1].atan2(axis[0]);
+            let half_turn = std::f32::consts::PI;
+            let mut points = arc(
+                end,
+                radius,
+                angle - half_turn / 2.,
+                half_turn,
+                CAPSULE_ARC_SEGMENTS,
+            );
+            points.extend(arc(
+                start,
+                radius,
+                angle + half_turn / 2.,
+                half_turn,
+                CAPSULE_ARC_SEGMENTS,
+            ));
+            points
+        }
+    }
+}
+
+///`segments + 1` points of the arc of `length` radians starting at `start` radians.
+fn arc(center: Vec2, radius: f32, start: f32, length: f32, segments: usize) -> Vec<Vec2> {
+    (0..=segments)
+        .map(|i| {
+            let angle = start + length * i as f32 / segments as f32;
+            center + Vec2::from([angle.cos(), angle.sin()]) * radius
+        })
+        .collect()
+}
+
+///Fan of triangles from `center`, that has to be inside the convex shape.
+fn push_shape(vertices: &mut Vec<DebugVertex>, shape: &Shape, center: Vec2, color: [f32; 3]) {
+    let points = outline(shape);
+    let vertex = |position: Vec2| DebugVertex {
+        position: *position.as_array(),
+        color,
+    };
+    for i in 0..points.len() {
+        let next = points[(i + 1) % points.len()];
+        vertices.extend([vertex(center), vertex(points[i]), vertex(next)]);
+    }
+}
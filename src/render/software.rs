@@ -0,0 +1,122 @@
+//! Software rasterizer drawing the same visuals as `shaders/ball_instanced.frag`, so frames can be rendered
+//! without any GPU or display (visual regression tests, demo videos...).
+
+use std::{
+    fs::File,
+    io::{self, BufWriter},
+    path::Path,
+};
+
+use my_glium_util::math::Vec2;
+
+use crate::{
+    balls::{Balls, one_ball::Ball},
+    physics::{geometry::segment_distance_sq, traits::Body},
+    render::{TRAIL_TIME, shapes},
+};
+
+pub type Rgb = [f32; 3];
+
+///Image buffer, with `(0, 0)` at the top left corner like the physics coordinates.
+pub struct Frame {
+    pub width: u32,
+    pub height: u32,
+    pixels: Vec<Rgb>,
+}
+
+impl Frame {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![[0.; 3]; (width * height) as usize],
+        }
+    }
+
+    pub fn pixel(&self, x: u32, y: u32) -> Rgb {
+        self.pixels[(y * self.width + x) as usize]
+    }
+
+    fn set_pixel(&mut self, x: u32, y: u32, color: Rgb) {
+        self.pixels[(y * self.width + x) as usize] = color;
+    }
+
+    pub fn to_rgba8(&self) -> Vec<u8> {
+        self.pixels
+            .iter()
+            .flat_map(|[r, g, b]| {
+                [r, g, b]
+                    .map(|c| (c.clamp(0., 1.) * 255.).round() as u8)
+                    .into_iter()
+                    .chain([u8::MAX])
+            })
+            .collect()
+    }
+
+    pub fn save_png<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let file = BufWriter::new(File::create(path)?);
+        let mut encoder = png::Encoder::new(file, self.width, self.height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&self.to_rgba8())?;
+
+        Ok(())
+    }
+}
+
+///Render every ball in a `width` x `height` frame.
+pub fn render(balls: &Balls, width: u32, height: u32) -> Frame {
+    let mut frame = Frame::new(width, height);
+
+    //background gradient
+    for y in 0..height {
+        for x in 0..width {
+            let (f_x, f_y) = (x as f32 + 0.5, y as f32 + 0.5);
+            frame.set_pixel(x, y, [f_x / width as f32, f_y / height as f32, 0.5]);
+        }
+    }
+
+    for ball in balls.iter() {
+        draw_ball(&mut frame, ball);
+    }
+
+    frame
+}
+
+fn draw_ball(frame: &mut Frame, ball: &Ball) {
+    let (pos, collision) = (ball.position, ball.coliding_pos);
+    let last_pos = pos - ball.speed * TRAIL_TIME;
+    let radius = ball.size;
+    let (shape, inside) = (ball.bounding_shape(), shapes::inside(ball));
+
+    // only the pixels around the ball, its velocity trail and its collision line can be touched
+    let min = [
+        (pos[0] - radius).min(collision[0]).min(last_pos[0]) - 1.,
+        (pos[1] - radius).min(collision[1]).min(last_pos[1]) - 1.,
+    ];
+    let max = [
+        (pos[0] + radius).max(collision[0]).max(last_pos[0]) + 1.,
+        (pos[1] + radius).max(collision[1]).max(last_pos[1]) + 1.,
+    ];
+    let x_range = min[0].max(0.) as u32..(max[0].max(0.) as u32).min(frame.width);
+    let y_range = min[1].max(0.) as u32..(max[1].max(0.) as u32).min(frame.height);
+
+    for y in y_range {
+        for x in x_range.clone() {
+            let p: Vec2 = [x as f32 + 0.5, y as f32 + 0.5].into();
+
+            // same branches as `ball_instanced.frag`, for any shape
+            if segment_distance_sq(p, pos, collision) < 1. {
+                frame.set_pixel(x, y, [1.; 3]);
+            } else if inside.contains(p) {
+                frame.set_pixel(x, y, ball.color);
+            } else if shape.contains(p) {
+                frame.set_pixel(x, y, [0.; 3]);
+            } else if segment_distance_sq(p, pos, last_pos) < 1. {
+                frame.set_pixel(x, y, [1., 0.2, 0.2]);
+            }
+        }
+    }
+}
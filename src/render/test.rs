@@ -0,0 +1,566 @@
+#![cfg(test)]
+
+use my_glium_util::datastruct::aabb::Aabb;
+
+use super::software;
+use crate::balls::{Balls, one_ball::Ball};
+
+fn one_ball_world() -> Balls {
+    let mut balls = Balls::empty(Aabb::new((50., 50.), 50.));
+    balls.auto_spawn = false;
+
+    let mut ball = Ball::new(10., [50., 50.], 0);
+    ball.color = [0.2, 0.4, 0.6];
+    balls.push_ball(ball);
+
+    balls
+}
+
+fn assert_color_eq(got: software::Rgb, expected: software::Rgb) {
+    for (g, e) in got.iter().zip(expected) {
+        assert!(
+            (g - e).abs() < 1e-4,
+            "got color {got:?} instead of {expected:?}"
+        );
+    }
+}
+
+#[test]
+fn software_render_matches_ball_shader() {
+    let frame = software::render(&one_ball_world(), 100, 100);
+
+    // no collision : the collision line is a dot in the middle of the ball
+    assert_color_eq(frame.pixel(50, 50), [1.; 3]);
+    assert_color_eq(frame.pixel(54, 50), [0.2, 0.4, 0.6]);
+    // outline between 0.95 and 1 radius
+    assert_color_eq(frame.pixel(59, 50), [0.; 3]);
+    // background gradient
+    assert_color_eq(frame.pixel(80, 20), [0.805, 0.205, 0.5]);
+}
+
+#[test]
+fn software_render_draws_collision_line() {
+    let mut balls = one_ball_world();
+    balls
+        .balls
+        .iter_mut()
+        .for_each(|b| b.coliding_pos = [80., 50.].into());
+
+    let frame = software::render(&balls, 100, 100);
+    assert_color_eq(frame.pixel(70, 50), [1.; 3]);
+    assert_color_eq(frame.pixel(70, 60), [0.705, 0.605, 0.5]);
+}
+
+#[test]
+fn rgba_export_has_one_pixel_per_4_bytes() {
+    let frame = software::render(&one_ball_world(), 100, 40);
+    let rgba = frame.to_rgba8();
+
+    assert_eq!(rgba.len(), 100 * 40 * 4);
+    assert_eq!(&rgba[..4], &[1, 3, 128, 255]);
+}
+
+#[test]
+fn software_render_draws_velocity_trail() {
+    let mut balls = one_ball_world();
+    balls
+        .balls
+        .iter_mut()
+        .for_each(|b| b.speed = [-300., 0.].into());
+
+    // the trail goes 300 * TRAIL_TIME = 30 pixels behind the ball
+    let frame = software::render(&balls, 100, 100);
+    assert_color_eq(frame.pixel(70, 50), [1., 0.2, 0.2]);
+    assert_color_eq(frame.pixel(85, 50), [0.855, 0.505, 0.5]);
+}
+
+mod shader_contract {
+    use std::collections::HashSet;
+
+    use super::super::shader::{
+        BackgroundUniforms, DebugUniforms, FluidSplatUniforms, FluidUniforms, InstancedUniforms,
+        declared_uniforms, uniform_names,
+    };
+
+    fn assert_provided(shader: &str, source: &str, provided: &HashSet<String>) {
+        for name in declared_uniforms(source) {
+            assert!(
+                provided.contains(name),
+                "{shader} declares the uniform {name} but it is never sent"
+            );
+        }
+    }
+
+    #[test]
+    fn instanced_uniforms_match_ball_instanced_shaders() {
+        let provided = uniform_names(&InstancedUniforms::default());
+
+        assert_provided(
+            "ball_instanced.vert",
+            include_str!("../../shaders/ball_instanced.vert"),
+            &provided,
+        );
+        assert_provided(
+            "ball_instanced.frag",
+            include_str!("../../shaders/ball_instanced.frag"),
+            &provided,
+        );
+    }
+
+    #[test]
+    fn background_uniforms_match_background_shaders() {
+        let provided = uniform_names(&BackgroundUniforms::default());
+
+        assert_provided(
+            "background.frag",
+            include_str!("../../shaders/background.frag"),
+            &provided,
+        );
+    }
+
+    #[test]
+    fn debug_uniforms_match_debug_lines_shaders() {
+        let provided = uniform_names(&DebugUniforms::default());
+
+        assert_provided(
+            "debug_lines.vert",
+            include_str!("../../shaders/debug_lines.vert"),
+            &provided,
+        );
+    }
+
+    #[test]
+    fn fluid_uniforms_match_fluid_shaders() {
+        let provided = uniform_names(&FluidSplatUniforms::default());
+        assert_provided(
+            "fluid_splat.vert",
+            include_str!("../../shaders/fluid_splat.vert"),
+            &provided,
+        );
+
+        let mut provided = uniform_names(&FluidUniforms::default());
+        // the field texture only exists on a GPU
+        provided.insert("field".to_owned());
+        assert_provided(
+            "fluid.frag",
+            include_str!("../../shaders/fluid.frag"),
+            &provided,
+        );
+    }
+
+    #[test]
+    fn declared_uniforms_are_parsed() {
+        let source =
+            "#version 150\nuniform vec2 position;\n  uniform uvec2 resolution;\nin vec2 corner;";
+        assert_eq!(declared_uniforms(source), ["position", "resolution"]);
+    }
+}
+
+mod shader_sources {
+    use std::{
+        fs::{self, File},
+        path::PathBuf,
+        time::{Duration, SystemTime},
+    };
+
+    use super::super::shader::{PROGRAMS, ShaderSources, embedded_source};
+
+    ///Empty directory only used by one test.
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("bouncing_ball_{name}_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        dir
+    }
+
+    #[test]
+    fn embedded_shaders_are_used_by_default() {
+        let mut sources = ShaderSources::embedded();
+
+        assert_eq!(
+            sources.read("ball_instanced.frag").unwrap(),
+            embedded_source("ball_instanced.frag").unwrap()
+        );
+        assert!(sources.read("missing.frag").is_err());
+        assert!(!sources.changed());
+    }
+
+    #[test]
+    fn every_program_is_embedded() {
+        let mut sources = ShaderSources::embedded();
+        for name in PROGRAMS {
+            for file in [format!("{name}.vert"), format!("{name}.frag")] {
+                assert!(sources.read(&file).is_ok(), "{file} is not embedded");
+            }
+        }
+    }
+
+    #[test]
+    fn override_dir_replaces_only_its_files() {
+        let dir = temp_dir("override");
+        fs::write(dir.join("ball_instanced.frag"), "// overridden").unwrap();
+        let mut sources = ShaderSources::with_dir(&dir);
+
+        assert_eq!(
+            sources.read("ball_instanced.frag").unwrap(),
+            "// overridden"
+        );
+        assert_eq!(
+            sources.read("background.frag").unwrap(),
+            embedded_source("background.frag").unwrap()
+        );
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn modified_created_and_removed_files_are_changes() {
+        let dir = temp_dir("watch");
+        let path = dir.join("ball_instanced.frag");
+        fs::write(&path, "// first").unwrap();
+        let mut sources = ShaderSources::with_dir(&dir);
+        sources.read("ball_instanced.frag").unwrap();
+        sources.read("background.frag").unwrap();
+        assert!(!sources.changed());
+
+        let later = SystemTime::now() + Duration::from_secs(10);
+        File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(later)
+            .unwrap();
+        assert!(sources.changed());
+        sources.read("ball_instanced.frag").unwrap();
+        assert!(!sources.changed());
+
+        fs::write(dir.join("background.frag"), "// new").unwrap();
+        assert!(sources.changed());
+        sources.read("background.frag").unwrap();
+        assert!(!sources.changed());
+
+        fs::remove_file(&path).unwrap();
+        assert!(sources.changed());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
+
+mod debug_overlay {
+    use my_glium_util::datastruct::aabb::Aabb;
+
+    use super::super::debug::{DebugLayers, DebugLines};
+    use crate::balls::{Balls, QUADTREE_CAPACITY, one_ball::Ball};
+
+    const NONE: DebugLayers = DebugLayers {
+        quadtree: false,
+        broad_phase: false,
+        contacts: false,
+        velocities: false,
+        ids: false,
+    };
+
+    fn world(balls: impl IntoIterator<Item = Ball>) -> Balls {
+        let mut world = Balls::empty(Aabb::new((50., 50.), 50.));
+        world.auto_spawn = false;
+        for ball in balls {
+            world.push_ball(ball);
+        }
+
+        world
+    }
+
+    #[test]
+    fn quadtree_is_split_above_its_capacity() {
+        let few = world((0..QUADTREE_CAPACITY).map(|i| Ball::new(1., [10. + i as f32, 10.], i)));
+        let layers = DebugLayers {
+            quadtree: true,
+            ..NONE
+        };
+        assert_eq!(DebugLines::build(&few, layers).len(), 4);
+
+        // all in the top left quadrant : the root and its 4 children, then the 4 children of the
+        // top left one
+        let many = world((0..=QUADTREE_CAPACITY).map(|i| Ball::new(1., [10. + i as f32, 10.], i)));
+        assert_eq!(DebugLines::build(&many, layers).len(), 4 * 9);
+    }
+
+    #[test]
+    fn broad_phase_links_overlapping_balls_once() {
+        let balls = world([
+            Ball::new(10., [40., 50.], 0),
+            Ball::new(10., [48., 50.], 1),
+            Ball::new(10., [90., 90.], 2),
+        ]);
+        let layers = DebugLayers {
+            broad_phase: true,
+            ..NONE
+        };
+
+        // a box per ball and one pair
+        assert_eq!(DebugLines::build(&balls, layers).len(), 3 * 4 + 1);
+    }
+
+    #[test]
+    fn velocity_and_id_are_drawn() {
+        let mut ball = Ball::new(10., [50., 50.], 18);
+        ball.speed = [100., 0.].into();
+        let balls = world([ball]);
+
+        let velocities = DebugLines::build(
+            &balls,
+            DebugLayers {
+                velocities: true,
+                ..NONE
+            },
+        );
+        let end = velocities.vertices()[1].position;
+        assert_eq!(end, [60., 50.]);
+
+        // "1" has 2 segments and "8" all 7
+        let ids = DebugLines::build(&balls, DebugLayers { ids: true, ..NONE });
+        assert_eq!(ids.len(), 2 + 7);
+    }
+}
+
+mod hud {
+    use super::super::{
+        hud::{FrameTime, HUD_FRAMES, Hud, HudInfo},
+        text,
+    };
+
+    fn frame(total: f32) -> FrameTime {
+        FrameTime {
+            total,
+            physics: total / 4.,
+            render: total / 2.,
+        }
+    }
+
+    #[test]
+    fn only_the_last_frames_are_kept() {
+        let mut hud = Hud::default();
+        for _ in 0..HUD_FRAMES {
+            hud.record(frame(1. / 10.));
+        }
+        for _ in 0..HUD_FRAMES {
+            hud.record(frame(1. / 50.));
+        }
+
+        assert!((hud.current_fps() - 50.).abs() < 1e-2);
+        let mean = hud.mean();
+        assert!((mean.physics - 1. / 200.).abs() < 1e-6);
+        assert!((mean.render - 1. / 100.).abs() < 1e-6);
+    }
+
+    #[test]
+    fn empty_hud_has_no_nan() {
+        let hud = Hud::default();
+
+        assert_eq!(hud.current_fps(), 0.);
+        assert_eq!(hud.mean(), FrameTime::default());
+    }
+
+    #[test]
+    fn hud_text_only_uses_known_glyphs() {
+        let mut hud = Hud::default();
+        hud.record(frame(1. / 60.));
+        let info = HudInfo {
+            average_fps: 59.6,
+            ball_count: 1234,
+            parameters: vec![("GRAVITY", "9.81".to_owned()), ("SPAWN", "ON".to_owned())],
+        };
+
+        let lines = hud.text(&info);
+        assert_eq!(lines[0], "FPS 60  AVG 60");
+        assert_eq!(lines[2], "BALLS 1234");
+        assert_eq!(lines[3], "GRAVITY 9.81  SPAWN ON");
+        let unknown = text::glyph('\u{1}');
+        for c in lines.concat().chars() {
+            assert_ne!(text::glyph(c), unknown, "no glyph for {c:?}");
+        }
+    }
+
+    #[test]
+    fn text_is_one_quad_per_lit_pixel() {
+        let mut vertices = vec![];
+        text::push_text(&mut vertices, "1 ", [0., 0.], 2., [1.; 3]);
+
+        // "1" lights 8 pixels, " " none
+        assert_eq!(vertices.len(), 8 * 6);
+        assert_eq!(text::text_size("1 ", 2.), [14., 10.]);
+    }
+}
+
+mod camera {
+    use super::super::camera::{Camera, MAX_ZOOM};
+
+    fn assert_near(got: [f32; 2], expected: [f32; 2]) {
+        assert!(
+            (got[0] - expected[0]).abs() < 1e-3 && (got[1] - expected[1]).abs() < 1e-3,
+            "got {got:?} instead of {expected:?}"
+        );
+    }
+
+    #[test]
+    fn screen_and_world_round_trip() {
+        let camera = Camera::new([120., -40.], 2.5, [800., 600.]);
+        let p = [33., 470.];
+
+        assert_near(camera.world_to_screen(camera.screen_to_world(p)), p);
+        assert_near(camera.world_to_screen(camera.center), [400., 300.]);
+    }
+
+    #[test]
+    fn screen_camera_is_the_identity() {
+        let camera = Camera::screen([800., 600.]);
+
+        assert_near(camera.world_to_screen([12., 34.]), [12., 34.]);
+    }
+
+    #[test]
+    fn fit_shows_the_whole_world() {
+        let camera = Camera::fit((800., 600.), [400., 400.]);
+
+        assert_eq!(camera.zoom, 0.5);
+        assert_near(camera.world_to_screen([0., 0.]), [0., 50.]);
+        assert_near(camera.world_to_screen([800., 600.]), [400., 350.]);
+    }
+
+    #[test]
+    fn zoom_keeps_the_point_under_the_mouse() {
+        let mut camera = Camera::fit((800., 600.), [800., 600.]);
+        let mouse = [100., 500.];
+        let under_mouse = camera.screen_to_world(mouse);
+
+        camera.zoom_at(mouse, 3.);
+        assert_near(camera.screen_to_world(mouse), under_mouse);
+        assert_eq!(camera.zoom, 3.);
+
+        camera.zoom_at(mouse, 1000.);
+        assert_eq!(camera.zoom, MAX_ZOOM);
+        assert_near(camera.screen_to_world(mouse), under_mouse);
+    }
+
+    #[test]
+    fn pan_moves_the_world_with_the_mouse() {
+        let mut camera = Camera::new([400., 300.], 2., [800., 600.]);
+        let grabbed = camera.screen_to_world([100., 100.]);
+
+        camera.pan([50., -20.]);
+        assert_near(camera.world_to_screen(grabbed), [150., 80.]);
+    }
+
+    #[test]
+    fn resizing_does_not_move_the_world_center() {
+        let mut camera = Camera::fit((800., 600.), [800., 600.]);
+        camera.set_viewport([1600., 300.]);
+
+        assert_near(camera.world_to_screen([400., 300.]), [800., 150.]);
+    }
+}
+
+mod resize_policy {
+    use super::super::camera::{Camera, ResizePolicy};
+
+    #[test]
+    fn fixed_world_is_letterboxed() {
+        let mut camera = Camera::fit((800., 600.), [800., 600.]);
+        let world = ResizePolicy::Fixed.resize((800., 600.), &mut camera, [1600., 600.]);
+
+        assert_eq!(world, (800., 600.));
+        assert_eq!(camera, Camera::fit((800., 600.), [1600., 600.]));
+        assert_eq!(camera.world_to_screen([0., 0.]), [400., 0.]);
+    }
+
+    #[test]
+    fn fixed_world_stays_whole_on_screen() {
+        let mut camera = Camera::fit((800., 600.), [800., 600.]);
+        camera.pan([100., -50.]);
+        camera.zoom_at([0., 0.], 2.);
+
+        for viewport in [[640., 480.], [1600., 300.], [200., 900.]] {
+            let world = ResizePolicy::Fixed.resize((800., 600.), &mut camera, viewport);
+            assert_eq!(world, (800., 600.));
+            for corner in [[0., 0.], [800., 0.], [0., 600.], [800., 600.]] {
+                let [x, y] = camera.world_to_screen(corner);
+                assert!(
+                    (-0.01..=viewport[0] + 0.01).contains(&x)
+                        && (-0.01..=viewport[1] + 0.01).contains(&y),
+                    "corner {corner:?} off screen at {:?} in {viewport:?}",
+                    [x, y]
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn world_grows_with_the_window() {
+        let mut camera = Camera::new([0., 0.], 2., [800., 600.]);
+        for policy in [ResizePolicy::Grow, ResizePolicy::Scale] {
+            let world = policy.resize((400., 300.), &mut camera, [1000., 400.]);
+
+            assert_eq!(world, (500., 200.));
+            assert_eq!(camera.world_to_screen([0., 0.]), [0., 0.]);
+        }
+    }
+
+    #[test]
+    fn minimized_window_keeps_the_world() {
+        let mut camera = Camera::fit((800., 600.), [800., 600.]);
+        let world = ResizePolicy::Grow.resize((800., 600.), &mut camera, [0., 0.]);
+
+        assert_eq!(world, (800., 600.));
+        assert_eq!(camera.zoom, 1.);
+    }
+
+    #[test]
+    fn policy_is_parsed() {
+        assert_eq!("scale".parse(), Ok(ResizePolicy::Scale));
+        assert!("stretch".parse::<ResizePolicy>().is_err());
+    }
+}
+
+mod shapes {
+    use super::*;
+    use crate::{physics::geometry::BodyShape, render::shapes::shapes_mesh};
+
+    fn plank_world() -> Balls {
+        let mut balls = Balls::empty(Aabb::new((50., 50.), 50.));
+        balls.auto_spawn = false;
+
+        let mut plank = Ball::new(1., [50., 50.], 1).with_shape(BodyShape::Box {
+            half_extents: [20., 5.],
+        });
+        plank.angle = std::f32::consts::FRAC_PI_2;
+        plank.color = [0.6, 0.4, 0.2];
+        balls.push_ball(plank);
+
+        balls
+    }
+
+    #[test]
+    fn software_render_fills_turned_boxes() {
+        let frame = software::render(&plank_world(), 100, 100);
+
+        assert_color_eq(frame.pixel(50, 65), [0.6, 0.4, 0.2]);
+        assert_color_eq(frame.pixel(53, 35), [0.6, 0.4, 0.2]);
+        // outline between 0.95 and 1 times the shape
+        assert_color_eq(frame.pixel(50, 69), [0.; 3]);
+        // the box is turned, its long side is vertical
+        assert_color_eq(frame.pixel(65, 50), [0.655, 0.505, 0.5]);
+    }
+
+    #[test]
+    fn only_other_shapes_than_circles_are_meshed() {
+        assert!(shapes_mesh(&one_ball_world()).is_empty());
+
+        //a fan of 4 triangles for the outline, then 4 for the inside
+        let mesh = shapes_mesh(&plank_world());
+        assert_eq!(mesh.len(), 2 * 4 * 3);
+        assert!(mesh.iter().all(|v| {
+            (29.9..=70.1).contains(&v.position[1]) && (44.9..=55.1).contains(&v.position[0])
+        }));
+    }
+}
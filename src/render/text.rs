@@ -0,0 +1,114 @@
+//! Tiny 3x5 pixel font, drawn as colored triangles with the debug lines shader.
+
+use super::debug::DebugVertex;
+
+///Width of a glyph, in font pixels.
+pub const GLYPH_WIDTH: u32 = 3;
+pub const GLYPH_HEIGHT: u32 = 5;
+
+///Rows of the glyph, top to bottom, the highest of the 3 bits being the left pixel.
+///Lowercase letters are drawn as uppercase, unknown characters as `?`.
+pub fn glyph(c: char) -> [u8; 5] {
+    match c.to_ascii_uppercase() {
+        '0' => [0b111, 0b101, 0b101, 0b101, 0b111],
+        '1' => [0b010, 0b110, 0b010, 0b010, 0b111],
+        '2' => [0b111, 0b001, 0b111, 0b100, 0b111],
+        '3' => [0b111, 0b001, 0b111, 0b001, 0b111],
+        '4' => [0b101, 0b101, 0b111, 0b001, 0b001],
+        '5' => [0b111, 0b100, 0b111, 0b001, 0b111],
+        '6' => [0b111, 0b100, 0b111, 0b101, 0b111],
+        '7' => [0b111, 0b001, 0b001, 0b001, 0b001],
+        '8' => [0b111, 0b101, 0b111, 0b101, 0b111],
+        '9' => [0b111, 0b101, 0b111, 0b001, 0b111],
+        'A' => [0b010, 0b101, 0b111, 0b101, 0b101],
+        'B' => [0b110, 0b101, 0b110, 0b101, 0b110],
+        'C' => [0b011, 0b100, 0b100, 0b100, 0b011],
+        'D' => [0b110, 0b101, 0b101, 0b101, 0b110],
+        'E' => [0b111, 0b100, 0b110, 0b100, 0b111],
+        'F' => [0b111, 0b100, 0b110, 0b100, 0b100],
+        'G' => [0b011, 0b100, 0b101, 0b101, 0b011],
+        'H' => [0b101, 0b101, 0b111, 0b101, 0b101],
+        'I' => [0b111, 0b010, 0b010, 0b010, 0b111],
+        'J' => [0b001, 0b001, 0b001, 0b101, 0b010],
+        'K' => [0b101, 0b101, 0b110, 0b101, 0b101],
+        'L' => [0b100, 0b100, 0b100, 0b100, 0b111],
+        'M' => [0b101, 0b111, 0b111, 0b101, 0b101],
+        'N' => [0b110, 0b101, 0b101, 0b101, 0b101],
+        'O' => [0b010, 0b101, 0b101, 0b101, 0b010],
+        'P' => [0b110, 0b101, 0b110, 0b100, 0b100],
+        'Q' => [0b010, 0b101, 0b101, 0b110, 0b011],
+        'R' => [0b110, 0b101, 0b110, 0b101, 0b101],
+        'S' => [0b011, 0b100, 0b010, 0b001, 0b110],
+        'T' => [0b111, 0b010, 0b010, 0b010, 0b010],
+        'U' => [0b101, 0b101, 0b101, 0b101, 0b111],
+        'V' => [0b101, 0b101, 0b101, 0b101, 0b010],
+        'W' => [0b101, 0b101, 0b111, 0b111, 0b101],
+        'X' => [0b101, 0b101, 0b010, 0b101, 0b101],
+        'Y' => [0b101, 0b101, 0b010, 0b010, 0b010],
+        'Z' => [0b111, 0b001, 0b010, 0b100, 0b111],
+        ' ' => [0; 5],
+        '.' => [0b000, 0b000, 0b000, 0b000, 0b010],
+        ',' => [0b000, 0b000, 0b000, 0b010, 0b100],
+        ':' => [0b000, 0b010, 0b000, 0b010, 0b000],
+        '/' => [0b001, 0b001, 0b010, 0b100, 0b100],
+        '%' => [0b101, 0b001, 0b010, 0b100, 0b101],
+        '-' => [0b000, 0b000, 0b111, 0b000, 0b000],
+        '+' => [0b000, 0b010, 0b111, 0b010, 0b000],
+        '=' => [0b000, 0b111, 0b000, 0b111, 0b000],
+        '(' => [0b010, 0b100, 0b100, 0b100, 0b010],
+        ')' => [0b010, 0b001, 0b001, 0b001, 0b010],
+        '[' => [0b110, 0b100, 0b100, 0b100, 0b110],
+        ']' => [0b011, 0b001, 0b001, 0b001, 0b011],
+        _ => [0b111, 0b001, 0b010, 0b000, 0b010],
+    }
+}
+
+///Size in pixels of `text` drawn with `scale` pixels per font pixel.
+pub fn text_size(text: &str, scale: f32) -> [f32; 2] {
+    let chars = text.chars().count() as f32;
+    let width = if chars == 0. {
+        0.
+    } else {
+        (chars * (GLYPH_WIDTH + 1) as f32 - 1.) * scale
+    };
+
+    [width, GLYPH_HEIGHT as f32 * scale]
+}
+
+///Add the triangles of `text`, its top left corner at `pos`.
+pub fn push_text(
+    vertices: &mut Vec<DebugVertex>,
+    text: &str,
+    pos: [f32; 2],
+    scale: f32,
+    color: [f32; 3],
+) {
+    for (i, c) in text.chars().enumerate() {
+        let x = pos[0] + (i as u32 * (GLYPH_WIDTH + 1)) as f32 * scale;
+        for (row, bits) in glyph(c).into_iter().enumerate() {
+            for column in 0..GLYPH_WIDTH {
+                if bits & (1 << (GLYPH_WIDTH - 1 - column)) != 0 {
+                    let min = [x + column as f32 * scale, pos[1] + row as f32 * scale];
+                    push_rect(vertices, min, [min[0] + scale, min[1] + scale], color);
+                }
+            }
+        }
+    }
+}
+
+///Add the two triangles of a filled rectangle.
+pub fn push_rect(vertices: &mut Vec<DebugVertex>, min: [f32; 2], max: [f32; 2], color: [f32; 3]) {
+    let corners = [
+        [min[0], min[1]],
+        [max[0], min[1]],
+        [min[0], max[1]],
+        [max[0], min[1]],
+        [max[0], max[1]],
+        [min[0], max[1]],
+    ];
+    vertices.extend(
+        corners
+            .into_iter()
+            .map(|position| DebugVertex { position, color }),
+    );
+}
@@ -0,0 +1,453 @@
+//! Scenes: a description of a world (its size, balls and sensors) loaded from a JSON file.
+//!
+//! ```json
+//! {
+//!     "width": 800, "height": 600,
+//!     "balls": [
+//!         { "size": 10, "position": [300, 300], "speed": [200, 0] },
+//!         { "shape": { "box": { "half_extents": [40, 10] } }, "position": [400, 90], "angle": 1 }
+//!     ],
+//!     "soft_bodies": [{ "kind": "jelly", "min": [100, 400], "max": [200, 480], "radius": 5 }],
+//!     "sensors": [{ "circle": { "center": [400, 500], "radius": 50 } }]
+//! }
+//! ```
+
+use std::{fmt, fs, io, path::Path};
+
+use my_glium_util::datastruct::aabb::Aabb;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    balls::{Balls, one_ball::Ball, sensor::SensorShape, soft::SoftKind},
+    physics::{
+        fluid::FluidParams,
+        geometry::{BodyShape, ConvexPolygon, MAX_POLYGON_VERTICES},
+    },
+};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Scene {
+    pub width: f32,
+    pub height: f32,
+    ///Spawn new balls regularly, like the interactive app does.
+    #[serde(default)]
+    pub auto_spawn: bool,
+    ///Simulate the circles as a fluid.
+    #[serde(default)]
+    pub fluid: Option<FluidDesc>,
+    #[serde(default)]
+    pub balls: Vec<BallDesc>,
+    #[serde(default)]
+    pub soft_bodies: Vec<SoftBodyDesc>,
+    #[serde(default)]
+    pub sensors: Vec<SensorDesc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BallDesc {
+    ///Radius of a circle, unused by the other shapes.
+    #[serde(default)]
+    pub size: f32,
+    ///A circle by default.
+    #[serde(default)]
+    pub shape: Option<ShapeDesc>,
+    pub position: [f32; 2],
+    ///In radians.
+    #[serde(default)]
+    pub angle: f32,
+    #[serde(default)]
+    pub speed: [f32; 2],
+    ///Defaults to `size * size`, or the area of the shape over pi.
+    #[serde(default)]
+    pub mass: Option<f32>,
+    #[serde(default)]
+    pub bounce: Option<f32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ShapeDesc {
+    Box {
+        half_extents: [f32; 2],
+    },
+    Capsule {
+        half_length: f32,
+        radius: f32,
+    },
+    ///Convex hull of the vertices, centered on its centroid.
+    Polygon {
+        vertices: Vec<[f32; 2]>,
+    },
+}
+
+///Fluid of balls of about `particle_radius`, see `FluidParams::new`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FluidDesc {
+    pub particle_radius: f32,
+    ///Kinematic viscosity, in world units² per second.
+    #[serde(default)]
+    pub viscosity: Option<f32>,
+}
+
+///Soft body filling the rectangle between `min` and `max`, its balls added after the balls of the
+///scene.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SoftBodyDesc {
+    pub kind: SoftKind,
+    pub min: [f32; 2],
+    pub max: [f32; 2],
+    ///Of its balls.
+    pub radius: f32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SensorDesc {
+    Aabb { min: [f32; 2], max: [f32; 2] },
+    Circle { center: [f32; 2], radius: f32 },
+    Polygon { vertices: Vec<[f32; 2]> },
+}
+
+#[derive(Debug)]
+pub enum SceneError {
+    Io(io::Error),
+    Parse(serde_json::Error),
+    ///The width or the height of the world is not positive.
+    InvalidSize,
+    ///The shape of the ball at this index is not a valid convex shape, or the ball is a circle
+    ///without size.
+    InvalidShape(usize),
+    ///The soft body at this index has balls without size.
+    InvalidSoftBody(usize),
+    ///The fluid has particles without size or a negative viscosity.
+    InvalidFluid,
+    ///The sensor at this index has no area.
+    InvalidSensor(usize),
+}
+
+impl fmt::Display for SceneError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SceneError::Io(e) => write!(f, "could not read the scene : {e}"),
+            SceneError::Parse(e) => write!(f, "invalid scene : {e}"),
+            SceneError::InvalidSize => {
+                write!(f, "invalid scene : its width and height must be positive")
+            }
+            SceneError::InvalidShape(index) => write!(
+                f,
+                "invalid shape for ball {index} : sizes must be positive, and polygons need 3 to \
+                 {MAX_POLYGON_VERTICES} vertices that are not aligned"
+            ),
+            SceneError::InvalidSoftBody(index) => {
+                write!(f, "invalid soft body {index} : its radius must be positive")
+            }
+            SceneError::InvalidFluid => write!(
+                f,
+                "invalid fluid : the particle radius must be positive and the viscosity not \
+                 negative"
+            ),
+            SceneError::InvalidSensor(index) => write!(
+                f,
+                "invalid sensor {index} : boxes need a min below their max, circles a positive \
+                 radius and polygons at least 3 vertices"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SceneError {}
+
+impl From<io::Error> for SceneError {
+    fn from(e: io::Error) -> Self {
+        SceneError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for SceneError {
+    fn from(e: serde_json::Error) -> Self {
+        SceneError::Parse(e)
+    }
+}
+
+impl Scene {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, SceneError> {
+        Self::from_json(&fs::read_to_string(path)?)
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, SceneError> {
+        let scene: Self = serde_json::from_str(json)?;
+        //also false for NaN
+        if !(scene.width > 0. && scene.height > 0.) {
+            return Err(SceneError::InvalidSize);
+        }
+        if let Some(index) = scene.balls.iter().position(|ball| match &ball.shape {
+            Some(shape) => shape.to_shape().is_none(),
+            None => ball.size <= 0.,
+        }) {
+            return Err(SceneError::InvalidShape(index));
+        }
+        if let Some(index) = scene.soft_bodies.iter().position(|body| body.radius <= 0.) {
+            return Err(SceneError::InvalidSoftBody(index));
+        }
+        if scene.fluid.as_ref().is_some_and(|fluid| {
+            fluid.particle_radius <= 0. || fluid.viscosity.is_some_and(|v| v < 0.)
+        }) {
+            return Err(SceneError::InvalidFluid);
+        }
+        if let Some(index) = scene.sensors.iter().position(|sensor| !sensor.is_valid()) {
+            return Err(SceneError::InvalidSensor(index));
+        }
+
+        Ok(scene)
+    }
+
+    ///Border of the world, balls stay inside `(0, 0)..border`.
+    pub fn border(&self) -> (f32, f32) {
+        (self.width, self.height)
+    }
+
+    ///Build the world described by the scene, ball ids follow their order in the scene.
+    pub fn build(&self) -> Balls {
+        let half_dim = self.width.max(self.height) / 2.;
+        let mut balls = Balls::empty(Aabb::new((half_dim, half_dim), half_dim));
+        balls.auto_spawn = self.auto_spawn;
+        balls.set_fluid(self.fluid.as_ref().map(FluidDesc::to_params));
+
+        for desc in &self.balls {
+            let id = balls.next_id();
+            balls.push_ball(desc.to_ball(id));
+        }
+        for desc in &self.soft_bodies {
+            balls.add_soft_body(desc.kind, desc.min, desc.max, desc.radius);
+        }
+        for desc in &self.sensors {
+            balls.add_sensor(desc.to_shape());
+        }
+
+        balls
+    }
+
+    ///`count` balls of random sizes, positions and speeds in a `width * height` world, the same for
+    ///a given `seed`. The balls are small enough to cover about a third of the world.
+    pub fn random(width: f32, height: f32, count: usize, seed: u64) -> Self {
+        let mut rng = XorShift::new(seed);
+        let max_size = (width * height * 0.3 / (count.max(1) as f32 * std::f32::consts::PI))
+            .sqrt()
+            .clamp(1., 10.);
+        let balls = (0..count)
+            .map(|_| {
+                let size = rng.range(max_size / 2., max_size);
+                BallDesc {
+                    size,
+                    shape: None,
+                    position: [
+                        rng.range(size, (width - size).max(size)),
+                        rng.range(size, (height - size).max(size)),
+                    ],
+                    angle: 0.,
+                    speed: [rng.range(-100., 100.), rng.range(-100., 100.)],
+                    mass: None,
+                    bounce: None,
+                }
+            })
+            .collect();
+
+        Self {
+            width,
+            height,
+            auto_spawn: false,
+            fluid: None,
+            balls,
+            soft_bodies: vec![],
+            sensors: vec![],
+        }
+    }
+}
+
+///Small deterministic generator for `Scene::random`.
+struct XorShift(u64);
+
+impl XorShift {
+    fn new(seed: u64) -> Self {
+        //splitmix64, so that close seeds give unrelated sequences, and never a zero state
+        let mut z = seed.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        Self((z ^ (z >> 31)) | 1)
+    }
+
+    fn next_f32(&mut self) -> f32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    fn range(&mut self, min: f32, max: f32) -> f32 {
+        min + self.next_f32() * (max - min)
+    }
+}
+
+impl BallDesc {
+    pub fn to_ball(&self, id: usize) -> Ball {
+        let mut ball = Ball::new(self.size, self.position, id);
+        if let Some(shape) = self.shape.as_ref().and_then(ShapeDesc::to_shape) {
+            ball = ball.with_shape(shape);
+        }
+        ball.angle = self.angle;
+        ball.speed = self.speed.into();
+        if let Some(mass) = self.mass {
+            ball.mass = mass;
+        }
+        if let Some(bounce) = self.bounce {
+            ball.bounce = bounce;
+        }
+
+        ball
+    }
+}
+
+impl FluidDesc {
+    pub fn to_params(&self) -> FluidParams {
+        let mut params = FluidParams::new(self.particle_radius);
+        if let Some(viscosity) = self.viscosity {
+            params.viscosity = viscosity;
+        }
+
+        params
+    }
+}
+
+impl ShapeDesc {
+    ///`None` if the shape is flat or too complex.
+    pub fn to_shape(&self) -> Option<BodyShape> {
+        match self {
+            &ShapeDesc::Box {
+                half_extents: [x, y],
+            } => (x > 0. && y > 0.).then_some(BodyShape::Box {
+                half_extents: [x, y],
+            }),
+            &ShapeDesc::Capsule {
+                half_length,
+                radius,
+            } => (half_length > 0. && radius > 0.).then_some(BodyShape::Capsule {
+                half_length,
+                radius,
+            }),
+            ShapeDesc::Polygon { vertices } => ConvexPolygon::new(vertices).map(BodyShape::Polygon),
+        }
+    }
+}
+
+impl SensorDesc {
+    ///Whether the shape has an area, with finite coordinates.
+    pub fn is_valid(&self) -> bool {
+        let finite = |v: &[f32; 2]| v[0].is_finite() && v[1].is_finite();
+        match self {
+            SensorDesc::Aabb { min, max } => {
+                finite(min) && finite(max) && min[0] < max[0] && min[1] < max[1]
+            }
+            //also false for NaN
+            SensorDesc::Circle { center, radius } => {
+                finite(center) && *radius > 0. && radius.is_finite()
+            }
+            SensorDesc::Polygon { vertices } => vertices.len() >= 3 && vertices.iter().all(finite),
+        }
+    }
+
+    pub fn to_shape(&self) -> SensorShape {
+        match self {
+            SensorDesc::Aabb { min, max } => SensorShape::Aabb {
+                min: (*min).into(),
+                max: (*max).into(),
+            },
+            SensorDesc::Circle { center, radius } => SensorShape::Circle {
+                center: (*center).into(),
+                radius: *radius,
+            },
+            SensorDesc::Polygon { vertices } => {
+                SensorShape::Polygon(vertices.iter().map(|&v| v.into()).collect())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn scene_files_are_built() {
+        let scene = Scene::from_json(include_str!("../scenes/head_on.json")).unwrap();
+        assert_eq!(scene.border(), (800., 600.));
+        let world = scene.build();
+        assert_eq!(world.balls.len(), 2);
+        assert_eq!(world.ball(1).unwrap().speed[0], -200.);
+        assert!(world.sensor(0).is_some());
+    }
+
+    #[test]
+    fn invalid_scenes_are_rejected() {
+        for size in [
+            "0, \"height\": 600",
+            "800, \"height\": -1",
+            "-5, \"height\": 0",
+        ] {
+            let json = format!("{{ \"width\": {size} }}");
+            assert!(
+                matches!(Scene::from_json(&json), Err(SceneError::InvalidSize)),
+                "{json}"
+            );
+        }
+        assert!(matches!(
+            Scene::from_json(r#"{ "width": 800 }"#),
+            Err(SceneError::Parse(_))
+        ));
+        assert!(matches!(
+            Scene::from_json(
+                r#"{ "width": 800, "height": 600, "balls": [{ "position": [1, 1] }] }"#
+            ),
+            Err(SceneError::InvalidShape(0))
+        ));
+        for sensor in [
+            r#"{ "circle": { "center": [1, 1], "radius": 0 } }"#,
+            r#"{ "aabb": { "min": [10, 10], "max": [5, 20] } }"#,
+            r#"{ "polygon": { "vertices": [[0, 0], [10, 0]] } }"#,
+        ] {
+            let json = format!(
+                r#"{{ "width": 800, "height": 600, "sensors": [{{ "circle": {{ "center": [1, 1], "radius": 5 }} }}, {sensor}] }}"#
+            );
+            assert!(
+                matches!(Scene::from_json(&json), Err(SceneError::InvalidSensor(1))),
+                "{json}"
+            );
+        }
+        assert!(matches!(
+            Scene::load("scenes/missing.json"),
+            Err(SceneError::Io(_))
+        ));
+    }
+
+    #[test]
+    fn random_scenes_depend_only_on_the_seed() {
+        let positions = |scene: &Scene| -> Vec<[f32; 2]> {
+            scene.balls.iter().map(|ball| ball.position).collect()
+        };
+        let scene = Scene::random(800., 600., 1000, 7);
+
+        assert_eq!(scene.balls.len(), 1000);
+        assert_eq!(
+            positions(&scene),
+            positions(&Scene::random(800., 600., 1000, 7))
+        );
+        assert_ne!(
+            positions(&scene),
+            positions(&Scene::random(800., 600., 1000, 8))
+        );
+        assert!(scene.balls.iter().all(|ball| {
+            (ball.size..=800. - ball.size).contains(&ball.position[0])
+                && (ball.size..=600. - ball.size).contains(&ball.position[1])
+        }));
+        assert_eq!(scene.build().balls.len(), 1000);
+    }
+}
@@ -0,0 +1,184 @@
+//! Physics statistics (energy, momentum, collisions...), used to check that the simulation stays sane.
+
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+
+use crate::{balls::Balls, physics::constants::GRAVITY_CONST};
+
+///Counters filled during one physics step (one call to `Balls::simulate`, all sub steps included).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StepStats {
+    pub collisions: usize,
+    pub max_penetration: f32,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FrameStats {
+    pub step: u64,
+    pub time: f32,
+    pub ball_count: usize,
+
+    pub kinetic_energy: f32,
+    ///Gravitational potential energy, measured from the floor.
+    pub potential_energy: f32,
+    pub momentum: [f32; 2],
+
+    pub collisions: usize,
+    pub max_penetration: f32,
+}
+
+impl FrameStats {
+    pub fn measure(balls: &Balls) -> Self {
+        let (_, floor) = balls.border();
+        let step_stats = balls.last_step_stats();
+
+        let mut stats = Self {
+            step: balls.step(),
+            time: balls.time(),
+            collisions: step_stats.collisions,
+            max_penetration: step_stats.max_penetration,
+            ..Default::default()
+        };
+
+        for ball in balls.iter() {
+            let [v_x, v_y] = *ball.speed.as_array();
+            // gravity acceleration depends on the mass, see `Body::apply_gravity`
+            let gravity = GRAVITY_CONST * ball.mass;
+            let height = (floor - ball.size - ball.position[1]).max(0.);
+
+            stats.ball_count += 1;
+            stats.kinetic_energy += 0.5 * ball.mass * (v_x * v_x + v_y * v_y);
+            stats.potential_energy += ball.mass * gravity * height;
+            stats.momentum[0] += ball.mass * v_x;
+            stats.momentum[1] += ball.mass * v_y;
+        }
+
+        stats
+    }
+
+    pub fn total_energy(&self) -> f32 {
+        self.kinetic_energy + self.potential_energy
+    }
+}
+
+///Write one line of [`FrameStats`] per physics step in a CSV file.
+pub struct StatsLogger {
+    writer: BufWriter<File>,
+}
+
+impl StatsLogger {
+    const HEADER: &str = "step,time,ball_count,kinetic_energy,potential_energy,total_energy,momentum_x,momentum_y,collisions,max_penetration";
+
+    pub fn create<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        writeln!(writer, "{}", Self::HEADER)?;
+
+        Ok(Self { writer })
+    }
+
+    pub fn log(&mut self, stats: &FrameStats) -> io::Result<()> {
+        writeln!(
+            self.writer,
+            "{},{},{},{},{},{},{},{},{},{}",
+            stats.step,
+            stats.time,
+            stats.ball_count,
+            stats.kinetic_energy,
+            stats.potential_energy,
+            stats.total_energy(),
+            stats.momentum[0],
+            stats.momentum[1],
+            stats.collisions,
+            stats.max_penetration,
+        )
+    }
+
+    ///Write the lines still buffered, dropping the logger would lose their errors.
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use super::*;
+    use crate::test_util::{BORDER, ball, run, world};
+
+    #[test]
+    fn one_ball_is_measured() {
+        let mut world = world(vec![ball(10., [400., 290.], [3., -4.], 0)]);
+        world.simulate(0., BORDER);
+        let stats = FrameStats::measure(&world);
+        let ball = world.ball(0).unwrap();
+        let height = BORDER.1 - 10. - ball.position[1];
+
+        assert_eq!(stats.ball_count, 1);
+        assert!((stats.kinetic_energy - 0.5 * 100. * 25.).abs() < 1e-2);
+        assert!((stats.momentum[0] - 300.).abs() < 1e-3);
+        assert!((stats.momentum[1] + 400.).abs() < 1e-3);
+        assert!((stats.potential_energy - 100. * GRAVITY_CONST * 100. * height).abs() < 1.);
+    }
+
+    #[test]
+    fn head_on_collision_keeps_momentum_and_loses_energy() {
+        //on the floor, so that gravity does not change the energy
+        let floor = BORDER.1 - 10.;
+        let mut world = world(vec![
+            ball(10., [300., floor], [200., 0.], 0),
+            ball(10., [400., floor], [-100., 0.], 1),
+        ]);
+        run(&mut world, 1);
+        let before = FrameStats::measure(&world);
+        let mut collided = false;
+        for _ in 0..30 {
+            run(&mut world, 1);
+            collided |= world.last_step_stats().collisions > 0;
+        }
+        let after = FrameStats::measure(&world);
+
+        assert!(collided);
+        //the drag takes a few percents
+        assert!((after.momentum[0] - before.momentum[0]).abs() < 0.05 * before.momentum[0].abs());
+        assert!(after.total_energy() < before.total_energy());
+    }
+
+    #[test]
+    fn csv_has_one_line_per_step() {
+        let path =
+            std::env::temp_dir().join(format!("bouncing_ball_stats_{}.csv", std::process::id()));
+        let mut world = world(vec![
+            ball(10., [300., 300.], [200., 0.], 0),
+            ball(10., [500., 300.], [-200., 0.], 1),
+        ]);
+        world.log_stats_to(&path).unwrap();
+        let mut expected = vec![];
+        for _ in 0..20 {
+            run(&mut world, 1);
+            expected.push(world.stats());
+        }
+        world.finish_stats_log().unwrap();
+
+        let csv = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        let mut lines = csv.lines();
+        assert!(lines.next().unwrap().starts_with("step,time,ball_count"));
+        let rows: Vec<Vec<f32>> = lines
+            .map(|line| line.split(',').map(|v| v.parse().unwrap()).collect())
+            .collect();
+        assert_eq!(rows.len(), expected.len());
+        for (row, stats) in rows.iter().zip(&expected) {
+            assert_eq!(row[0] as u64, stats.step);
+            assert_eq!(row[1], stats.time);
+            assert_eq!(row[2] as usize, stats.ball_count);
+            assert_eq!(row[3], stats.kinetic_energy);
+            assert_eq!(row[5], stats.total_energy());
+            assert_eq!([row[6], row[7]], stats.momentum);
+            assert_eq!(row[8] as usize, stats.collisions);
+        }
+    }
+}
@@ -0,0 +1,131 @@
+//! Fixtures shared by the tests of the physics and of the world : balls, worlds and a reproducible
+//! random generator.
+
+use my_glium_util::datastruct::aabb::Aabb;
+
+use crate::balls::{Balls, one_ball::Ball};
+
+pub const DT: f32 = 1. / 60.;
+pub const BORDER: (f32, f32) = (800., 600.);
+
+pub fn ball(size: f32, pos: [f32; 2], speed: [f32; 2], id: usize) -> Ball {
+    let mut ball = Ball::new(size, pos, id);
+    ball.speed = speed.into();
+    ball
+}
+
+///World of `BORDER` holding `balls`, without spawner.
+pub fn world(balls: Vec<Ball>) -> Balls {
+    let half_dim = BORDER.0.max(BORDER.1) / 2.;
+    let mut world = Balls::empty(Aabb::new((half_dim, half_dim), half_dim));
+    world.auto_spawn = false;
+    for ball in balls {
+        world.push_ball(ball);
+    }
+
+    world
+}
+
+pub fn run(world: &mut Balls, steps: u32) {
+    for _ in 0..steps {
+        world.simulate(DT, BORDER);
+    }
+}
+
+///`count` still balls of `size` in rows of `columns`, the first at `origin`, ids following the rows.
+pub fn grid(
+    count: usize,
+    columns: usize,
+    origin: [f32; 2],
+    spacing: [f32; 2],
+    size: f32,
+) -> Vec<Ball> {
+    (0..count)
+        .map(|i| {
+            let pos = [
+                origin[0] + spacing[0] * (i % columns) as f32,
+                origin[1] + spacing[1] * (i / columns) as f32,
+            ];
+            ball(size, pos, [0., 0.], i)
+        })
+        .collect()
+}
+
+///`count` balls of sizes in `sizes`, inside `min..max` and with speeds up to `max_speed` on each
+///axis.
+pub fn random_balls(
+    rng: &mut XorShift,
+    count: usize,
+    sizes: (f32, f32),
+    min: [f32; 2],
+    max: [f32; 2],
+    max_speed: f32,
+) -> Vec<Ball> {
+    (0..count)
+        .map(|id| {
+            ball(
+                rng.range(sizes.0, sizes.1),
+                [rng.range(min[0], max[0]), rng.range(min[1], max[1])],
+                [
+                    rng.range(-max_speed, max_speed),
+                    rng.range(-max_speed, max_speed),
+                ],
+                id,
+            )
+        })
+        .collect()
+}
+
+pub fn momentum<'a>(balls: impl IntoIterator<Item = &'a Ball>) -> [f32; 2] {
+    balls.into_iter().fold([0.; 2], |[p_x, p_y], ball| {
+        [
+            p_x + ball.mass * ball.speed[0],
+            p_y + ball.mass * ball.speed[1],
+        ]
+    })
+}
+
+///Small xorshift generator, so the property tests are reproducible without any dependency.
+pub struct XorShift(pub u32);
+
+impl XorShift {
+    pub fn next_f32(&mut self) -> f32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 17;
+        self.0 ^= self.0 << 5;
+        (self.0 >> 8) as f32 / (1 << 24) as f32
+    }
+
+    pub fn range(&mut self, min: f32, max: f32) -> f32 {
+        min + (max - min) * self.next_f32()
+    }
+}
+
+///40 balls of sizes 5 to 15 thrown in the top half of the world.
+pub fn random_pile() -> Balls {
+    world(random_balls(
+        &mut XorShift(7),
+        40,
+        (5., 15.),
+        [50., 50.],
+        [750., 300.],
+        200.,
+    ))
+}
+
+///Asserts that both balls have the same id and positions and speeds within `tolerance`.
+pub fn assert_close(a: &Ball, b: &Ball, tolerance: f32) {
+    assert_eq!(a.id, b.id);
+    for axis in 0..2 {
+        assert!(
+            (a.position[axis] - b.position[axis]).abs() <= tolerance
+                && (a.speed[axis] - b.speed[axis]).abs() <= tolerance,
+            "ball {} : {:?} {:?} instead of {:?} {:?}",
+            a.id,
+            a.position,
+            a.speed,
+            b.position,
+            b.speed
+        );
+    }
+}
@@ -0,0 +1,204 @@
+//! Inspector : panel showing the properties of the selected balls, the highlighted one being edited
+//! with the keyboard for every selected ball at once.
+
+use std::collections::HashSet;
+
+use crate::{
+    balls::{Balls, one_ball::Ball},
+    render::{
+        debug::DebugVertex,
+        text::{self, GLYPH_HEIGHT},
+    },
+};
+
+///Pixels per font pixel.
+const TEXT_SCALE: f32 = 2.;
+const MARGIN: f32 = 8.;
+const PADDING: f32 = 6.;
+const LINE_SPACING: f32 = 2. * TEXT_SCALE;
+///Ids listed in the title before they are elided.
+const MAX_LISTED_IDS: usize = 5;
+
+const PANEL_COLOR: [f32; 3] = [0.05; 3];
+const TEXT_COLOR: [f32; 3] = [1.; 3];
+const CURRENT_COLOR: [f32; 3] = [1., 0.8, 0.2];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Field {
+    Radius,
+    Mass,
+    Bounce,
+    VelocityX,
+    VelocityY,
+    Red,
+    Green,
+    Blue,
+    DoPhysics,
+}
+
+impl Field {
+    pub const ALL: [Field; 9] = [
+        Field::Radius,
+        Field::Mass,
+        Field::Bounce,
+        Field::VelocityX,
+        Field::VelocityY,
+        Field::Red,
+        Field::Green,
+        Field::Blue,
+        Field::DoPhysics,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Field::Radius => "RADIUS",
+            Field::Mass => "MASS",
+            Field::Bounce => "BOUNCE",
+            Field::VelocityX => "VELOCITY X",
+            Field::VelocityY => "VELOCITY Y",
+            Field::Red => "RED",
+            Field::Green => "GREEN",
+            Field::Blue => "BLUE",
+            Field::DoPhysics => "PHYSICS",
+        }
+    }
+
+    ///Value of the field, `do_physics` being 0 or 1.
+    pub fn get(self, ball: &Ball) -> f32 {
+        match self {
+            Field::Radius => ball.size,
+            Field::Mass => ball.mass,
+            Field::Bounce => ball.bounce,
+            Field::VelocityX => ball.speed[0],
+            Field::VelocityY => ball.speed[1],
+            Field::Red => ball.color[0],
+            Field::Green => ball.color[1],
+            Field::Blue => ball.color[2],
+            Field::DoPhysics => f32::from(u8::from(ball.do_physics)),
+        }
+    }
+
+    ///Change the field by `steps` steps, toggling `do_physics` whatever the number of steps.
+    pub fn edit(self, ball: &mut Ball, steps: f32) {
+        match self {
+            Field::Radius => ball.set_size((ball.size + steps).max(1.)),
+            //the mass spans orders of magnitude, it is changed by 10% per step
+            Field::Mass => ball.mass = (ball.mass * 1.1f32.powf(steps)).max(0.01),
+            Field::Bounce => ball.bounce = (ball.bounce + steps * 0.05).clamp(0., 1.),
+            Field::VelocityX => ball.speed[0] += steps * 10.,
+            Field::VelocityY => ball.speed[1] += steps * 10.,
+            Field::Red | Field::Green | Field::Blue => {
+                let channel = self as usize - Field::Red as usize;
+                ball.color[channel] = (ball.color[channel] + steps * 0.05).clamp(0., 1.);
+                ball.fixed_color = true;
+            }
+            Field::DoPhysics => ball.do_physics = !ball.do_physics,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct Inspector {
+    ///Index in `Field::ALL` of the field being edited.
+    current: usize,
+}
+
+impl Inspector {
+    pub fn field(&self) -> Field {
+        Field::ALL[self.current]
+    }
+
+    pub fn next_field(&mut self) {
+        self.current = (self.current + 1) % Field::ALL.len();
+    }
+
+    pub fn previous_field(&mut self) {
+        self.current = (self.current + Field::ALL.len() - 1) % Field::ALL.len();
+    }
+
+    ///Edit the current field of every ball of `ids`. `do_physics` is set to the same value on every
+    ///ball : on if any of them is off, else off.
+    pub fn edit(&self, balls: &mut Balls, ids: &HashSet<usize>, steps: f32) {
+        let field = self.field();
+        if field == Field::DoPhysics {
+            let on = !balls.balls_with_ids(ids).iter().all(|ball| ball.do_physics);
+            balls.edit_balls(ids, |ball| ball.do_physics = on);
+        } else {
+            balls.edit_balls(ids, |ball| field.edit(ball, steps));
+        }
+    }
+
+    ///Title then one line per field, a field differing between the balls shows its range.
+    pub fn text(&self, selected: &[&Ball]) -> Vec<String> {
+        let listed: Vec<String> = selected
+            .iter()
+            .take(MAX_LISTED_IDS)
+            .map(|ball| ball.id.to_string())
+            .collect();
+        let elided = if selected.len() > MAX_LISTED_IDS {
+            " ..."
+        } else {
+            ""
+        };
+        let mut lines = vec![format!(
+            "SELECTED {}: {}{elided}",
+            selected.len(),
+            listed.join(" ")
+        )];
+
+        for field in Field::ALL {
+            let (min, max) = selected
+                .iter()
+                .map(|ball| field.get(ball))
+                .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), v| {
+                    (min.min(v), max.max(v))
+                });
+            let value = match field {
+                Field::DoPhysics if min == max => if min > 0. { "ON" } else { "OFF" }.to_owned(),
+                Field::DoPhysics => "MIXED".to_owned(),
+                _ if min == max => format!("{min:.2}"),
+                _ => format!("{min:.2} TO {max:.2}"),
+            };
+            lines.push(format!("{} {value}", field.name()));
+        }
+
+        lines
+    }
+
+    ///Triangles of the panel, in the top right corner of a window of `viewport` pixels. Nothing
+    ///is drawn without selection.
+    pub fn mesh(&self, selected: &[&Ball], viewport: [f32; 2]) -> Vec<DebugVertex> {
+        let mut vertices = vec![];
+        if selected.is_empty() {
+            return vertices;
+        }
+
+        let lines = self.text(selected);
+        let line_height = GLYPH_HEIGHT as f32 * TEXT_SCALE + LINE_SPACING;
+        let text_width = lines
+            .iter()
+            .map(|line| text::text_size(line, TEXT_SCALE)[0])
+            .fold(0., f32::max);
+
+        let panel_min = [viewport[0] - MARGIN - 2. * PADDING - text_width, MARGIN];
+        let panel_max = [
+            viewport[0] - MARGIN,
+            MARGIN + 2. * PADDING + lines.len() as f32 * line_height,
+        ];
+        text::push_rect(&mut vertices, panel_min, panel_max, PANEL_COLOR);
+
+        let left = panel_min[0] + PADDING;
+        for (i, line) in lines.iter().enumerate() {
+            let top = MARGIN + PADDING + i as f32 * line_height;
+            //the first line is the title
+            let color = if i == self.current + 1 {
+                CURRENT_COLOR
+            } else {
+                TEXT_COLOR
+            };
+            text::push_text(&mut vertices, line, [left, top], TEXT_SCALE, color);
+        }
+
+        vertices
+    }
+}
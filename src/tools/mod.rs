@@ -0,0 +1,432 @@
+//! Mouse tools : what a click and a drag do to the balls. Every position is in world coordinates and
+//! every time in seconds.
+
+use std::collections::{HashSet, VecDeque};
+
+use my_glium_util::math::Vec2;
+
+use crate::{
+    balls::{Balls, MouseSpring, one_ball::Ball, soft::SoftKind},
+    physics::geometry::{BodyShape, ConvexPolygon},
+    render::debug::DebugLines,
+};
+
+pub mod inspector;
+
+#[cfg(test)]
+mod test;
+
+///Spring of the grab tool, critically damped.
+const SPRING_STIFFNESS: f32 = 400.;
+const SPRING_DAMPING: f32 = 40.;
+///The cursor velocity is measured over this duration, in seconds.
+const CURSOR_VELOCITY_WINDOW: f32 = 0.1;
+///Initial speed of a spawned ball per unit of drag.
+const SPAWN_SPEED_PER_DRAG: f32 = 2.;
+
+const SPRING_COLOR: [f32; 3] = [1., 1., 0.];
+const SELECTION_COLOR: [f32; 3] = [0.2, 0.8, 1.];
+const SPAWN_COLOR: [f32; 3] = [0.2, 1., 0.2];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Tool {
+    ///Pull the ball with a spring, it still collides with the others.
+    Grab,
+    ///Carry the ball, it is thrown with the cursor velocity when released.
+    Throw,
+    ///Remove the balls under the cursor.
+    Delete,
+    ///Add a ball, dragging gives it an initial speed.
+    Spawn,
+    ///Select a ball or the balls in a box, then drag the selection to move them. With shift, a
+    ///click toggles a ball and a box adds to the selection.
+    Select,
+    ///Add a soft body filling the dragged box.
+    Soft,
+}
+
+impl Tool {
+    ///Tools in the order of their number key, starting at 1.
+    pub const ALL: [Tool; 6] = [
+        Tool::Grab,
+        Tool::Throw,
+        Tool::Delete,
+        Tool::Spawn,
+        Tool::Select,
+        Tool::Soft,
+    ];
+
+    pub fn from_number(number: usize) -> Option<Tool> {
+        Self::ALL.get(number.checked_sub(1)?).copied()
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Tool::Grab => "grab",
+            Tool::Throw => "throw",
+            Tool::Delete => "delete",
+            Tool::Spawn => "spawn",
+            Tool::Select => "select",
+            Tool::Soft => "soft",
+        }
+    }
+}
+
+///Shapes of the bodies added by the spawn tool, all scaled by its size.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SpawnShape {
+    #[default]
+    Circle,
+    Crate,
+    ///A long and thin box.
+    Plank,
+    Capsule,
+    Hexagon,
+}
+
+impl SpawnShape {
+    pub const ALL: [SpawnShape; 5] = [
+        SpawnShape::Circle,
+        SpawnShape::Crate,
+        SpawnShape::Plank,
+        SpawnShape::Capsule,
+        SpawnShape::Hexagon,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            SpawnShape::Circle => "circle",
+            SpawnShape::Crate => "crate",
+            SpawnShape::Plank => "plank",
+            SpawnShape::Capsule => "capsule",
+            SpawnShape::Hexagon => "hexagon",
+        }
+    }
+
+    ///The shape after this one, back to the first after the last.
+    pub fn next(self) -> Self {
+        let index = Self::ALL
+            .iter()
+            .position(|&shape| shape == self)
+            .unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
+    pub fn body_shape(self, size: f32) -> BodyShape {
+        match self {
+            SpawnShape::Circle => BodyShape::Circle,
+            SpawnShape::Crate => BodyShape::Box {
+                half_extents: [size; 2],
+            },
+            SpawnShape::Plank => BodyShape::Box {
+                half_extents: [size * 3., size / 3.],
+            },
+            SpawnShape::Capsule => BodyShape::Capsule {
+                half_length: size,
+                radius: size / 2.,
+            },
+            SpawnShape::Hexagon => {
+                ConvexPolygon::regular(6, size).map_or(BodyShape::Circle, BodyShape::Polygon)
+            }
+        }
+    }
+}
+
+///Balls added by the spawn tool.
+#[derive(Clone, Copy, Debug)]
+pub struct SpawnParams {
+    pub size: f32,
+    pub shape: SpawnShape,
+    ///Defaults to `size * size`, or the area of the shape over pi.
+    pub mass: Option<f32>,
+    pub bounce: f32,
+}
+
+impl Default for SpawnParams {
+    fn default() -> Self {
+        Self {
+            size: 10.,
+            shape: SpawnShape::Circle,
+            mass: None,
+            bounce: 0.3,
+        }
+    }
+}
+
+///What the pressed mouse button is doing.
+#[derive(Clone, Copy, Debug)]
+enum Held {
+    Spring,
+    ///`do_physics` is the one of the ball before it was picked, restored on release.
+    Throw {
+        id: usize,
+        offset: [f32; 2],
+        do_physics: bool,
+    },
+    Delete,
+    Spawn {
+        start: [f32; 2],
+    },
+    SelectBox {
+        start: [f32; 2],
+        additive: bool,
+    },
+    MoveSelection,
+    Soft {
+        start: [f32; 2],
+    },
+}
+
+pub struct Tools {
+    pub tool: Tool,
+    pub spawn: SpawnParams,
+    ///Kind of the bodies added by the soft body tool, made of balls of half the spawn size.
+    pub soft: SoftKind,
+    ///Shift is held, the select tool adds to the selection.
+    pub additive: bool,
+
+    cursor: [f32; 2],
+    ///Recent cursor positions and their time, the oldest first.
+    cursor_history: VecDeque<(f32, [f32; 2])>,
+    held: Option<Held>,
+    selection: HashSet<usize>,
+}
+
+impl Default for Tools {
+    fn default() -> Self {
+        Self {
+            tool: Tool::Grab,
+            spawn: SpawnParams::default(),
+            soft: SoftKind::default(),
+            additive: false,
+
+            cursor: [0.; 2],
+            cursor_history: VecDeque::new(),
+            held: None,
+            selection: HashSet::new(),
+        }
+    }
+}
+
+impl Tools {
+    ///Change the tool, letting go of what the current one holds.
+    pub fn set_tool(&mut self, tool: Tool, balls: &mut Balls, time: f32) {
+        if tool != self.tool {
+            self.release(balls, time);
+            self.tool = tool;
+        }
+    }
+
+    pub fn selection(&self) -> &HashSet<usize> {
+        &self.selection
+    }
+
+    ///Selected balls sorted by id, without the ones removed since.
+    pub fn selected_balls<'a>(&self, balls: &'a Balls) -> Vec<&'a Ball> {
+        balls.balls_with_ids(&self.selection)
+    }
+
+    ///Ids of the selected balls sorted, without the ones removed since.
+    pub fn selected_ids(&self, balls: &Balls) -> Vec<usize> {
+        self.selected_balls(balls)
+            .into_iter()
+            .map(|ball| ball.id)
+            .collect()
+    }
+
+    pub fn clear_selection(&mut self) {
+        self.selection.clear();
+    }
+
+    ///Cursor velocity over the last `CURSOR_VELOCITY_WINDOW` seconds.
+    pub fn cursor_velocity(&self) -> [f32; 2] {
+        let (Some((t_0, p_0)), Some((t_1, p_1))) =
+            (self.cursor_history.front(), self.cursor_history.back())
+        else {
+            return [0.; 2];
+        };
+        let duration = t_1 - t_0;
+        if duration <= f32::EPSILON {
+            return [0.; 2];
+        }
+
+        [(p_1[0] - p_0[0]) / duration, (p_1[1] - p_0[1]) / duration]
+    }
+
+    pub fn press(&mut self, balls: &mut Balls, pos: [f32; 2], time: f32) {
+        self.release(balls, time);
+        self.move_cursor(pos, time);
+        let under_cursor = balls.ball_at(pos);
+
+        self.held = match self.tool {
+            Tool::Grab => under_cursor.map(|id| {
+                balls.set_mouse_spring(Some(MouseSpring {
+                    id,
+                    target: pos,
+                    stiffness: SPRING_STIFFNESS,
+                    damping: SPRING_DAMPING,
+                }));
+                Held::Spring
+            }),
+            Tool::Throw => under_cursor.and_then(|id| {
+                let ball = balls.ball_mut(id)?;
+                ball.wake_up();
+                let do_physics = ball.do_physics;
+                ball.do_physics = false;
+                ball.speed = [0.; 2].into();
+                let offset = [ball.position[0] - pos[0], ball.position[1] - pos[1]];
+                Some(Held::Throw {
+                    id,
+                    offset,
+                    do_physics,
+                })
+            }),
+            Tool::Delete => {
+                let ids = balls.balls_at(pos);
+                balls.remove_balls(&ids);
+                Some(Held::Delete)
+            }
+            Tool::Spawn => Some(Held::Spawn { start: pos }),
+            Tool::Select => match under_cursor {
+                Some(id) if self.additive => {
+                    if !self.selection.remove(&id) {
+                        self.selection.insert(id);
+                    }
+                    None
+                }
+                Some(id) => {
+                    if !self.selection.contains(&id) {
+                        self.selection = HashSet::from([id]);
+                    }
+                    Some(Held::MoveSelection)
+                }
+                None => Some(Held::SelectBox {
+                    start: pos,
+                    additive: self.additive,
+                }),
+            },
+            Tool::Soft => Some(Held::Soft { start: pos }),
+        };
+    }
+
+    pub fn cursor_moved(&mut self, balls: &mut Balls, pos: [f32; 2], time: f32) {
+        let old = self.cursor;
+        self.move_cursor(pos, time);
+
+        match self.held {
+            Some(Held::Spring) => {
+                if let Some(spring) = balls.mouse_spring() {
+                    balls.set_mouse_spring(Some(MouseSpring {
+                        target: pos,
+                        ..spring
+                    }));
+                }
+            }
+            Some(Held::Throw { id, offset, .. }) => {
+                if let Some(ball) = balls.ball_mut(id) {
+                    ball.position = [pos[0] + offset[0], pos[1] + offset[1]].into();
+                    ball.coliding_pos = ball.position;
+                }
+            }
+            Some(Held::Delete) => {
+                let ids = balls.balls_at(pos);
+                balls.remove_balls(&ids);
+            }
+            Some(Held::MoveSelection) => {
+                let delta = Vec2::from([pos[0] - old[0], pos[1] - old[1]]);
+                balls.edit_balls(&self.selection, |ball| {
+                    ball.position += delta;
+                    ball.coliding_pos = ball.position;
+                    ball.speed = [0.; 2].into();
+                });
+            }
+            Some(Held::Spawn { .. })
+            | Some(Held::SelectBox { .. })
+            | Some(Held::Soft { .. })
+            | None => (),
+        }
+    }
+
+    ///Release the mouse button, `time` being the one of the release so a cursor held still throws nothing.
+    pub fn release(&mut self, balls: &mut Balls, time: f32) {
+        self.move_cursor(self.cursor, time);
+        match self.held.take() {
+            Some(Held::Spring) => balls.set_mouse_spring(None),
+            Some(Held::Throw { id, do_physics, .. }) => {
+                let velocity = self.cursor_velocity();
+                if let Some(ball) = balls.ball_mut(id) {
+                    //a pinned ball is only moved, it stays pinned where it is dropped
+                    ball.do_physics = do_physics;
+                    if do_physics {
+                        ball.speed = velocity.into();
+                    }
+                }
+            }
+            Some(Held::Spawn { start }) => {
+                let id = balls.next_id();
+                let mut ball = Ball::new(self.spawn.size, start, id)
+                    .with_shape(self.spawn.shape.body_shape(self.spawn.size));
+                ball.mass = self.spawn.mass.unwrap_or(ball.mass);
+                ball.bounce = self.spawn.bounce;
+                ball.speed = [
+                    (self.cursor[0] - start[0]) * SPAWN_SPEED_PER_DRAG,
+                    (self.cursor[1] - start[1]) * SPAWN_SPEED_PER_DRAG,
+                ]
+                .into();
+                balls.push_ball(ball);
+            }
+            Some(Held::SelectBox { start, additive }) => {
+                if !additive {
+                    self.selection.clear();
+                }
+                self.selection.extend(balls.balls_in(start, self.cursor));
+            }
+            Some(Held::Soft { start }) => {
+                balls.add_soft_body(self.soft, start, self.cursor, self.spawn.size / 2.);
+            }
+            Some(Held::Delete) | Some(Held::MoveSelection) | None => (),
+        }
+    }
+
+    fn move_cursor(&mut self, pos: [f32; 2], time: f32) {
+        self.cursor = pos;
+        self.cursor_history.push_back((time, pos));
+        while self
+            .cursor_history
+            .front()
+            .is_some_and(|(t, _)| time - t > CURSOR_VELOCITY_WINDOW)
+        {
+            self.cursor_history.pop_front();
+        }
+    }
+
+    ///Lines showing what the tool does : the spring, the selection and the box being drawn.
+    pub fn overlay(&self, balls: &Balls, lines: &mut DebugLines) {
+        for ball in self.selected_balls(balls) {
+            let half = Vec2::from([ball.size, ball.size]);
+            lines.rect(ball.position - half, ball.position + half, SELECTION_COLOR);
+        }
+
+        match self.held {
+            Some(Held::Spring) => {
+                if let Some(ball) = balls.mouse_spring().and_then(|s| balls.ball(s.id)) {
+                    lines.line(ball.position, self.cursor.into(), SPRING_COLOR);
+                }
+            }
+            Some(Held::Spawn { start }) => {
+                lines.line(start.into(), self.cursor.into(), SPAWN_COLOR);
+            }
+            Some(Held::Soft { start }) => {
+                let min = [start[0].min(self.cursor[0]), start[1].min(self.cursor[1])];
+                let max = [start[0].max(self.cursor[0]), start[1].max(self.cursor[1])];
+                lines.rect(min.into(), max.into(), SPAWN_COLOR);
+            }
+            Some(Held::SelectBox { start, .. }) => {
+                let min = [start[0].min(self.cursor[0]), start[1].min(self.cursor[1])];
+                let max = [start[0].max(self.cursor[0]), start[1].max(self.cursor[1])];
+                lines.rect(min.into(), max.into(), SELECTION_COLOR);
+            }
+            Some(Held::Throw { .. }) | Some(Held::Delete) | Some(Held::MoveSelection) | None => (),
+        }
+    }
+}
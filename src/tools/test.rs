@@ -0,0 +1,295 @@
+#![cfg(test)]
+
+use std::collections::HashSet;
+
+use super::{
+    CURSOR_VELOCITY_WINDOW, SpawnParams, SpawnShape, Tool, Tools,
+    inspector::{Field, Inspector},
+};
+use crate::{
+    balls::{Balls, one_ball::Ball, soft::SoftKind},
+    physics::events::PhysicsEvent,
+};
+
+const DT: f32 = 1. / 60.;
+const WORLD: (f32, f32) = (400., 300.);
+
+fn world(positions: &[[f32; 2]]) -> Balls {
+    let mut balls = Balls::empty(Balls::boundary_for(WORLD));
+    balls.auto_spawn = false;
+    for &pos in positions {
+        let id = balls.next_id();
+        balls.push_ball(Ball::new(10., pos, id));
+    }
+
+    balls
+}
+
+fn tools(tool: Tool) -> Tools {
+    Tools {
+        tool,
+        ..Tools::default()
+    }
+}
+
+fn position(balls: &Balls, id: usize) -> [f32; 2] {
+    *balls.ball(id).expect("the ball exists").position.as_array()
+}
+
+#[test]
+fn number_keys_select_the_tools_in_order() {
+    assert_eq!(Tool::from_number(1), Some(Tool::Grab));
+    assert_eq!(Tool::from_number(5), Some(Tool::Select));
+    assert_eq!(Tool::from_number(6), Some(Tool::Soft));
+    assert_eq!(Tool::from_number(0), None);
+    assert_eq!(Tool::from_number(7), None);
+}
+
+#[test]
+fn grab_pulls_the_ball_without_teleporting_it() {
+    let mut balls = world(&[[100., 150.]]);
+    let mut tools = tools(Tool::Grab);
+
+    tools.press(&mut balls, [100., 150.], 0.);
+    tools.cursor_moved(&mut balls, [300., 150.], 0.1);
+    balls.simulate(DT, WORLD);
+    let after_one_step = position(&balls, 0)[0];
+    assert!(
+        after_one_step > 100. && after_one_step < 150.,
+        "the ball jumped to {after_one_step}"
+    );
+
+    for _ in 0..300 {
+        balls.simulate(DT, WORLD);
+    }
+    let pos = position(&balls, 0);
+    assert!(
+        (pos[0] - 300.).abs() < 10.,
+        "the ball did not follow the cursor : {pos:?}"
+    );
+    assert!(!balls.ball(0).unwrap().sleeping);
+
+    tools.release(&mut balls, 0.1);
+    assert!(balls.mouse_spring().is_none());
+}
+
+#[test]
+fn throw_uses_the_cursor_velocity() {
+    let mut balls = world(&[[100., 150.]]);
+    let mut tools = tools(Tool::Throw);
+
+    tools.press(&mut balls, [100., 150.], 0.);
+    for i in 1..=10 {
+        let t = i as f32 * 0.01;
+        tools.cursor_moved(&mut balls, [100. + 500. * t, 150.], t);
+    }
+    assert_eq!(position(&balls, 0), [150., 150.]);
+
+    tools.release(&mut balls, 0.1);
+    let ball = balls.ball(0).unwrap();
+    assert!(ball.do_physics);
+    assert!(
+        (ball.speed[0] - 500.).abs() < 1. && ball.speed[1].abs() < 1.,
+        "thrown at {:?}",
+        ball.speed
+    );
+}
+
+#[test]
+fn thrown_pinned_ball_stays_pinned() {
+    let mut balls = world(&[[100., 150.]]);
+    balls.edit_ball(0, |ball| ball.do_physics = false);
+    let mut tools = tools(Tool::Throw);
+
+    tools.press(&mut balls, [100., 150.], 0.);
+    tools.cursor_moved(&mut balls, [150., 150.], 0.05);
+    tools.release(&mut balls, 0.05);
+
+    let ball = balls.ball(0).unwrap();
+    assert!(!ball.do_physics);
+    assert_eq!(*ball.speed.as_array(), [0.; 2]);
+    balls.simulate(DT, WORLD);
+    assert_eq!(position(&balls, 0), [150., 150.]);
+}
+
+#[test]
+fn throw_after_holding_still_does_not_move_the_ball() {
+    let mut balls = world(&[[100., 150.]]);
+    let mut tools = tools(Tool::Throw);
+
+    tools.press(&mut balls, [100., 150.], 0.);
+    tools.cursor_moved(&mut balls, [150., 150.], 0.05);
+    tools.release(&mut balls, 0.05 + 2. * CURSOR_VELOCITY_WINDOW);
+
+    let ball = balls.ball(0).unwrap();
+    assert!(ball.do_physics);
+    assert_eq!(*ball.speed.as_array(), [0.; 2]);
+}
+
+#[test]
+fn delete_removes_the_balls_under_the_cursor() {
+    let mut balls = world(&[[100., 150.], [200., 150.], [300., 150.]]);
+    let mut tools = tools(Tool::Delete);
+
+    tools.press(&mut balls, [100., 150.], 0.);
+    tools.cursor_moved(&mut balls, [200., 150.], 0.1);
+    tools.release(&mut balls, 0.1);
+
+    let ids: Vec<usize> = balls.iter().map(|ball| ball.id).collect();
+    assert_eq!(ids, vec![2]);
+}
+
+#[test]
+fn delete_removes_every_overlapping_ball_under_the_cursor() {
+    let mut balls = world(&[[100., 150.], [105., 150.], [300., 150.]]);
+    let mut tools = tools(Tool::Delete);
+    balls.events().drain();
+
+    tools.press(&mut balls, [102., 150.], 0.);
+
+    assert_eq!(
+        balls.events().drain(),
+        [
+            PhysicsEvent::Removed { id: 0 },
+            PhysicsEvent::Removed { id: 1 }
+        ]
+    );
+    let ids: Vec<usize> = balls.iter().map(|ball| ball.id).collect();
+    assert_eq!(ids, vec![2]);
+}
+
+#[test]
+fn spawn_uses_the_parameters_and_the_drag() {
+    let mut balls = world(&[]);
+    let mut tools = tools(Tool::Spawn);
+    tools.spawn = SpawnParams {
+        size: 20.,
+        shape: SpawnShape::Circle,
+        mass: Some(5.),
+        bounce: 0.9,
+    };
+
+    tools.press(&mut balls, [100., 100.], 0.);
+    tools.cursor_moved(&mut balls, [110., 90.], 0.1);
+    tools.release(&mut balls, 0.1);
+
+    let ball = balls.iter().next().expect("a ball was spawned");
+    assert_eq!(*ball.position.as_array(), [100., 100.]);
+    assert_eq!(ball.size, 20.);
+    assert_eq!(ball.mass, 5.);
+    assert_eq!(ball.bounce, 0.9);
+    assert!(ball.speed[0] > 0. && ball.speed[1] < 0.);
+}
+
+#[test]
+fn soft_tool_fills_the_dragged_box() {
+    let mut balls = world(&[]);
+    let mut tools = tools(Tool::Soft);
+    tools.soft = SoftKind::Cloth;
+
+    tools.press(&mut balls, [150., 150.], 0.);
+    tools.cursor_moved(&mut balls, [100., 100.], 0.1);
+    tools.release(&mut balls, 0.1);
+
+    //balls of half the spawn size, 12.5 apart
+    assert_eq!(balls.balls.len(), 25);
+    assert!(balls.iter().all(|ball| ball.size == 5.));
+    let body = &balls.soft_bodies()[0];
+    assert_eq!(body.kind, SoftKind::Cloth);
+    assert_eq!(body.particles.len(), 25);
+}
+
+#[test]
+fn box_selection_moves_every_selected_ball() {
+    let mut balls = world(&[[100., 100.], [120., 110.], [300., 200.]]);
+    let mut tools = tools(Tool::Select);
+
+    tools.press(&mut balls, [50., 50.], 0.);
+    tools.cursor_moved(&mut balls, [150., 150.], 0.1);
+    tools.release(&mut balls, 0.1);
+    assert_eq!(tools.selection().len(), 2);
+    assert!(tools.selection().contains(&0) && tools.selection().contains(&1));
+
+    tools.press(&mut balls, [100., 100.], 0.2);
+    tools.cursor_moved(&mut balls, [130., 140.], 0.3);
+    tools.release(&mut balls, 0.3);
+
+    assert_eq!(position(&balls, 0), [130., 140.]);
+    assert_eq!(position(&balls, 1), [150., 150.]);
+    assert_eq!(position(&balls, 2), [300., 200.]);
+}
+
+#[test]
+fn click_selects_and_shift_click_toggles() {
+    let mut balls = world(&[[100., 100.], [200., 100.], [300., 100.]]);
+    let mut tools = tools(Tool::Select);
+    fn click(tools: &mut Tools, balls: &mut Balls, pos: [f32; 2], additive: bool) {
+        tools.additive = additive;
+        tools.press(balls, pos, 0.);
+        tools.release(balls, 0.);
+    }
+
+    click(&mut tools, &mut balls, [100., 100.], false);
+    assert_eq!(tools.selected_ids(&balls), vec![0]);
+    click(&mut tools, &mut balls, [200., 100.], false);
+    assert_eq!(tools.selected_ids(&balls), vec![1]);
+    click(&mut tools, &mut balls, [300., 100.], true);
+    assert_eq!(tools.selected_ids(&balls), vec![1, 2]);
+    click(&mut tools, &mut balls, [200., 100.], true);
+    assert_eq!(tools.selected_ids(&balls), vec![2]);
+
+    //shift box adds, a click in the void clears
+    tools.additive = true;
+    tools.press(&mut balls, [50., 50.], 0.);
+    tools.cursor_moved(&mut balls, [150., 150.], 0.1);
+    tools.release(&mut balls, 0.1);
+    assert_eq!(tools.selected_ids(&balls), vec![0, 2]);
+    click(&mut tools, &mut balls, [200., 250.], false);
+    assert!(tools.selected_ids(&balls).is_empty());
+}
+
+#[test]
+fn inspector_edits_every_selected_ball() {
+    let mut balls = world(&[[100., 100.], [200., 100.], [300., 100.]]);
+    let mut inspector = Inspector::default();
+    assert_eq!(inspector.field(), Field::Radius);
+
+    inspector.edit(&mut balls, &HashSet::from([0, 1]), 5.);
+    let sizes: Vec<f32> = (0..3).map(|id| balls.ball(id).unwrap().size).collect();
+    assert_eq!(sizes, vec![15., 15., 10.]);
+    //the grown balls can be picked on their whole radius
+    assert_eq!(balls.ball_at([114., 100.]), Some(0));
+
+    inspector.previous_field();
+    assert_eq!(inspector.field(), Field::DoPhysics);
+    inspector.edit(&mut balls, &HashSet::from([1]), 1.);
+    assert!(!balls.ball(1).unwrap().do_physics);
+
+    let text = inspector.text(&balls.balls_with_ids(&HashSet::from([0, 1])));
+    assert_eq!(text[0], "SELECTED 2: 0 1");
+    assert!(text.contains(&"RADIUS 15.00".to_owned()));
+    assert!(text.contains(&"PHYSICS MIXED".to_owned()));
+    assert!(text.contains(&"BOUNCE 0.30".to_owned()));
+
+    //a mixed selection is turned on as a whole, then off
+    inspector.edit(&mut balls, &HashSet::from([0, 1]), 1.);
+    assert!(balls.ball(0).unwrap().do_physics && balls.ball(1).unwrap().do_physics);
+    inspector.edit(&mut balls, &HashSet::from([0, 1]), 1.);
+    assert!(!balls.ball(0).unwrap().do_physics && !balls.ball(1).unwrap().do_physics);
+}
+
+#[test]
+fn edited_color_is_kept_by_the_simulation() {
+    let mut balls = world(&[[100., 100.]]);
+    let mut inspector = Inspector::default();
+    while inspector.field() != Field::Red {
+        inspector.next_field();
+    }
+
+    inspector.edit(&mut balls, &HashSet::from([0]), -100.);
+    balls.simulate(DT, WORLD);
+
+    let ball = balls.ball(0).unwrap();
+    assert!(ball.fixed_color);
+    assert_eq!(ball.color[0], 0.);
+}
@@ -0,0 +1,170 @@
+//! Export of the balls trajectories, one row per ball and per recorded step.
+
+use std::{io::Write, str::FromStr};
+
+use serde::Serialize;
+
+use crate::balls::Balls;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrajectoryFormat {
+    Csv,
+    ///One JSON object per line.
+    JsonLines,
+}
+
+impl FromStr for TrajectoryFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "csv" => Ok(Self::Csv),
+            "jsonl" | "json-lines" => Ok(Self::JsonLines),
+            _ => Err(format!(
+                "unknown trajectory format {s:?}, expected csv or jsonl"
+            )),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct TrajectoryPoint {
+    step: u64,
+    time: f32,
+    id: usize,
+    x: f32,
+    y: f32,
+    speed_x: f32,
+    speed_y: f32,
+}
+
+pub struct TrajectoryWriter<W: Write> {
+    writer: W,
+    format: TrajectoryFormat,
+}
+
+impl<W: Write> TrajectoryWriter<W> {
+    pub fn new(mut writer: W, format: TrajectoryFormat) -> std::io::Result<Self> {
+        if format == TrajectoryFormat::Csv {
+            writeln!(writer, "step,time,id,x,y,speed_x,speed_y")?;
+        }
+
+        Ok(Self { writer, format })
+    }
+
+    ///Write the current state of every ball, sorted by id.
+    pub fn write_step(&mut self, balls: &Balls) -> std::io::Result<()> {
+        let mut points: Vec<TrajectoryPoint> = balls
+            .iter()
+            .map(|ball| TrajectoryPoint {
+                step: balls.step(),
+                time: balls.time(),
+                id: ball.id,
+                x: ball.position[0],
+                y: ball.position[1],
+                speed_x: ball.speed[0],
+                speed_y: ball.speed[1],
+            })
+            .collect();
+        points.sort_by_key(|p| p.id);
+
+        for p in points {
+            match self.format {
+                TrajectoryFormat::Csv => writeln!(
+                    self.writer,
+                    "{},{},{},{},{},{},{}",
+                    p.step, p.time, p.id, p.x, p.y, p.speed_x, p.speed_y
+                )?,
+                TrajectoryFormat::JsonLines => {
+                    serde_json::to_writer(&mut self.writer, &p)?;
+                    writeln!(self.writer)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> std::io::Result<()> {
+        self.writer.flush()
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{scene::Scene, test_util::run};
+
+    fn head_on() -> Balls {
+        Scene::from_json(include_str!("../scenes/head_on.json"))
+            .unwrap()
+            .build()
+    }
+
+    #[test]
+    fn csv_trajectories_have_one_row_per_ball_and_step() {
+        let mut world = head_on();
+        let mut writer = TrajectoryWriter::new(vec![], TrajectoryFormat::Csv).unwrap();
+        for _ in 0..3 {
+            run(&mut world, 1);
+            writer.write_step(&world).unwrap();
+        }
+        writer.flush().unwrap();
+        let csv = String::from_utf8(writer.into_inner()).unwrap();
+
+        let mut lines = csv.lines();
+        assert_eq!(lines.next(), Some("step,time,id,x,y,speed_x,speed_y"));
+        let rows: Vec<Vec<f32>> = lines
+            .map(|line| line.split(',').map(|v| v.parse().unwrap()).collect())
+            .collect();
+        assert_eq!(rows.len(), 6);
+        assert_eq!(
+            rows.iter().map(|row| [row[0], row[2]]).collect::<Vec<_>>(),
+            [[1., 0.], [1., 1.], [2., 0.], [2., 1.], [3., 0.], [3., 1.]]
+        );
+        let last = world.ball(1).unwrap();
+        assert_eq!(
+            rows[5][3..],
+            [
+                last.position[0],
+                last.position[1],
+                last.speed[0],
+                last.speed[1]
+            ]
+        );
+    }
+
+    #[test]
+    fn jsonl_trajectories_have_one_object_per_line() {
+        let mut world = head_on();
+        let mut writer = TrajectoryWriter::new(vec![], "jsonl".parse().unwrap()).unwrap();
+        run(&mut world, 1);
+        writer.write_step(&world).unwrap();
+        let jsonl = String::from_utf8(writer.into_inner()).unwrap();
+
+        let points: Vec<serde_json::Value> = jsonl
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(points.len(), 2);
+        for (id, point) in points.iter().enumerate() {
+            let ball = world.ball(id).unwrap();
+            assert_eq!(point["step"], 1);
+            assert_eq!(point["id"], id);
+            //written as the shortest decimal giving back the same f32
+            assert_eq!(
+                point["x"].as_f64().map(|x| x as f32),
+                Some(ball.position[0])
+            );
+            assert_eq!(
+                point["speed_y"].as_f64().map(|s| s as f32),
+                Some(ball.speed[1])
+            );
+        }
+        assert!("xml".parse::<TrajectoryFormat>().is_err());
+    }
+}